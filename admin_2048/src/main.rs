@@ -235,8 +235,15 @@ async fn get_top_game(
                 games_played += output.records.len();
 
                 for record in &output.records {
-                    let game: types_2048::blue::_2048::game::RecordData =
-                        types_2048::blue::_2048::game::RecordData::from(record.value.clone());
+                    let game = match types_2048::blue::_2048::game::RecordData::try_from(
+                        record.value.clone(),
+                    ) {
+                        Ok(game) => game,
+                        Err(err) => {
+                            log::error!("Skipping malformed game {}: {}", record.uri, err);
+                            continue;
+                        }
+                    };
                     match parse_game_and_validate(&game.seeded_recording) {
                         Ok(real_score) => {
                             if real_score > top_score {
@@ -0,0 +1,248 @@
+use crate::AppViewContext;
+use crate::index::IndexedGame;
+use crate::leaderboard::GamePath;
+use dropshot::{Body, HttpError, Path, RequestContext, endpoint};
+use http::{Response, StatusCode, header};
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::{Options, Tree, fontdb};
+use std::sync::Arc;
+use twothousand_forty_eight::board::Board;
+use twothousand_forty_eight::unified::game::GameState;
+use twothousand_forty_eight::v2::recording::SeededRecording;
+use types_2048::board_card::{
+    BACKGROUND_COLOR, BOARD_COLOR, BOARD_RADIUS, CARD_HEIGHT, CARD_PADDING, CARD_WIDTH,
+    DETAILS_TEXT, FONT_FAMILY, FOOTER_COLOR, FOOTER_TEXT, MODE_TEXT, NAME_TEXT, SCORE_LABEL_TEXT,
+    SCORE_TEXT, TEXT_COLOR, TILE_RADIUS, TITLE_TEXT, TileLayout, board_size, text_x, tile_colors,
+};
+use types_2048::game_mode::GameMode;
+
+/// Where the client is hosted, link previews need absolute urls
+const SITE_URL: &str = "https://2048.blue";
+/// Games do not change once indexed so previews can be cached for a while
+const CARD_CACHE_SECONDS: usize = 3600;
+
+/// An image of the game's final board and score, used as the preview when a game link is shared.
+/// A png since Bluesky and most other link unfurlers do not show svg images
+#[endpoint {
+    method = GET,
+    path = "/api/game/{did}/{rkey}/card.png",
+}]
+pub async fn get_game_card(
+    request_context: RequestContext<AppViewContext>,
+    path: Path<GamePath>,
+) -> Result<Response<Body>, HttpError> {
+    let GamePath { did, rkey } = path.into_inner();
+    let uri = format!("at://{}/blue.2048.game/{}", did, rkey);
+    let context = request_context.context();
+    let svg = {
+        let index = context.index.read().await;
+        let game = index.get(&uri).ok_or_else(|| {
+            HttpError::for_not_found(None, format!("No game indexed for {}", uri))
+        })?;
+        let board = final_board(game).ok_or_else(|| {
+            HttpError::for_internal_error(format!("Could not replay the game {}", uri))
+        })?;
+        render_card(game, &board)
+    };
+    //Rasterizing takes a while, so it is kept off the async workers
+    let fonts = Arc::clone(&context.card_fonts);
+    let png = tokio::task::spawn_blocking(move || rasterize(&svg, fonts))
+        .await
+        .map_err(|err| HttpError::for_internal_error(err.to_string()))??;
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "image/png")
+        .header(
+            header::CACHE_CONTROL,
+            format!("public, max-age={}", CARD_CACHE_SECONDS),
+        )
+        .body(png.into())
+        .map_err(|err| HttpError::for_internal_error(err.to_string()))
+}
+
+/// The fonts cards are drawn with. Loaded once at startup since it reads every font on the system
+pub fn load_card_fonts() -> Arc<fontdb::Database> {
+    let mut fonts = fontdb::Database::new();
+    fonts.load_system_fonts();
+    //The docker image only has DejaVu, the card asks for Helvetica or Arial first
+    fonts.set_sans_serif_family("DejaVu Sans");
+    Arc::new(fonts)
+}
+
+fn rasterize(svg: &str, fonts: Arc<fontdb::Database>) -> Result<Vec<u8>, HttpError> {
+    let options = Options {
+        fontdb: fonts,
+        ..Options::default()
+    };
+    let tree =
+        Tree::from_str(svg, &options).map_err(|err| HttpError::for_internal_error(err.to_string()))?;
+    let mut pixmap = Pixmap::new(CARD_WIDTH as u32, CARD_HEIGHT as u32)
+        .ok_or_else(|| HttpError::for_internal_error(String::from("Could not make the card")))?;
+    resvg::render(&tree, Transform::default(), &mut pixmap.as_mut());
+    pixmap
+        .encode_png()
+        .map_err(|err| HttpError::for_internal_error(err.to_string()))
+}
+
+/// A bare page with the OpenGraph tags for a game. The Caddyfile sends link preview bots here for
+/// /game links since the client is a single page app and cannot set them itself
+#[endpoint {
+    method = GET,
+    path = "/api/card/game/{did}/{rkey}",
+}]
+pub async fn get_game_card_page(
+    request_context: RequestContext<AppViewContext>,
+    path: Path<GamePath>,
+) -> Result<Response<Body>, HttpError> {
+    let GamePath { did, rkey } = path.into_inner();
+    let uri = format!("at://{}/blue.2048.game/{}", did, rkey);
+    let game_url = format!("{}/game/{}/{}", SITE_URL, did, rkey);
+    let index = request_context.context().index.read().await;
+    //Not indexed yet still gets a page so the link shows something
+    let (title, description, image) = match index.get(&uri) {
+        Some(game) => (
+            format!("{} scored {} on at://2048", display_name(game), game.score),
+            format!(
+                "Best tile {} in {} moves{}. Think you can do better?",
+                game.highest_tile,
+                game.moves,
+                mode_description(game.mode)
+            ),
+            format!("{}/api/game/{}/{}/card.png", SITE_URL, did, rkey),
+        ),
+        None => (
+            String::from("at://2048"),
+            String::from("2048 on the AT Protocol. Your games, your data, your AT Protocol."),
+            format!("{}/assets/imgs/banner.png", SITE_URL),
+        ),
+    };
+
+    let page = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="utf-8"/>
+    <title>{title}</title>
+    <meta property="og:type" content="website"/>
+    <meta property="og:url" content="{url}"/>
+    <meta property="og:title" content="{title}"/>
+    <meta property="og:description" content="{description}"/>
+    <meta property="og:image" content="{image}"/>
+    <meta name="twitter:card" content="summary_large_image"/>
+</head>
+<body>
+    <a href="{url}">{title}</a>
+</body>
+</html>"#,
+        title = escape_html(&title),
+        description = escape_html(&description),
+        image = escape_html(&image),
+        url = escape_html(&game_url),
+    );
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+        .body(page.into())
+        .map_err(|err| HttpError::for_internal_error(err.to_string()))
+}
+
+fn display_name(game: &IndexedGame) -> String {
+    match &game.handle {
+        Some(handle) => format!("@{}", handle),
+        None => game.did.clone(),
+    }
+}
+
+fn mode_description(mode: GameMode) -> String {
+    match mode {
+        GameMode::Classic => String::new(),
+        GameMode::TimeAttack(seconds) => {
+            format!(" in a {}:{:02} time attack", seconds / 60, seconds % 60)
+        }
+        GameMode::MoveLimit(moves) => format!(" with a {} move limit", moves),
+    }
+}
+
+fn final_board(game: &IndexedGame) -> Option<Board> {
+    let recording: SeededRecording = game.seeded_recording.parse().ok()?;
+    GameState::from_reconstructable_ruleset(&recording)
+        .ok()
+        .map(|gamestate| gamestate.board)
+}
+
+/// Board on the left, who played and the score on the right
+fn render_card(game: &IndexedGame, board: &Board) -> String {
+    let layout = TileLayout::new(board.width, board.height);
+    let tile_size = layout.size;
+
+    let mut tiles = String::new();
+    for y in 0..board.height {
+        for x in 0..board.width {
+            let value = board.tiles[y][x].map(|tile| tile.value).unwrap_or(0);
+            let (background, text) = tile_colors(value);
+            let (tile_x, tile_y) = layout.position(x, y);
+            tiles.push_str(&format!(
+                r#"<rect x="{tile_x}" y="{tile_y}" width="{tile_size}" height="{tile_size}" rx="{TILE_RADIUS}" fill="{background}"/>"#
+            ));
+            if value > 0 {
+                tiles.push_str(&format!(
+                    r#"<text x="{}" y="{}" font-size="{}" font-weight="bold" fill="{text}" text-anchor="middle" dominant-baseline="central">{value}</text>"#,
+                    tile_x + tile_size / 2.0,
+                    tile_y + tile_size / 2.0,
+                    layout.font_size(value),
+                ));
+            }
+        }
+    }
+
+    let lines = [
+        (TITLE_TEXT, TEXT_COLOR, String::from("at://2048")),
+        (NAME_TEXT, TEXT_COLOR, escape_html(&display_name(game))),
+        (SCORE_LABEL_TEXT, TEXT_COLOR, String::from("Score")),
+        (SCORE_TEXT, TEXT_COLOR, game.score.to_string()),
+        (
+            DETAILS_TEXT,
+            TEXT_COLOR,
+            format!("Best tile {} · {} moves", game.highest_tile, game.moves),
+        ),
+        (
+            MODE_TEXT,
+            TEXT_COLOR,
+            escape_html(mode_description(game.mode).trim_start()),
+        ),
+        (FOOTER_TEXT, FOOTER_COLOR, String::from("2048.blue")),
+    ]
+    .into_iter()
+    .map(|(line, color, text)| {
+        format!(
+            r#"<text x="{}" y="{}" font-size="{}"{} fill="{color}">{text}</text>"#,
+            text_x(),
+            line.y,
+            line.font_size,
+            if line.bold { r#" font-weight="bold""# } else { "" },
+        )
+    })
+    .collect::<Vec<_>>()
+    .join("\n");
+
+    let board_size = board_size();
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{CARD_WIDTH}" height="{CARD_HEIGHT}" viewBox="0 0 {CARD_WIDTH} {CARD_HEIGHT}" font-family="{FONT_FAMILY}">
+<rect width="{CARD_WIDTH}" height="{CARD_HEIGHT}" fill="{BACKGROUND_COLOR}"/>
+<rect x="{CARD_PADDING}" y="{CARD_PADDING}" width="{board_size}" height="{board_size}" rx="{BOARD_RADIUS}" fill="{BOARD_COLOR}"/>
+{tiles}
+{lines}
+</svg>"##
+    )
+}
+
+/// Handles and dids are user controlled, so they get escaped before going in markup
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -0,0 +1,490 @@
+use crate::index::{GameIndex, IndexedGame, IndexedPost};
+use atrium_api::agent::atp_agent::AtpAgent;
+use atrium_api::agent::atp_agent::store::MemorySessionStore;
+use atrium_api::types::string::Did;
+use atrium_api::app::bsky::feed::defs::PostViewEmbedRefs;
+use atrium_api::app::bsky::richtext::facet::MainFeaturesItem;
+use atrium_api::types::{
+    Collection, LimitedNonZeroU8, LimitedNonZeroU16, TryFromUnknown, Union,
+};
+use atrium_common::resolver::Resolver;
+use atrium_identity::did::{CommonDidResolver, CommonDidResolverConfig, DEFAULT_PLC_DIRECTORY_URL};
+use atrium_oauth::DefaultHttpClient;
+use atrium_xrpc_client::reqwest::ReqwestClient;
+use serde::Deserialize;
+use slog::Logger;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use twothousand_forty_eight::unified::game::GameState;
+use twothousand_forty_eight::unified::validation::Validatable;
+use twothousand_forty_eight::v2::recording::SeededRecording;
+use types_2048::blue;
+use types_2048::game_mode::GameMode;
+
+const RELAY_ENDPOINT: &str = "https://relay1.us-east.bsky.network";
+/// Public Bluesky appview used to search for posts linking to the game
+const PUBLIC_API_ENDPOINT: &str = "https://public.api.bsky.app";
+const SITE_DOMAIN: &str = "2048.blue";
+/// Search results are newest first, so this is only hit on the first crawl or a very busy day
+const MAX_SEARCH_PAGES: usize = 10;
+/// Quicker than a fast player keeps up over a whole time attack game. The record does not say when
+/// each move was made, so a recording with more moves than this allows was not played in time
+const MAX_MOVES_PER_SECOND: usize = 6;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CrawlerConfig {
+    /// How often to crawl the relay for new at://2048 games
+    pub interval_secs: u64,
+}
+
+impl Default for CrawlerConfig {
+    fn default() -> Self {
+        Self { interval_secs: 900 }
+    }
+}
+
+/// A player found from the relay with where their repo lives
+struct Player {
+    did: Did,
+    handle: Option<String>,
+    pds_url: String,
+}
+
+/// Crawls the relay for repos with blue.2048.game records forever on the configured interval
+pub async fn run(index: Arc<RwLock<GameIndex>>, config: CrawlerConfig, log: Logger) {
+    let mut interval = tokio::time::interval(Duration::from_secs(config.interval_secs));
+    loop {
+        interval.tick().await;
+        slog::info!(log, "Starting a crawl for at://2048 games");
+        match crawl(&index, &log).await {
+            Ok(_) => {
+                let games_indexed = index.read().await.len();
+                slog::info!(log, "Crawl finished"; "games_indexed" => games_indexed);
+            }
+            Err(err) => {
+                slog::error!(log, "Crawl failed"; "error" => err);
+            }
+        }
+        //After the games so posts about new games can be matched up to them
+        if let Err(err) = crawl_posts(&index).await {
+            slog::error!(log, "Searching for posts failed"; "error" => err);
+        }
+    }
+}
+
+/// Finds posts linking to 2048.blue for the feed generator
+async fn crawl_posts(index: &Arc<RwLock<GameIndex>>) -> Result<(), String> {
+    let agent = AtpAgent::new(
+        ReqwestClient::new(PUBLIC_API_ENDPOINT),
+        MemorySessionStore::default(),
+    );
+
+    let mut cursor = None;
+    for _ in 0..MAX_SEARCH_PAGES {
+        let output = agent
+            .api
+            .app
+            .bsky
+            .feed
+            .search_posts(
+                atrium_api::app::bsky::feed::search_posts::ParametersData {
+                    author: None,
+                    cursor: cursor.clone(),
+                    domain: Some(SITE_DOMAIN.to_string()),
+                    lang: None,
+                    limit: LimitedNonZeroU8::<100>::try_from(100_u8).ok(),
+                    mentions: None,
+                    q: SITE_DOMAIN.to_string(),
+                    since: None,
+                    sort: Some(String::from("latest")),
+                    tag: None,
+                    until: None,
+                    url: None,
+                }
+                .into(),
+            )
+            .await
+            .map_err(|err| err.to_string())?;
+
+        let mut found_new = false;
+        let mut index = index.write().await;
+        for post in &output.posts {
+            if index.has_post(&post.uri) {
+                continue;
+            }
+            found_new = true;
+            index.insert_post(IndexedPost {
+                uri: post.uri.clone(),
+                did: post.author.did.to_string(),
+                indexed_at: post.indexed_at.clone(),
+                game_uri: post_links(post).iter().find_map(|link| game_uri(link)),
+            });
+        }
+
+        //Once a whole page has been seen before the rest has been too
+        match output.cursor.clone() {
+            Some(next) if found_new => cursor = Some(next),
+            _ => break,
+        }
+    }
+    Ok(())
+}
+
+/// Every link in the post's facets and link card
+fn post_links(post: &atrium_api::app::bsky::feed::defs::PostView) -> Vec<String> {
+    let mut links = vec![];
+    if let Some(Union::Refs(PostViewEmbedRefs::AppBskyEmbedExternalView(embed))) = &post.embed {
+        links.push(embed.external.uri.clone());
+    }
+    if let Ok(record) =
+        atrium_api::app::bsky::feed::post::RecordData::try_from_unknown(post.record.clone())
+    {
+        for facet in record.facets.iter().flatten() {
+            for feature in &facet.features {
+                if let Union::Refs(MainFeaturesItem::Link(link)) = feature {
+                    links.push(link.uri.clone());
+                }
+            }
+        }
+    }
+    links
+}
+
+/// https://2048.blue/game/{did}/{rkey} to the game's at uri
+fn game_uri(link: &str) -> Option<String> {
+    let path = link
+        .strip_prefix("https://")
+        .or_else(|| link.strip_prefix("http://"))?
+        .strip_prefix(SITE_DOMAIN)?
+        .strip_prefix("/game/")?;
+    match path.split('/').collect::<Vec<_>>().as_slice() {
+        [did, rkey] if did.starts_with("did:") && !rkey.is_empty() => {
+            Some(format!("at://{}/{}/{}", did, blue::_2048::Game::NSID, rkey))
+        }
+        _ => None,
+    }
+}
+
+async fn crawl(index: &Arc<RwLock<GameIndex>>, log: &Logger) -> Result<(), String> {
+    let http_client = Arc::new(DefaultHttpClient::default());
+    //finds the did document from the users did
+    let did_resolver = CommonDidResolver::new(CommonDidResolverConfig {
+        plc_directory_url: DEFAULT_PLC_DIRECTORY_URL.to_string(),
+        http_client: Arc::clone(&http_client),
+    });
+
+    let relay_agent = AtpAgent::new(
+        ReqwestClient::new(RELAY_ENDPOINT),
+        MemorySessionStore::default(),
+    );
+
+    let mut cursor = None;
+    loop {
+        let output = relay_agent
+            .api
+            .com
+            .atproto
+            .sync
+            .list_repos_by_collection(
+                atrium_api::com::atproto::sync::list_repos_by_collection::ParametersData {
+                    collection: blue::_2048::Game::NSID.parse().unwrap(),
+                    cursor: cursor.clone(),
+                    limit: LimitedNonZeroU16::try_from(2000_u16).ok(),
+                }
+                .into(),
+            )
+            .await
+            .map_err(|err| err.to_string())?;
+
+        for repo in &output.repos {
+            let player = match resolve_player(&did_resolver, &repo.did).await {
+                Ok(player) => player,
+                Err(err) => {
+                    slog::warn!(log, "Skipping repo"; "did" => repo.did.as_str(), "error" => err);
+                    continue;
+                }
+            };
+            if let Err(err) = index_player(index, &player).await {
+                slog::warn!(log, "Error indexing repo"; "did" => repo.did.as_str(), "error" => err);
+            }
+        }
+
+        match output.cursor.clone() {
+            Some(next) if !output.repos.is_empty() => cursor = Some(next),
+            _ => break,
+        }
+    }
+    Ok(())
+}
+
+async fn resolve_player(
+    did_resolver: &CommonDidResolver<DefaultHttpClient>,
+    did: &Did,
+) -> Result<Player, String> {
+    let did_doc = did_resolver
+        .resolve(did)
+        .await
+        .map_err(|err| err.to_string())?;
+    let handle = did_doc
+        .also_known_as
+        .as_ref()
+        .and_then(|known_as| known_as.first())
+        .map(|handle| handle.replace("at://", ""));
+    let pds_url = did_doc
+        .service
+        .as_ref()
+        .and_then(|services| {
+            services
+                .iter()
+                .find(|service| service.r#type == "AtprotoPersonalDataServer")
+                .map(|service| service.service_endpoint.clone())
+        })
+        .ok_or_else(|| String::from("No pds url found"))?;
+    Ok(Player {
+        did: did.clone(),
+        handle,
+        pds_url,
+    })
+}
+
+/// Indexes every game in the player's repo and notes if they do not want to be on leaderboards
+async fn index_player(index: &Arc<RwLock<GameIndex>>, player: &Player) -> Result<(), String> {
+    let pds_agent = AtpAgent::new(
+        ReqwestClient::new(&player.pds_url),
+        MemorySessionStore::default(),
+    );
+
+    let solo_play = match pds_agent
+        .api
+        .com
+        .atproto
+        .repo
+        .get_record(
+            atrium_api::com::atproto::repo::get_record::ParametersData {
+                cid: None,
+                collection: blue::_2048::player::Profile::NSID.parse().unwrap(),
+                repo: player.did.clone().into(),
+                rkey: "self".parse().unwrap(),
+            }
+            .into(),
+        )
+        .await
+    {
+        Ok(output) => {
+            blue::_2048::player::profile::RecordData::try_from(output.value.clone())
+                .map(|profile| profile.solo_play)
+                .unwrap_or(false)
+        }
+        //No profile just means they have not logged in since profiles were added
+        Err(_) => false,
+    };
+    index.write().await.set_solo_play(player.did.as_str(), solo_play);
+
+    let mut cursor = None;
+    loop {
+        let output = pds_agent
+            .api
+            .com
+            .atproto
+            .repo
+            .list_records(
+                atrium_api::com::atproto::repo::list_records::ParametersData {
+                    collection: blue::_2048::Game::NSID.parse().unwrap(),
+                    cursor: cursor.clone(),
+                    limit: LimitedNonZeroU8::<100>::try_from(100_u8).ok(),
+                    repo: player.did.clone().into(),
+                    reverse: None,
+                }
+                .into(),
+            )
+            .await
+            .map_err(|err| err.to_string())?;
+
+        for record in &output.records {
+            if index
+                .read()
+                .await
+                .is_indexed(&record.uri, record.cid.as_ref().to_string().as_str())
+            {
+                continue;
+            }
+            let game = match blue::_2048::game::RecordData::try_from(record.value.clone())
+            {
+                Ok(game) => game,
+                Err(_) => continue,
+            };
+            if let Some(indexed_game) = validate_game(player, record, game) {
+                index.write().await.insert(indexed_game);
+            }
+        }
+
+        match output.cursor.clone() {
+            Some(next) if output.records.len() == 100 => cursor = Some(next),
+            _ => break,
+        }
+    }
+    Ok(())
+}
+
+/// Replays the recording so the leaderboards only ever show what was actually played
+fn validate_game(
+    player: &Player,
+    record: &atrium_api::com::atproto::repo::list_records::Record,
+    game: blue::_2048::game::RecordData,
+) -> Option<IndexedGame> {
+    //Games played with hints or auto-play do not get ranked
+    if game.assisted.unwrap_or(false) {
+        return None;
+    }
+    let mode = GameMode::from_record(game.mode.as_deref(), game.limit)?;
+    let mut recording: SeededRecording = game.seeded_recording.parse().ok()?;
+    match mode {
+        GameMode::Classic => {}
+        //Games that ran past the clock or were played faster than a person can are not ranked
+        GameMode::TimeAttack(seconds) => {
+            let duration_ms = game.duration_ms?;
+            if duration_ms < 0 || duration_ms > i64::from(seconds) * 1000 {
+                return None;
+            }
+            if recording.moves.len() > seconds as usize * MAX_MOVES_PER_SECOND {
+                return None;
+            }
+        }
+        //Only the moves made within the limit count
+        GameMode::MoveLimit(moves) => recording.moves.truncate(moves as usize),
+    }
+    let validation = recording.validate().ok()?;
+    if validation.score == 0 {
+        return None;
+    }
+    let gamestate = GameState::from_reconstructable_ruleset(&recording).ok()?;
+    let highest_tile = gamestate
+        .board
+        .tiles
+        .iter()
+        .flatten()
+        .filter_map(|tile| *tile)
+        .map(|tile| tile.value)
+        .max()
+        .unwrap_or(0);
+
+    Some(IndexedGame {
+        did: player.did.to_string(),
+        handle: player.handle.clone(),
+        rkey: record.uri.split('/').next_back()?.to_string(),
+        uri: record.uri.clone(),
+        cid: record.cid.as_ref().to_string(),
+        score: validation.score,
+        seed: recording.seed,
+        highest_tile,
+        won: gamestate.won,
+        moves: recording.moves.len(),
+        seeded_recording: (&recording).into(),
+        mode,
+        created_at: game.created_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atrium_api::com::atproto::repo::list_records;
+    use atrium_api::types::Unknown;
+    use atrium_api::types::string::Datetime;
+
+    const CID: &str = "bafyreidfayvfuwqa7qlnopdjiqrxzs6blmoeu4rujcjtnci5beludirz2a";
+
+    fn player() -> Player {
+        Player {
+            did: "did:plc:player".parse().unwrap(),
+            handle: Some(String::from("player.test")),
+            pds_url: String::from("https://pds.test"),
+        }
+    }
+
+    fn record() -> list_records::Record {
+        list_records::RecordData {
+            cid: CID.parse().unwrap(),
+            uri: String::from("at://did:plc:player/blue.2048.game/3lmhx3c5ds22o"),
+            value: Unknown::Null,
+        }
+        .into()
+    }
+
+    /// Plays the first allowed move each turn so every move in the recording is valid
+    fn recording(moves: usize) -> SeededRecording {
+        let mut recording = SeededRecording::empty(1, 4, 4);
+        for _ in 0..moves {
+            let gamestate = GameState::from_reconstructable_ruleset(&recording).unwrap();
+            recording.moves.push(gamestate.allowed_moves[0]);
+        }
+        recording
+    }
+
+    fn game(recording: &SeededRecording, mode: GameMode) -> blue::_2048::game::RecordData {
+        blue::_2048::game::RecordData {
+            assisted: None,
+            completed: true,
+            created_at: Datetime::now(),
+            current_score: 0,
+            duration_ms: Some(30_000),
+            limit: mode.record_limit(),
+            mode: mode.record_mode(),
+            move_count: Some(recording.moves.len() as i64),
+            seeded_recording: recording.into(),
+            sync_status: blue::_2048::defs::SyncStatusData {
+                created_at: Datetime::now(),
+                hash: String::new(),
+                synced_with_at_repo: true,
+                updated_at: Datetime::now(),
+            }
+            .into(),
+            time_to_find_twenty_forty_eight_ms: None,
+            won: false,
+        }
+    }
+
+    #[test]
+    fn assisted_game_is_not_ranked() {
+        let recording = recording(30);
+        let played = game(&recording, GameMode::Classic);
+        assert!(validate_game(&player(), &record(), played.clone()).is_some());
+
+        let assisted = blue::_2048::game::RecordData {
+            assisted: Some(true),
+            ..played
+        };
+        assert!(validate_game(&player(), &record(), assisted).is_none());
+    }
+
+    #[test]
+    fn time_attack_over_the_time_limit_is_not_ranked() {
+        let recording = recording(30);
+        let mode = GameMode::TimeAttack(60);
+        let in_time = blue::_2048::game::RecordData {
+            duration_ms: Some(59_000),
+            ..game(&recording, mode)
+        };
+        assert!(validate_game(&player(), &record(), in_time).is_some());
+
+        let over_time = blue::_2048::game::RecordData {
+            duration_ms: Some(61_000),
+            ..game(&recording, mode)
+        };
+        assert!(validate_game(&player(), &record(), over_time).is_none());
+    }
+
+    #[test]
+    fn move_limit_extra_moves_are_truncated() {
+        let recording = recording(40);
+        let played = game(&recording, GameMode::MoveLimit(25));
+        let indexed = validate_game(&player(), &record(), played).unwrap();
+
+        let mut within_limit = recording.clone();
+        within_limit.moves.truncate(25);
+        assert_eq!(indexed.moves, 25);
+        assert_eq!(indexed.seeded_recording, String::from(&within_limit));
+        assert_eq!(indexed.score, within_limit.validate().unwrap().score);
+    }
+}
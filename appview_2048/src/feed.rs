@@ -0,0 +1,408 @@
+use crate::AppViewContext;
+use crate::index::{GameIndex, IndexedGame, IndexedPost};
+use dropshot::{HttpError, HttpResponseOk, Query, RequestContext, endpoint};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+const DEFAULT_PAGE_SIZE: usize = 30;
+/// Most Bluesky will ask for in one go
+const MAX_PAGE_SIZE: usize = 100;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FeedGeneratorConfig {
+    /// Where the appview is reachable, the feed generator's did is did:web:{hostname}
+    pub hostname: String,
+    /// The account that published the app.bsky.feed.generator records. Defaults to the service did
+    pub publisher_did: Option<String>,
+}
+
+impl Default for FeedGeneratorConfig {
+    fn default() -> Self {
+        Self {
+            hostname: String::from("2048.blue"),
+            publisher_did: None,
+        }
+    }
+}
+
+impl FeedGeneratorConfig {
+    pub fn service_did(&self) -> String {
+        format!("did:web:{}", self.hostname)
+    }
+
+    /// The at uri Bluesky asks for the feed by
+    fn feed_uri(&self, feed: Feed) -> String {
+        format!(
+            "at://{}/app.bsky.feed.generator/{}",
+            self.publisher_did.clone().unwrap_or_else(|| self.service_did()),
+            feed.rkey()
+        )
+    }
+}
+
+/// The feeds the appview serves
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Feed {
+    /// Every post linking to 2048.blue
+    Posts,
+    /// Posts linking to a game that hit a big tile or was a personal best
+    BigMoments,
+}
+
+impl Feed {
+    fn all() -> [Feed; 2] {
+        [Feed::Posts, Feed::BigMoments]
+    }
+
+    /// Record key of the feed's app.bsky.feed.generator record
+    fn rkey(&self) -> &'static str {
+        match self {
+            Feed::Posts => "at2048",
+            Feed::BigMoments => "at2048-big",
+        }
+    }
+
+    fn includes(
+        &self,
+        index: &GameIndex,
+        personal_bests: &HashSet<&str>,
+        game: Option<&IndexedGame>,
+    ) -> bool {
+        match self {
+            Feed::Posts => true,
+            Feed::BigMoments => game
+                .map(|game| index.is_notable(game, personal_bests))
+                .unwrap_or(false),
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DidService {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub service_type: String,
+    #[serde(rename = "serviceEndpoint")]
+    pub service_endpoint: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DidDocument {
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+    pub id: String,
+    pub service: Vec<DidService>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct FeedDescription {
+    pub uri: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DescribeFeedGeneratorResponse {
+    pub did: String,
+    pub feeds: Vec<FeedDescription>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct FeedSkeletonQuery {
+    /// At uri of the feed generator record
+    pub feed: String,
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct SkeletonFeedPost {
+    pub post: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct FeedSkeletonResponse {
+    pub feed: Vec<SkeletonFeedPost>,
+    /// Pass back to get older posts, missing once there are no more
+    pub cursor: Option<String>,
+}
+
+/// The did:web document pointing Bluesky at the feed generator
+#[endpoint {
+    method = GET,
+    path = "/.well-known/did.json",
+}]
+pub async fn get_did_document(
+    request_context: RequestContext<AppViewContext>,
+) -> Result<HttpResponseOk<DidDocument>, HttpError> {
+    let config = &request_context.context().feed_generator;
+    Ok(HttpResponseOk(DidDocument {
+        context: vec![String::from("https://www.w3.org/ns/did/v1")],
+        id: config.service_did(),
+        service: vec![DidService {
+            id: String::from("#bsky_fg"),
+            service_type: String::from("BskyFeedGenerator"),
+            service_endpoint: format!("https://{}", config.hostname),
+        }],
+    }))
+}
+
+/// Lists the feeds this generator serves
+#[endpoint {
+    method = GET,
+    path = "/xrpc/app.bsky.feed.describeFeedGenerator",
+}]
+pub async fn describe_feed_generator(
+    request_context: RequestContext<AppViewContext>,
+) -> Result<HttpResponseOk<DescribeFeedGeneratorResponse>, HttpError> {
+    let config = &request_context.context().feed_generator;
+    Ok(HttpResponseOk(DescribeFeedGeneratorResponse {
+        did: config.service_did(),
+        feeds: Feed::all()
+            .into_iter()
+            .map(|feed| FeedDescription {
+                uri: config.feed_uri(feed),
+            })
+            .collect(),
+    }))
+}
+
+/// The post uris for a page of a feed, Bluesky fills in the rest
+#[endpoint {
+    method = GET,
+    path = "/xrpc/app.bsky.feed.getFeedSkeleton",
+}]
+pub async fn get_feed_skeleton(
+    request_context: RequestContext<AppViewContext>,
+    query: Query<FeedSkeletonQuery>,
+) -> Result<HttpResponseOk<FeedSkeletonResponse>, HttpError> {
+    let context = request_context.context();
+    let query = query.into_inner();
+    let feed = Feed::all()
+        .into_iter()
+        .find(|feed| context.feed_generator.feed_uri(*feed) == query.feed)
+        .ok_or_else(|| {
+            HttpError::for_bad_request(
+                Some(String::from("UnknownFeed")),
+                format!("Unknown feed {}", query.feed),
+            )
+        })?;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let after = query.cursor.as_deref().map(parse_cursor).transpose()?;
+
+    let index = context.index.read().await;
+    Ok(HttpResponseOk(feed_page(&index, feed, limit, after)))
+}
+
+/// Newest first, starting after the post the cursor points at
+fn feed_page(
+    index: &GameIndex,
+    feed: Feed,
+    limit: usize,
+    after: Option<(i64, String)>,
+) -> FeedSkeletonResponse {
+    let personal_bests = match feed {
+        Feed::Posts => HashSet::new(),
+        Feed::BigMoments => index.personal_bests(),
+    };
+    let posts = index
+        .posts()
+        .into_iter()
+        .filter(|(post, _)| match &after {
+            Some((timestamp, uri)) => (post_timestamp(post), &post.uri) < (*timestamp, uri),
+            None => true,
+        })
+        .filter(|(_, game)| feed.includes(index, &personal_bests, *game))
+        .map(|(post, _)| post)
+        .take(limit + 1)
+        .collect::<Vec<_>>();
+    //Fetches one extra to know if there is another page
+    let cursor = (posts.len() > limit)
+        .then(|| posts.get(limit - 1))
+        .flatten()
+        .map(|post| format!("{}::{}", post_timestamp(post), post.uri));
+    FeedSkeletonResponse {
+        feed: posts
+            .into_iter()
+            .take(limit)
+            .map(|post| SkeletonFeedPost {
+                post: post.uri.clone(),
+            })
+            .collect(),
+        cursor,
+    }
+}
+
+fn post_timestamp(post: &IndexedPost) -> i64 {
+    post.indexed_at.as_ref().timestamp_millis()
+}
+
+/// Cursors are the last post's indexed at in milliseconds and its uri, so new posts coming in do
+/// not shift pages
+fn parse_cursor(cursor: &str) -> Result<(i64, String), HttpError> {
+    cursor
+        .split_once("::")
+        .and_then(|(timestamp, uri)| Some((timestamp.parse().ok()?, uri.to_string())))
+        .ok_or_else(|| HttpError::for_bad_request(None, String::from("Invalid cursor")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atrium_api::types::string::Datetime;
+    use types_2048::game_mode::GameMode;
+
+    fn datetime(minute: u32) -> Datetime {
+        format!("2025-05-01T12:{:02}:00.000Z", minute).parse().unwrap()
+    }
+
+    fn game(did: &str, rkey: &str, score: usize, highest_tile: usize, minute: u32) -> IndexedGame {
+        IndexedGame {
+            did: did.to_string(),
+            handle: None,
+            rkey: rkey.to_string(),
+            uri: format!("at://{}/blue.2048.game/{}", did, rkey),
+            cid: String::from("cid"),
+            score,
+            seed: 1,
+            highest_tile,
+            won: highest_tile >= 2048,
+            moves: 100,
+            seeded_recording: String::new(),
+            mode: GameMode::Classic,
+            created_at: datetime(minute),
+        }
+    }
+
+    fn post(rkey: &str, minute: u32, game: Option<&IndexedGame>) -> IndexedPost {
+        IndexedPost {
+            uri: format!("at://did:plc:poster/app.bsky.feed.post/{}", rkey),
+            did: String::from("did:plc:poster"),
+            indexed_at: datetime(minute),
+            game_uri: game.map(|game| game.uri.clone()),
+        }
+    }
+
+    fn uris(response: &FeedSkeletonResponse) -> Vec<&str> {
+        response.feed.iter().map(|post| post.post.as_str()).collect()
+    }
+
+    /// One player getting better, a worse game, a 4096 that is not their best and a post with no game
+    fn stub_index() -> GameIndex {
+        let mut index = GameIndex::default();
+        let first = game("did:plc:alice", "first", 1000, 128, 0);
+        let better = game("did:plc:alice", "better", 5000, 512, 10);
+        let worse = game("did:plc:alice", "worse", 2000, 256, 20);
+        let big_tile = game("did:plc:alice", "big", 4000, 4096, 30);
+        index.insert_post(post("a", 1, Some(&first)));
+        index.insert_post(post("b", 11, Some(&better)));
+        index.insert_post(post("c", 21, Some(&worse)));
+        index.insert_post(post("d", 31, Some(&big_tile)));
+        index.insert_post(post("e", 41, None));
+        for game in [first, better, worse, big_tile] {
+            index.insert(game);
+        }
+        index
+    }
+
+    #[test]
+    fn posts_feed_is_newest_first() {
+        let index = stub_index();
+        let page = feed_page(&index, Feed::Posts, 10, None);
+        assert_eq!(
+            uris(&page),
+            ["e", "d", "c", "b", "a"]
+                .map(|rkey| format!("at://did:plc:poster/app.bsky.feed.post/{}", rkey))
+        );
+        assert_eq!(page.cursor, None);
+    }
+
+    #[test]
+    fn limit_caps_the_page_and_cursor_continues_it() {
+        let index = stub_index();
+        let first_page = feed_page(&index, Feed::Posts, 2, None);
+        assert_eq!(first_page.feed.len(), 2);
+        let cursor = first_page.cursor.clone().expect("more posts to page through");
+
+        let second_page = feed_page(&index, Feed::Posts, 2, Some(parse_cursor(&cursor).unwrap()));
+        let third_page = feed_page(
+            &index,
+            Feed::Posts,
+            2,
+            Some(parse_cursor(&second_page.cursor.clone().unwrap()).unwrap()),
+        );
+        assert_eq!(third_page.feed.len(), 1);
+        assert_eq!(third_page.cursor, None);
+
+        let paged = [&first_page, &second_page, &third_page]
+            .into_iter()
+            .flat_map(uris)
+            .collect::<Vec<_>>();
+        let all = feed_page(&index, Feed::Posts, 10, None);
+        assert_eq!(paged, uris(&all));
+    }
+
+    #[test]
+    fn big_moments_only_has_notable_games_in_post_order() {
+        let index = stub_index();
+        let page = feed_page(&index, Feed::BigMoments, 10, None);
+        assert_eq!(
+            uris(&page),
+            ["d", "b", "a"].map(|rkey| format!("at://did:plc:poster/app.bsky.feed.post/{}", rkey))
+        );
+    }
+
+    #[test]
+    fn big_moments_pages_skip_games_that_are_not_notable() {
+        let index = stub_index();
+        let first_page = feed_page(&index, Feed::BigMoments, 1, None);
+        let cursor = parse_cursor(&first_page.cursor.unwrap()).unwrap();
+        let second_page = feed_page(&index, Feed::BigMoments, 1, Some(cursor));
+        assert_eq!(
+            uris(&second_page),
+            ["at://did:plc:poster/app.bsky.feed.post/b"]
+        );
+    }
+
+    #[test]
+    fn notable_games() {
+        let index = stub_index();
+        let personal_bests = index.personal_bests();
+        let notable = |rkey: &str| {
+            let game = index
+                .get(&format!("at://did:plc:alice/blue.2048.game/{}", rkey))
+                .unwrap();
+            index.is_notable(game, &personal_bests)
+        };
+        assert!(notable("first"));
+        assert!(notable("better"), "a new personal best");
+        assert!(!notable("worse"), "an ordinary game");
+        assert!(notable("big"), "a 4096 tile");
+    }
+
+    #[test]
+    fn solo_players_are_never_notable() {
+        let mut index = stub_index();
+        index.set_solo_play("did:plc:alice", true);
+        let personal_bests = index.personal_bests();
+        let big_tile = index.get("at://did:plc:alice/blue.2048.game/big").unwrap();
+        assert!(!index.is_notable(big_tile, &personal_bests));
+        assert!(feed_page(&index, Feed::BigMoments, 10, None).feed.is_empty());
+    }
+
+    #[test]
+    fn personal_bests_are_per_mode() {
+        let mut index = GameIndex::default();
+        index.insert(game("did:plc:bob", "classic", 8000, 1024, 0));
+        let mut timed = game("did:plc:bob", "timed", 3000, 256, 5);
+        timed.mode = GameMode::TimeAttack(180);
+        index.insert(timed);
+        index.insert(game("did:plc:carol", "other", 100, 16, 10));
+
+        let personal_bests = index.personal_bests();
+        assert!(personal_bests.contains("at://did:plc:bob/blue.2048.game/timed"));
+        assert!(personal_bests.contains("at://did:plc:carol/blue.2048.game/other"));
+    }
+}
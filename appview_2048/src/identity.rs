@@ -0,0 +1,631 @@
+use crate::AppViewContext;
+use atrium_api::did_doc::DidDocument;
+use atrium_api::types::string::{Did, Handle};
+use atrium_common::resolver::Resolver;
+use atrium_identity::Error as IdentityError;
+use atrium_identity::did::{CommonDidResolver, CommonDidResolverConfig, DEFAULT_PLC_DIRECTORY_URL};
+use atrium_identity::handle::{DnsTxtResolver, WellKnownHandleResolver, WellKnownHandleResolverConfig};
+use atrium_oauth::DefaultHttpClient;
+use atrium_xrpc::HttpClient;
+use dropshot::{ClientErrorStatusCode, HttpError, HttpResponseOk, Query, RequestContext, endpoint};
+use hickory_resolver::TokioAsyncResolver;
+use hickory_resolver::config::{ResolverConfig, ResolverOpts};
+use hickory_resolver::error::{ResolveError, ResolveErrorKind};
+use hickory_resolver::proto::op::ResponseCode;
+use http::StatusCode;
+use reqwest::redirect::Policy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// How long a resolved handle is trusted before looking it up again
+const HANDLE_CACHE_TTL: Duration = Duration::from_secs(60 * 5);
+/// How long a did document is trusted before fetching it again
+const DID_CACHE_TTL: Duration = Duration::from_secs(60 * 30);
+/// How long to remember that there was nothing to find, so the same miss is not looked up again
+/// and again
+const NOT_FOUND_CACHE_TTL: Duration = Duration::from_secs(60);
+/// Most entries kept in each cache, the oldest go first after that
+const MAX_CACHE_ENTRIES: usize = 10_000;
+/// Most lookups each client can make that miss the cache and go out to DNS, plc.directory or
+/// someone's server
+const MAX_LOOKUPS_PER_WINDOW: usize = 120;
+const LOOKUP_WINDOW: Duration = Duration::from_secs(60);
+/// Top level domains that are never real handles and would only point at internal hosts
+const RESERVED_TLDS: [&str; 9] = [
+    "alt",
+    "arpa",
+    "example",
+    "internal",
+    "invalid",
+    "local",
+    "localhost",
+    "onion",
+    "test",
+];
+
+/// Looks up TXT records with real DNS, which the browser can't do
+pub struct HickoryDnsTxtResolver {
+    resolver: TokioAsyncResolver,
+}
+
+impl Default for HickoryDnsTxtResolver {
+    /// Uses the system's DNS config, or Google's public resolvers if it can't be read
+    fn default() -> Self {
+        let resolver = TokioAsyncResolver::tokio_from_system_conf().unwrap_or_else(|_| {
+            TokioAsyncResolver::tokio(ResolverConfig::google(), ResolverOpts::default())
+        });
+        Self { resolver }
+    }
+}
+
+impl HickoryDnsTxtResolver {
+    /// Only hosts that resolve to public addresses get requests made to them, so a handle can't
+    /// point us at something on our own network. NotFound when the host does not exist or is
+    /// not public
+    async fn public_ip(&self, host: &str) -> Result<IpAddr, IdentityError> {
+        match self.resolver.lookup_ip(host).await {
+            Ok(lookup) => {
+                let ips = lookup.iter().collect::<Vec<_>>();
+                match ips.first() {
+                    Some(ip) if ips.iter().all(is_public_ip) => Ok(*ip),
+                    _ => Err(IdentityError::NotFound),
+                }
+            }
+            Err(err) if is_nxdomain(&err) => Err(IdentityError::NotFound),
+            Err(err) => Err(IdentityError::DnsResolver(Box::new(err))),
+        }
+    }
+}
+
+impl HickoryDnsTxtResolver {
+    async fn txt_records(&self, query: &str) -> Result<Vec<String>, ResolveError> {
+        let lookup = self.resolver.txt_lookup(query).await?;
+        Ok(lookup
+            .iter()
+            .map(|txt| {
+                txt.txt_data()
+                    .iter()
+                    .map(|data| String::from_utf8_lossy(data))
+                    .collect::<String>()
+            })
+            .collect())
+    }
+}
+
+impl DnsTxtResolver for HickoryDnsTxtResolver {
+    async fn resolve(
+        &self,
+        query: &str,
+    ) -> core::result::Result<Vec<String>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        Ok(self.txt_records(query).await?)
+    }
+}
+
+/// Sends every request for a host to the address that was already checked, so the name can't be
+/// pointed somewhere else between the check and the request
+struct PinnedHttpClient {
+    client: reqwest::Client,
+}
+
+impl PinnedHttpClient {
+    fn new(host: &str, ip: IpAddr) -> Result<Self, IdentityError> {
+        let client = reqwest::Client::builder()
+            //Port 0 keeps the scheme's usual port
+            .resolve(&host.to_lowercase(), SocketAddr::new(ip, 0))
+            //A proxy or a redirect would go to an address that was never checked
+            .no_proxy()
+            .redirect(Policy::none())
+            .build()
+            .map_err(|err| IdentityError::HttpClient(Box::new(err)))?;
+        Ok(Self { client })
+    }
+}
+
+impl HttpClient for PinnedHttpClient {
+    async fn send_http(
+        &self,
+        request: http::Request<Vec<u8>>,
+    ) -> core::result::Result<
+        http::Response<Vec<u8>>,
+        Box<dyn std::error::Error + Send + Sync + 'static>,
+    > {
+        let response = self.client.execute(request.try_into()?).await?;
+        let mut builder = http::Response::builder().status(response.status());
+        for (key, value) in response.headers() {
+            builder = builder.header(key, value);
+        }
+        builder
+            .body(response.bytes().await?.to_vec())
+            .map_err(Into::into)
+    }
+}
+
+/// Remembers values for a while so repeat lookups don't go back out to DNS or plc.directory
+struct TtlCache<K, V> {
+    max_entries: usize,
+    /// Each entry with when it expires
+    entries: RwLock<HashMap<K, (Instant, V)>>,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> TtlCache<K, V> {
+    fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries,
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    async fn get(&self, key: &K) -> Option<V> {
+        let entries = self.entries.read().await;
+        entries
+            .get(key)
+            .filter(|(expires_at, _)| *expires_at > Instant::now())
+            .map(|(_, value)| value.clone())
+    }
+
+    async fn insert(&self, key: K, value: V, ttl: Duration) {
+        let mut entries = self.entries.write().await;
+        let now = Instant::now();
+        //Clears out anything expired while it has the lock so the cache doesn't grow forever
+        entries.retain(|_, (expires_at, _)| *expires_at > now);
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            let soonest = entries
+                .iter()
+                .min_by_key(|(_, (expires_at, _))| *expires_at)
+                .map(|(key, _)| key.clone());
+            if let Some(soonest) = soonest {
+                entries.remove(&soonest);
+            }
+        }
+        entries.insert(key, (now + ttl, value));
+    }
+}
+
+/// Caps how many lookups each client can send out in a window of time, so one caller can't use
+/// up the lookups for everyone else
+struct RateLimiter {
+    max: usize,
+    window: Duration,
+    /// When each client's current window started and how many lookups it has had
+    clients: Mutex<HashMap<IpAddr, (Instant, usize)>>,
+}
+
+impl RateLimiter {
+    fn new(max: usize, window: Duration) -> Self {
+        Self {
+            max,
+            window,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// False once the client's window is used up
+    fn try_acquire(&self, client: IpAddr) -> bool {
+        let mut clients = self.clients.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        //Forgets clients whose window is over so the map doesn't grow forever
+        clients.retain(|_, (started_at, _)| started_at.elapsed() < self.window);
+        let (_, count) = clients.entry(client).or_insert((Instant::now(), 0));
+        if *count >= self.max {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+}
+
+/// Why a lookup did not give an answer
+pub enum LookupError {
+    Identity(IdentityError),
+    /// Too many lookups missed the cache lately
+    RateLimited,
+}
+
+impl From<IdentityError> for LookupError {
+    fn from(err: IdentityError) -> Self {
+        LookupError::Identity(err)
+    }
+}
+
+/// Resolves handles and dids for the client so it can go through our own origin
+pub struct IdentityResolver {
+    dns: HickoryDnsTxtResolver,
+    /// For did:plc, did:web documents go through a PinnedHttpClient instead
+    did_resolver: CommonDidResolver<DefaultHttpClient>,
+    /// None when there was nothing to find
+    handles: TtlCache<Handle, Option<Did>>,
+    did_documents: TtlCache<Did, Option<DidDocument>>,
+    lookups: RateLimiter,
+}
+
+impl Default for IdentityResolver {
+    fn default() -> Self {
+        Self {
+            dns: HickoryDnsTxtResolver::default(),
+            did_resolver: CommonDidResolver::new(CommonDidResolverConfig {
+                plc_directory_url: DEFAULT_PLC_DIRECTORY_URL.to_string(),
+                http_client: Arc::new(DefaultHttpClient::default()),
+            }),
+            handles: TtlCache::new(MAX_CACHE_ENTRIES),
+            did_documents: TtlCache::new(MAX_CACHE_ENTRIES),
+            lookups: RateLimiter::new(MAX_LOOKUPS_PER_WINDOW, LOOKUP_WINDOW),
+        }
+    }
+}
+
+impl IdentityResolver {
+    /// DNS TXT record first, then https://{handle}/.well-known/atproto-did. The client is who
+    /// asked, for rate limiting
+    pub async fn resolve_handle(&self, handle: &Handle, client: IpAddr) -> Result<Did, LookupError> {
+        if let Some(did) = self.handles.get(handle).await {
+            return did.ok_or(LookupError::Identity(IdentityError::NotFound));
+        }
+        if !self.lookups.try_acquire(client) {
+            return Err(LookupError::RateLimited);
+        }
+        match self.lookup_handle(handle).await {
+            Ok(did) => {
+                self.handles
+                    .insert(handle.clone(), Some(did.clone()), HANDLE_CACHE_TTL)
+                    .await;
+                Ok(did)
+            }
+            Err(err) => {
+                if is_not_found(&err) {
+                    self.handles
+                        .insert(handle.clone(), None, NOT_FOUND_CACHE_TTL)
+                        .await;
+                }
+                Err(err.into())
+            }
+        }
+    }
+
+    pub async fn resolve_did(&self, did: &Did, client: IpAddr) -> Result<DidDocument, LookupError> {
+        if let Some(did_document) = self.did_documents.get(did).await {
+            return did_document.ok_or(LookupError::Identity(IdentityError::NotFound));
+        }
+        if !self.lookups.try_acquire(client) {
+            return Err(LookupError::RateLimited);
+        }
+        match self.lookup_did(did).await {
+            Ok(did_document) => {
+                self.did_documents
+                    .insert(did.clone(), Some(did_document.clone()), DID_CACHE_TTL)
+                    .await;
+                Ok(did_document)
+            }
+            Err(err) => {
+                if is_not_found(&err) {
+                    self.did_documents
+                        .insert(did.clone(), None, NOT_FOUND_CACHE_TTL)
+                        .await;
+                }
+                Err(err.into())
+            }
+        }
+    }
+
+    async fn lookup_handle(&self, handle: &Handle) -> Result<Did, IdentityError> {
+        if !is_allowed_host(handle.as_str()) {
+            return Err(IdentityError::NotFound);
+        }
+        let records = match self
+            .dns
+            .txt_records(&format!("_atproto.{}", handle.as_str()))
+            .await
+        {
+            Ok(records) => records,
+            //No TXT record just means the handle uses the well-known file instead
+            Err(err) if matches!(err.kind(), ResolveErrorKind::NoRecordsFound { .. }) => vec![],
+            Err(err) => return Err(IdentityError::DnsResolver(Box::new(err))),
+        };
+        if let Some(did) = records.iter().find_map(|record| record.strip_prefix("did=")) {
+            return did
+                .parse::<Did>()
+                .map_err(|err| IdentityError::Did(err.to_string()));
+        }
+        let ip = self.dns.public_ip(handle.as_str()).await?;
+        WellKnownHandleResolver::new(WellKnownHandleResolverConfig {
+            http_client: Arc::new(PinnedHttpClient::new(handle.as_str(), ip)?),
+        })
+        .resolve(handle)
+        .await
+    }
+
+    async fn lookup_did(&self, did: &Did) -> Result<DidDocument, IdentityError> {
+        //did:web documents are fetched from the host in the did, so it gets the same checks as
+        //handles. Ports and paths are only used for local development
+        if let Some(host) = did.as_str().strip_prefix("did:web:") {
+            if host.contains([':', '%']) || !is_allowed_host(host) {
+                return Err(IdentityError::NotFound);
+            }
+            let ip = self.dns.public_ip(host).await?;
+            return CommonDidResolver::new(CommonDidResolverConfig {
+                plc_directory_url: DEFAULT_PLC_DIRECTORY_URL.to_string(),
+                http_client: Arc::new(PinnedHttpClient::new(host, ip)?),
+            })
+            .resolve(did)
+            .await;
+        }
+        self.did_resolver.resolve(did).await
+    }
+}
+
+/// Misses worth remembering: the name does not exist (NXDOMAIN), the server said 404, or it is a
+/// host we never make requests to. Anything else is DNS or a server having trouble and could work
+/// on the next try
+fn is_not_found(err: &IdentityError) -> bool {
+    match err {
+        IdentityError::NotFound => true,
+        IdentityError::HttpStatus(status) => *status == StatusCode::NOT_FOUND,
+        _ => false,
+    }
+}
+
+/// The name does not exist, as opposed to DNS not answering
+fn is_nxdomain(err: &ResolveError) -> bool {
+    matches!(
+        err.kind(),
+        ResolveErrorKind::NoRecordsFound {
+            response_code: ResponseCode::NXDomain,
+            ..
+        }
+    )
+}
+
+/// Host names we would make a request to. Not IP addresses or names that only exist on a private
+/// network
+fn is_allowed_host(host: &str) -> bool {
+    let host = host.trim_end_matches('.').to_lowercase();
+    if host.trim_matches(['[', ']']).parse::<IpAddr>().is_ok() {
+        return false;
+    }
+    match host.rsplit_once('.') {
+        Some((_, tld)) => !RESERVED_TLDS.contains(&tld),
+        None => false,
+    }
+}
+
+/// Addresses out on the internet, not loopback, private, link local and the like
+fn is_public_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                //Carrier grade NAT, 100.64.0.0/10
+                || (first == 100 && (64..128).contains(&second))
+                || first == 0)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_ip(&IpAddr::V4(ip));
+            }
+            let first_segment = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                //Unique local, fc00::/7
+                || (first_segment & 0xfe00) == 0xfc00
+                //Link local, fe80::/10
+                || (first_segment & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// Who made the request. In production Caddy is in front and passes the caller's address on in
+/// X-Forwarded-For, which is only trusted when the request came from a private address like it
+fn client_ip(request_context: &RequestContext<AppViewContext>) -> IpAddr {
+    let remote = request_context.request.remote_addr().ip();
+    if is_public_ip(&remote) {
+        return remote;
+    }
+    request_context
+        .request
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        //The last address is the one the proxy saw
+        .and_then(|value| value.rsplit(',').next())
+        .and_then(|ip| ip.trim().parse().ok())
+        .unwrap_or(remote)
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ResolveHandleQuery {
+    pub handle: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ResolveHandleResponse {
+    pub did: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ResolveDidQuery {
+    pub did: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ResolveDidResponse {
+    #[serde(rename = "didDoc")]
+    pub did_doc: serde_json::Value,
+}
+
+/// Same as com.atproto.identity.resolveHandle on a PDS
+#[endpoint {
+    method = GET,
+    path = "/xrpc/com.atproto.identity.resolveHandle",
+}]
+pub async fn resolve_handle(
+    request_context: RequestContext<AppViewContext>,
+    query: Query<ResolveHandleQuery>,
+) -> Result<HttpResponseOk<ResolveHandleResponse>, HttpError> {
+    let handle = query
+        .into_inner()
+        .handle
+        .to_lowercase()
+        .parse::<Handle>()
+        .map_err(|err| {
+            HttpError::for_bad_request(Some(String::from("InvalidRequest")), err.to_string())
+        })?;
+    let did = request_context
+        .context()
+        .identity
+        .resolve_handle(&handle, client_ip(&request_context))
+        .await
+        .map_err(|err| to_http_error(err, "HandleNotFound"))?;
+    Ok(HttpResponseOk(ResolveHandleResponse {
+        did: did.to_string(),
+    }))
+}
+
+/// Same as com.atproto.identity.resolveDid on a PDS
+#[endpoint {
+    method = GET,
+    path = "/xrpc/com.atproto.identity.resolveDid",
+}]
+pub async fn resolve_did(
+    request_context: RequestContext<AppViewContext>,
+    query: Query<ResolveDidQuery>,
+) -> Result<HttpResponseOk<ResolveDidResponse>, HttpError> {
+    let did = query.into_inner().did.parse::<Did>().map_err(|err| {
+        HttpError::for_bad_request(Some(String::from("InvalidRequest")), err.to_string())
+    })?;
+    let did_document = request_context
+        .context()
+        .identity
+        .resolve_did(&did, client_ip(&request_context))
+        .await
+        .map_err(|err| to_http_error(err, "DidNotFound"))?;
+    let did_doc = serde_json::to_value(did_document)
+        .map_err(|err| HttpError::for_internal_error(err.to_string()))?;
+    Ok(HttpResponseOk(ResolveDidResponse { did_doc }))
+}
+
+/// Not found is the caller's problem, anything else is DNS or plc.directory having trouble
+fn to_http_error(err: LookupError, not_found_code: &str) -> HttpError {
+    let err = match err {
+        LookupError::Identity(err) => err,
+        LookupError::RateLimited => {
+            return HttpError::for_client_error(
+                Some(String::from("RateLimitExceeded")),
+                ClientErrorStatusCode::TOO_MANY_REQUESTS,
+                String::from("Too many lookups, try again in a minute"),
+            );
+        }
+    };
+    match err {
+        IdentityError::NotFound => {
+            HttpError::for_bad_request(Some(String::from(not_found_code)), err.to_string())
+        }
+        IdentityError::HttpStatus(StatusCode::NOT_FOUND) => {
+            HttpError::for_bad_request(Some(String::from(not_found_code)), err.to_string())
+        }
+        err => HttpError::for_unavail(None, err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(ip: &str) -> IpAddr {
+        ip.parse().unwrap()
+    }
+
+    #[test]
+    fn public_ips() {
+        for public in ["1.1.1.1", "8.8.8.8", "100.128.0.1", "2606:4700:4700::1111"] {
+            assert!(is_public_ip(&ip(public)), "{}", public);
+        }
+        for private in [
+            "127.0.0.1",
+            "10.0.0.1",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "224.0.0.1",
+            "192.0.2.1",
+            "::1",
+            "::",
+            "fc00::1",
+            "fd12:3456::1",
+            "fe80::1",
+            "ff02::1",
+            "::ffff:127.0.0.1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(!is_public_ip(&ip(private)), "{}", private);
+        }
+    }
+
+    #[test]
+    fn allowed_hosts() {
+        for allowed in ["alice.bsky.social", "2048.blue", "Example.COM", "alice.bsky.social."] {
+            assert!(is_allowed_host(allowed), "{}", allowed);
+        }
+        for not_allowed in [
+            "localhost",
+            "127.0.0.1",
+            "[::1]",
+            "printer.local",
+            "db.internal",
+            "alice.test",
+            "ALICE.LOCALHOST",
+            "router.home.arpa",
+            "something.onion",
+        ] {
+            assert!(!is_allowed_host(not_allowed), "{}", not_allowed);
+        }
+    }
+
+    #[tokio::test]
+    async fn cache_entries_expire() {
+        let cache = TtlCache::new(10);
+        cache.insert("kept", 1, Duration::from_secs(60)).await;
+        cache.insert("expired", 2, Duration::ZERO).await;
+        assert_eq!(cache.get(&"kept").await, Some(1));
+        assert_eq!(cache.get(&"expired").await, None);
+        assert_eq!(cache.get(&"missing").await, None);
+    }
+
+    #[tokio::test]
+    async fn full_cache_drops_the_soonest_to_expire() {
+        let cache = TtlCache::new(2);
+        cache.insert("soonest", 1, Duration::from_secs(10)).await;
+        cache.insert("later", 2, Duration::from_secs(60)).await;
+        cache.insert("new", 3, Duration::from_secs(30)).await;
+        assert_eq!(cache.get(&"soonest").await, None);
+        assert_eq!(cache.get(&"later").await, Some(2));
+        assert_eq!(cache.get(&"new").await, Some(3));
+
+        //Replacing a key that is already there does not push anything out
+        cache.insert("new", 4, Duration::from_secs(30)).await;
+        assert_eq!(cache.get(&"later").await, Some(2));
+        assert_eq!(cache.get(&"new").await, Some(4));
+    }
+
+    #[test]
+    fn rate_limit_is_per_client() {
+        let lookups = RateLimiter::new(2, Duration::from_secs(60));
+        assert!(lookups.try_acquire(ip("1.1.1.1")));
+        assert!(lookups.try_acquire(ip("1.1.1.1")));
+        assert!(!lookups.try_acquire(ip("1.1.1.1")));
+        assert!(lookups.try_acquire(ip("8.8.8.8")));
+    }
+}
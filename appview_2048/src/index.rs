@@ -0,0 +1,276 @@
+use atrium_api::types::string::Datetime;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use types_2048::game_mode::GameMode;
+use types_2048::streaks::PlayStreaks;
+
+/// Games reaching this tile are always notable
+const NOTABLE_TILE: usize = 4096;
+
+/// A blue.2048.game record that has been crawled and validated
+#[derive(Clone, Debug)]
+pub struct IndexedGame {
+    pub did: String,
+    pub handle: Option<String>,
+    pub rkey: String,
+    pub uri: String,
+    /// Cid of the record when it was indexed. Used to skip re validating games that have not changed
+    pub cid: String,
+    /// The score from validating the recording, not the one the record claims
+    pub score: usize,
+    pub seed: u32,
+    pub highest_tile: usize,
+    pub won: bool,
+    pub moves: usize,
+    /// Cut off at the move limit if the record had moves past it
+    pub seeded_recording: String,
+    pub mode: GameMode,
+    pub created_at: Datetime,
+}
+
+/// The different leaderboards the index can rank games for
+#[derive(Clone, Debug, PartialEq)]
+pub enum Board {
+    /// Best game for every player
+    Global,
+    /// Best game for every player that was created today (UTC)
+    Daily,
+    /// Best game for every player on a single starting seed
+    Seed(u32),
+    /// Best game for a set of players, usually someone and who they follow
+    Friends(Vec<String>),
+}
+
+/// A player's days in a row with at least one game, worked out from their indexed games (UTC days)
+#[derive(Clone, Debug)]
+pub struct PlayerStreak {
+    pub did: String,
+    pub handle: Option<String>,
+    /// The run ending today or yesterday, since there is still time to keep it going
+    pub current: usize,
+    pub longest: usize,
+    /// When their most recent game was made
+    pub last_played: Datetime,
+}
+
+/// A Bluesky post linking to 2048.blue, found by searching the public appview
+#[derive(Clone, Debug)]
+pub struct IndexedPost {
+    pub uri: String,
+    pub did: String,
+    /// When Bluesky indexed the post. Unlike createdAt it cannot be backdated
+    pub indexed_at: Datetime,
+    /// At uri of the game if the post links to one on 2048.blue
+    pub game_uri: Option<String>,
+}
+
+/// In memory index of every at://2048 game the crawler has found
+#[derive(Default)]
+pub struct GameIndex {
+    /// Keyed by the at uri of the game
+    games: HashMap<String, IndexedGame>,
+    /// At uris of every game started from a seed, so seed boards do not scan every game
+    seeds: HashMap<u32, Vec<String>>,
+    /// Players that have set soloPlay on their profile and should not show up on leaderboards
+    solo_players: Vec<String>,
+    /// Posts linking to 2048.blue keyed by their at uri
+    posts: HashMap<String, IndexedPost>,
+}
+
+impl GameIndex {
+    pub fn insert(&mut self, game: IndexedGame) {
+        let seed_games = self.seeds.entry(game.seed).or_default();
+        if !seed_games.contains(&game.uri) {
+            seed_games.push(game.uri.clone());
+        }
+        self.games.insert(game.uri.clone(), game);
+    }
+
+    pub fn get(&self, uri: &str) -> Option<&IndexedGame> {
+        self.games.get(uri)
+    }
+
+    /// Has this exact version of the record already been indexed
+    pub fn is_indexed(&self, uri: &str, cid: &str) -> bool {
+        self.games
+            .get(uri)
+            .map(|game| game.cid == cid)
+            .unwrap_or(false)
+    }
+
+    pub fn set_solo_play(&mut self, did: &str, solo_play: bool) {
+        self.solo_players.retain(|solo_did| solo_did != did);
+        if solo_play {
+            self.solo_players.push(did.to_string());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn insert_post(&mut self, post: IndexedPost) {
+        self.posts.insert(post.uri.clone(), post);
+    }
+
+    pub fn has_post(&self, uri: &str) -> bool {
+        self.posts.contains_key(uri)
+    }
+
+    /// Every indexed post newest first, with the game it links to if that game is indexed
+    pub fn posts(&self) -> Vec<(&IndexedPost, Option<&IndexedGame>)> {
+        let mut posts = self
+            .posts
+            .values()
+            .map(|post| {
+                let game = post
+                    .game_uri
+                    .as_ref()
+                    .and_then(|game_uri| self.games.get(game_uri));
+                (post, game)
+            })
+            .collect::<Vec<_>>();
+        posts.sort_by(|(a, _), (b, _)| {
+            b.indexed_at
+                .as_ref()
+                .cmp(a.indexed_at.as_ref())
+                .then_with(|| b.uri.cmp(&a.uri))
+        });
+        posts
+    }
+
+    /// At uris of every game that was the player's best in its mode when it was played, nothing
+    /// earlier scored as much. Worked out once so checking a page of games does not scan them all
+    pub fn personal_bests(&self) -> HashSet<&str> {
+        let mut games_by_player: HashMap<(&str, GameMode), Vec<&IndexedGame>> = HashMap::new();
+        for game in self.games.values() {
+            games_by_player
+                .entry((game.did.as_str(), game.mode))
+                .or_default()
+                .push(game);
+        }
+
+        let mut personal_bests = HashSet::new();
+        for mut games in games_by_player.into_values() {
+            games.sort_by(|a, b| a.created_at.as_ref().cmp(b.created_at.as_ref()));
+            let mut best_before: Option<usize> = None;
+            //Games made at the same time do not beat each other
+            for same_time in games.chunk_by(|a, b| a.created_at.as_ref() == b.created_at.as_ref()) {
+                for game in same_time {
+                    if best_before.is_none_or(|best| game.score > best) {
+                        personal_bests.insert(game.uri.as_str());
+                    }
+                }
+                let best_now = same_time.iter().map(|game| game.score).max();
+                best_before = best_before.max(best_now);
+            }
+        }
+        personal_bests
+    }
+
+    /// A game worth calling out, either a big tile or in personal_bests. Solo players are never
+    /// called out
+    pub fn is_notable(&self, game: &IndexedGame, personal_bests: &HashSet<&str>) -> bool {
+        if self.solo_players.iter().any(|did| did == &game.did) {
+            return false;
+        }
+        game.highest_tile >= NOTABLE_TILE || personal_bests.contains(game.uri.as_str())
+    }
+
+    /// Every player's best game on the board for the mode sorted by score, highest first
+    pub fn leaderboard(&self, board: &Board, mode: GameMode) -> Vec<&IndexedGame> {
+        let today = Datetime::now().as_ref().date_naive();
+        let mut best_by_player: HashMap<&str, &IndexedGame> = HashMap::new();
+        let games: Box<dyn Iterator<Item = &IndexedGame>> = match board {
+            Board::Seed(seed) => Box::new(
+                self.seeds
+                    .get(seed)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|uri| self.games.get(uri)),
+            ),
+            _ => Box::new(self.games.values()),
+        };
+        for game in games {
+            if self.solo_players.iter().any(|did| did == &game.did) || game.mode != mode {
+                continue;
+            }
+            let on_board = match board {
+                Board::Global => true,
+                Board::Daily => game.created_at.as_ref().date_naive() == today,
+                Board::Seed(seed) => game.seed == *seed,
+                Board::Friends(dids) => dids.iter().any(|did| did == &game.did),
+            };
+            if !on_board {
+                continue;
+            }
+            match best_by_player.get(game.did.as_str()) {
+                Some(best) if best.score >= game.score => {}
+                _ => {
+                    best_by_player.insert(game.did.as_str(), game);
+                }
+            }
+        }
+
+        let mut ranked = best_by_player.into_values().collect::<Vec<_>>();
+        //Ties go to who got there first
+        ranked.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.created_at.as_ref().cmp(b.created_at.as_ref()))
+        });
+        ranked
+    }
+
+    /// Every player with a streak still going, longest current streak first
+    pub fn streaks(&self) -> Vec<PlayerStreak> {
+        let today = utc_day(&Datetime::now());
+        let mut games_by_player: HashMap<&str, Vec<&IndexedGame>> = HashMap::new();
+        for game in self.games.values() {
+            if self.solo_players.iter().any(|did| did == &game.did) {
+                continue;
+            }
+            games_by_player
+                .entry(game.did.as_str())
+                .or_default()
+                .push(game);
+        }
+
+        let mut streaks = games_by_player
+            .into_values()
+            .filter_map(|games| {
+                let latest = games
+                    .iter()
+                    .max_by(|a, b| a.created_at.as_ref().cmp(b.created_at.as_ref()))?;
+                let days = games
+                    .iter()
+                    .map(|game| utc_day(&game.created_at))
+                    .collect::<BTreeSet<_>>();
+                let streaks = PlayStreaks::from_days(days, today);
+                //Broken streaks do not go on the board
+                if streaks.current == 0 {
+                    return None;
+                }
+                Some(PlayerStreak {
+                    did: latest.did.clone(),
+                    handle: latest.handle.clone(),
+                    current: streaks.current,
+                    longest: streaks.longest,
+                    last_played: latest.created_at.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+        //Ties go to who has kept it going the longest overall
+        streaks.sort_by(|a, b| {
+            b.current
+                .cmp(&a.current)
+                .then_with(|| b.longest.cmp(&a.longest))
+                .then_with(|| a.did.cmp(&b.did))
+        });
+        streaks
+    }
+}
+
+/// Days since the unix epoch in UTC. The appview does not know the player's time zone
+fn utc_day(datetime: &Datetime) -> i64 {
+    datetime.as_ref().timestamp().div_euclid(86_400)
+}
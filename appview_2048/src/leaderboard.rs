@@ -0,0 +1,361 @@
+use crate::AppViewContext;
+use crate::index::{Board, IndexedGame, PlayerStreak};
+use atrium_api::agent::atp_agent::AtpAgent;
+use atrium_api::agent::atp_agent::store::MemorySessionStore;
+use atrium_api::types::LimitedNonZeroU8;
+use atrium_api::types::string::AtIdentifier;
+use atrium_xrpc_client::reqwest::ReqwestClient;
+use dropshot::{HttpError, HttpResponseOk, Path, Query, RequestContext, endpoint};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use types_2048::game_mode::GameMode;
+
+/// Public Bluesky appview used to look up who a player follows
+const PUBLIC_API_ENDPOINT: &str = "https://public.api.bsky.app";
+const DEFAULT_PAGE_SIZE: usize = 25;
+const MAX_PAGE_SIZE: usize = 100;
+/// Stops someone following the whole network from making us crawl forever
+const MAX_FOLLOW_PAGES: usize = 10;
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderboardEntry {
+    pub rank: usize,
+    pub did: String,
+    pub handle: Option<String>,
+    pub score: usize,
+    pub highest_tile: usize,
+    pub seed: u32,
+    pub uri: String,
+    pub rkey: String,
+    pub created_at: String,
+}
+
+impl LeaderboardEntry {
+    fn new(rank: usize, game: &IndexedGame) -> Self {
+        Self {
+            rank,
+            did: game.did.clone(),
+            handle: game.handle.clone(),
+            score: game.score,
+            highest_tile: game.highest_tile,
+            seed: game.seed,
+            uri: game.uri.clone(),
+            rkey: game.rkey.clone(),
+            created_at: game.created_at.as_str().to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderboardResponse {
+    pub entries: Vec<LeaderboardEntry>,
+    /// Pass back to get the next page, missing once you are at the bottom of the board
+    pub cursor: Option<String>,
+    /// Where the viewer placed on the board, if they asked and are on it
+    pub viewer: Option<LeaderboardEntry>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderboardQuery {
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+    /// Did of the logged in player to find their rank
+    pub viewer: Option<String>,
+    /// classic, timeAttack, or moveLimit. Defaults to classic
+    pub mode: Option<String>,
+    /// Seconds for timeAttack, moves for moveLimit
+    pub mode_limit: Option<u32>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StreakEntry {
+    pub rank: usize,
+    pub did: String,
+    pub handle: Option<String>,
+    /// Days in a row (UTC) with at least one game, ending today or yesterday
+    pub current_streak: usize,
+    pub longest_streak: usize,
+    pub last_played_at: String,
+}
+
+impl StreakEntry {
+    fn new(rank: usize, streak: &PlayerStreak) -> Self {
+        Self {
+            rank,
+            did: streak.did.clone(),
+            handle: streak.handle.clone(),
+            current_streak: streak.current,
+            longest_streak: streak.longest,
+            last_played_at: streak.last_played.as_str().to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StreakLeaderboardResponse {
+    pub entries: Vec<StreakEntry>,
+    /// Pass back to get the next page, missing once you are at the bottom of the board
+    pub cursor: Option<String>,
+    /// Where the viewer placed on the board, if they asked and have a streak going
+    pub viewer: Option<StreakEntry>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct StreakLeaderboardQuery {
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+    /// Did of the logged in player to find their rank
+    pub viewer: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct SeedPath {
+    pub seed: u32,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ActorPath {
+    pub did: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GamePath {
+    pub did: String,
+    pub rkey: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameView {
+    pub did: String,
+    pub handle: Option<String>,
+    pub uri: String,
+    pub rkey: String,
+    pub score: usize,
+    pub highest_tile: usize,
+    pub seed: u32,
+    pub won: bool,
+    pub moves: usize,
+    pub seeded_recording: String,
+    pub mode: String,
+    pub mode_limit: Option<u32>,
+    pub created_at: String,
+}
+
+/// Best score for every player
+#[endpoint {
+    method = GET,
+    path = "/api/leaderboard/global",
+}]
+pub async fn get_global_leaderboard(
+    request_context: RequestContext<AppViewContext>,
+    query: Query<LeaderboardQuery>,
+) -> Result<HttpResponseOk<LeaderboardResponse>, HttpError> {
+    leaderboard_page(request_context.context(), &Board::Global, query.into_inner()).await
+}
+
+/// Best score for every player from games started today (UTC)
+#[endpoint {
+    method = GET,
+    path = "/api/leaderboard/daily",
+}]
+pub async fn get_daily_leaderboard(
+    request_context: RequestContext<AppViewContext>,
+    query: Query<LeaderboardQuery>,
+) -> Result<HttpResponseOk<LeaderboardResponse>, HttpError> {
+    leaderboard_page(request_context.context(), &Board::Daily, query.into_inner()).await
+}
+
+/// Best score for every player on one starting seed
+#[endpoint {
+    method = GET,
+    path = "/api/leaderboard/seed/{seed}",
+}]
+pub async fn get_seed_leaderboard(
+    request_context: RequestContext<AppViewContext>,
+    path: Path<SeedPath>,
+    query: Query<LeaderboardQuery>,
+) -> Result<HttpResponseOk<LeaderboardResponse>, HttpError> {
+    let board = Board::Seed(path.into_inner().seed);
+    leaderboard_page(request_context.context(), &board, query.into_inner()).await
+}
+
+/// Best score for the player and everyone they follow on Bluesky
+#[endpoint {
+    method = GET,
+    path = "/api/leaderboard/friends/{did}",
+}]
+pub async fn get_friends_leaderboard(
+    request_context: RequestContext<AppViewContext>,
+    path: Path<ActorPath>,
+    query: Query<LeaderboardQuery>,
+) -> Result<HttpResponseOk<LeaderboardResponse>, HttpError> {
+    let did = path.into_inner().did;
+    let mut friends = get_follows(&did)
+        .await
+        .map_err(|err| HttpError::for_bad_request(None, err))?;
+    friends.push(did);
+    leaderboard_page(
+        request_context.context(),
+        &Board::Friends(friends),
+        query.into_inner(),
+    )
+    .await
+}
+
+/// Players with the most days in a row played, counting any mode
+#[endpoint {
+    method = GET,
+    path = "/api/leaderboard/streaks",
+}]
+pub async fn get_streak_leaderboard(
+    request_context: RequestContext<AppViewContext>,
+    query: Query<StreakLeaderboardQuery>,
+) -> Result<HttpResponseOk<StreakLeaderboardResponse>, HttpError> {
+    let query = query.into_inner();
+    let (limit, offset) = page_bounds(query.limit, query.cursor)?;
+
+    let index = request_context.context().index.read().await;
+    let ranked = index.streaks();
+    let entries = ranked
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(limit)
+        .map(|(position, streak)| StreakEntry::new(position + 1, streak))
+        .collect::<Vec<_>>();
+    let cursor = (offset + limit < ranked.len()).then(|| (offset + limit).to_string());
+    let viewer = query.viewer.and_then(|viewer| {
+        ranked
+            .iter()
+            .position(|streak| streak.did == viewer)
+            .map(|position| StreakEntry::new(position + 1, &ranked[position]))
+    });
+
+    Ok(HttpResponseOk(StreakLeaderboardResponse {
+        entries,
+        cursor,
+        viewer,
+    }))
+}
+
+/// A single indexed game, used to show games linked from the leaderboards
+#[endpoint {
+    method = GET,
+    path = "/api/game/{did}/{rkey}",
+}]
+pub async fn get_game(
+    request_context: RequestContext<AppViewContext>,
+    path: Path<GamePath>,
+) -> Result<HttpResponseOk<GameView>, HttpError> {
+    let GamePath { did, rkey } = path.into_inner();
+    let uri = format!("at://{}/blue.2048.game/{}", did, rkey);
+    let index = request_context.context().index.read().await;
+    match index.get(&uri) {
+        Some(game) => Ok(HttpResponseOk(GameView {
+            did: game.did.clone(),
+            handle: game.handle.clone(),
+            uri: game.uri.clone(),
+            rkey: game.rkey.clone(),
+            score: game.score,
+            highest_tile: game.highest_tile,
+            seed: game.seed,
+            won: game.won,
+            moves: game.moves,
+            seeded_recording: game.seeded_recording.clone(),
+            mode: game.mode.as_str().to_string(),
+            mode_limit: game.mode.limit(),
+            created_at: game.created_at.as_str().to_string(),
+        })),
+        None => Err(HttpError::for_not_found(
+            None,
+            format!("No game indexed for {}", uri),
+        )),
+    }
+}
+
+async fn leaderboard_page(
+    context: &AppViewContext,
+    board: &Board,
+    query: LeaderboardQuery,
+) -> Result<HttpResponseOk<LeaderboardResponse>, HttpError> {
+    let (limit, offset) = page_bounds(query.limit, query.cursor)?;
+    let mode = GameMode::from_record(query.mode.as_deref(), query.mode_limit.map(i64::from))
+        .ok_or_else(|| HttpError::for_bad_request(None, String::from("Invalid mode")))?;
+
+    let index = context.index.read().await;
+    let ranked = index.leaderboard(board, mode);
+    let entries = ranked
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(limit)
+        .map(|(position, game)| LeaderboardEntry::new(position + 1, game))
+        .collect::<Vec<_>>();
+    let cursor = (offset + limit < ranked.len()).then(|| (offset + limit).to_string());
+    let viewer = query.viewer.and_then(|viewer| {
+        ranked
+            .iter()
+            .position(|game| game.did == viewer)
+            .map(|position| LeaderboardEntry::new(position + 1, ranked[position]))
+    });
+
+    Ok(HttpResponseOk(LeaderboardResponse {
+        entries,
+        cursor,
+        viewer,
+    }))
+}
+
+/// The page size and how far into the ranked board the page starts
+fn page_bounds(limit: Option<usize>, cursor: Option<String>) -> Result<(usize, usize), HttpError> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    //The cursor is just the offset into the ranked board
+    let offset = match cursor {
+        None => 0,
+        Some(cursor) => cursor
+            .parse::<usize>()
+            .map_err(|_| HttpError::for_bad_request(None, String::from("Invalid cursor")))?,
+    };
+    Ok((limit, offset))
+}
+
+/// Every did the player follows from the public Bluesky appview
+async fn get_follows(did: &str) -> Result<Vec<String>, String> {
+    let actor: AtIdentifier = did.parse().map_err(|_| String::from("Invalid did"))?;
+    let agent = AtpAgent::new(
+        ReqwestClient::new(PUBLIC_API_ENDPOINT),
+        MemorySessionStore::default(),
+    );
+
+    let mut follows = vec![];
+    let mut cursor = None;
+    for _ in 0..MAX_FOLLOW_PAGES {
+        let output = agent
+            .api
+            .app
+            .bsky
+            .graph
+            .get_follows(
+                atrium_api::app::bsky::graph::get_follows::ParametersData {
+                    actor: actor.clone(),
+                    cursor: cursor.clone(),
+                    limit: LimitedNonZeroU8::<100>::try_from(100_u8).ok(),
+                }
+                .into(),
+            )
+            .await
+            .map_err(|err| err.to_string())?;
+        follows.extend(output.follows.iter().map(|follow| follow.did.to_string()));
+        match output.cursor.clone() {
+            Some(next) if !output.follows.is_empty() => cursor = Some(next),
+            _ => break,
+        }
+    }
+    Ok(follows)
+}
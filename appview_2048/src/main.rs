@@ -15,15 +15,37 @@ use schemars::JsonSchema;
 use serde::Deserialize;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
 
-/// Our context is simply the root of the directory we want to serve.
-struct FileServerContext {
+mod card;
+mod crawler;
+mod feed;
+mod identity;
+mod index;
+mod leaderboard;
+mod oauth;
+
+/// Shared state for every endpoint
+pub struct AppViewContext {
+    /// The root of the directory we want to serve.
     base: PathBuf,
+    /// Every at://2048 game the crawler has found
+    pub index: Arc<RwLock<index::GameIndex>>,
+    pub feed_generator: feed::FeedGeneratorConfig,
+    /// Handle and did resolution for clients that can't do DNS
+    pub identity: identity::IdentityResolver,
+    /// Fonts for drawing the game preview cards
+    pub card_fonts: Arc<resvg::usvg::fontdb::Database>,
 }
 
 #[derive(Deserialize)]
 struct MyAppConfig {
     http_api_server: ConfigDropshot,
+    #[serde(default)]
+    crawler: crawler::CrawlerConfig,
+    #[serde(default)]
+    feed_generator: feed::FeedGeneratorConfig,
 }
 
 #[tokio::main]
@@ -34,12 +56,16 @@ async fn main() -> Result<(), String> {
             println!("Error parsing config file: {}", e);
             MyAppConfig {
                 http_api_server: ConfigDropshot::default(),
+                crawler: crawler::CrawlerConfig::default(),
+                feed_generator: feed::FeedGeneratorConfig::default(),
             }
         }),
         Err(_) => {
             println!("Error reading config file");
             MyAppConfig {
                 http_api_server: ConfigDropshot::default(),
+                crawler: crawler::CrawlerConfig::default(),
+                feed_generator: feed::FeedGeneratorConfig::default(),
             }
         }
     };
@@ -53,10 +79,35 @@ async fn main() -> Result<(), String> {
 
     let mut api = ApiDescription::new();
     api.register(example_api_get_counter).unwrap();
+    api.register(leaderboard::get_global_leaderboard).unwrap();
+    api.register(leaderboard::get_daily_leaderboard).unwrap();
+    api.register(leaderboard::get_seed_leaderboard).unwrap();
+    api.register(leaderboard::get_friends_leaderboard).unwrap();
+    api.register(leaderboard::get_streak_leaderboard).unwrap();
+    api.register(leaderboard::get_game).unwrap();
+    api.register(card::get_game_card).unwrap();
+    api.register(card::get_game_card_page).unwrap();
+    api.register(feed::get_did_document).unwrap();
+    api.register(feed::describe_feed_generator).unwrap();
+    api.register(feed::get_feed_skeleton).unwrap();
+    api.register(identity::resolve_handle).unwrap();
+    api.register(identity::resolve_did).unwrap();
+    api.register(oauth::get_client_metadata).unwrap();
     // api.register(static_content).unwrap();
 
-    let context = FileServerContext {
+    let index = Arc::new(RwLock::new(index::GameIndex::default()));
+    tokio::spawn(crawler::run(
+        Arc::clone(&index),
+        config.crawler,
+        log.new(slog::o!("component" => "crawler")),
+    ));
+
+    let context = AppViewContext {
         base: PathBuf::from("../../client_2048/dist/"),
+        index,
+        feed_generator: config.feed_generator,
+        identity: identity::IdentityResolver::default(),
+        card_fonts: card::load_card_fonts(),
     };
 
     let server = ServerBuilder::new(api, context, log)
@@ -73,7 +124,7 @@ async fn main() -> Result<(), String> {
     path = "/api/test",
     }]
 async fn example_api_get_counter(
-    request_context: RequestContext<FileServerContext>,
+    request_context: RequestContext<AppViewContext>,
 ) -> Result<HttpResponseOk<String>, HttpError> {
     let api_context = request_context.context();
 
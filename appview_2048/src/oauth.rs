@@ -0,0 +1,29 @@
+use crate::AppViewContext;
+use dropshot::{HttpError, HttpResponseOk, RequestContext, endpoint};
+
+/// The client's OAuth metadata, made from the same definition the client logs in with. The origin
+/// comes from the request so staging and self hosted copies get metadata matching where they are
+#[endpoint {
+    method = GET,
+    path = "/client_metadata.json",
+}]
+pub async fn get_client_metadata(
+    request_context: RequestContext<AppViewContext>,
+) -> Result<HttpResponseOk<serde_json::Value>, HttpError> {
+    let headers = request_context.request.headers();
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+    let host = header("x-forwarded-host")
+        .or_else(|| header("host"))
+        .ok_or_else(|| HttpError::for_bad_request(None, String::from("Missing Host header")))?;
+    //Caddy terminates TLS, so anything not marked as plain http came in over https
+    let scheme = header("x-forwarded-proto").unwrap_or_else(|| String::from("https"));
+    let origin = format!("{}://{}", scheme, host);
+    oauth_2048::client_metadata_document(&origin)
+        .map(HttpResponseOk)
+        .map_err(|err| HttpError::for_internal_error(err.to_string()))
+}
@@ -0,0 +1,119 @@
+use twothousand_forty_eight::direction::Direction;
+use twothousand_forty_eight::unified::game::GameState;
+
+/// How many days in a row of playing unlocks the streak achievement
+pub const STREAK_DAYS: usize = 7;
+
+/// Milestones a player can unlock. Saved as blue.2048.player.achievement records keyed by their id
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Achievement {
+    First1024,
+    First2048,
+    First4096,
+    TenGames,
+    HundredGames,
+    SevenDayStreak,
+    NoUpWin,
+}
+
+impl Achievement {
+    /// Every achievement in the order they are shown
+    pub fn all() -> [Achievement; 7] {
+        [
+            Achievement::First1024,
+            Achievement::First2048,
+            Achievement::First4096,
+            Achievement::TenGames,
+            Achievement::HundredGames,
+            Achievement::SevenDayStreak,
+            Achievement::NoUpWin,
+        ]
+    }
+
+    /// The id used as the record key and the record's achievement field
+    pub fn id(&self) -> &'static str {
+        match self {
+            Achievement::First1024 => "first1024",
+            Achievement::First2048 => "first2048",
+            Achievement::First4096 => "first4096",
+            Achievement::TenGames => "tenGames",
+            Achievement::HundredGames => "hundredGames",
+            Achievement::SevenDayStreak => "sevenDayStreak",
+            Achievement::NoUpWin => "noUpWin",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::all()
+            .into_iter()
+            .find(|achievement| achievement.id() == id)
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Achievement::First1024 => "Halfway There",
+            Achievement::First2048 => "2048!",
+            Achievement::First4096 => "Keep Going",
+            Achievement::TenGames => "Getting Started",
+            Achievement::HundredGames => "Dedicated",
+            Achievement::SevenDayStreak => "Week Streak",
+            Achievement::NoUpWin => "Never Look Up",
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Achievement::First1024 => String::from("Make a 1024 block"),
+            Achievement::First2048 => String::from("Make a 2048 block"),
+            Achievement::First4096 => String::from("Make a 4096 block"),
+            Achievement::TenGames => String::from("Finish 10 games"),
+            Achievement::HundredGames => String::from("Finish 100 games"),
+            Achievement::SevenDayStreak => format!("Play on {} days in a row", STREAK_DAYS),
+            Achievement::NoUpWin => String::from("Make a 2048 block without ever moving up"),
+        }
+    }
+}
+
+/// Checks a finished game against every achievement and returns the ones it earns.
+/// Already unlocked achievements are filtered out by the caller
+pub fn earned_by_game(
+    gamestate: &GameState,
+    moves: &[Direction],
+    games_played: i64,
+    current_streak: usize,
+) -> Vec<Achievement> {
+    //Blocks only ever merge upwards so the final board has the game's best block
+    let highest_block = gamestate
+        .board
+        .tiles
+        .iter()
+        .flatten()
+        .filter_map(|tile| *tile)
+        .map(|tile| tile.value)
+        .max()
+        .unwrap_or(0);
+
+    let mut earned = vec![];
+    if highest_block >= 1024 {
+        earned.push(Achievement::First1024);
+    }
+    if highest_block >= 2048 {
+        earned.push(Achievement::First2048);
+    }
+    if highest_block >= 4096 {
+        earned.push(Achievement::First4096);
+    }
+    if games_played >= 10 {
+        earned.push(Achievement::TenGames);
+    }
+    if games_played >= 100 {
+        earned.push(Achievement::HundredGames);
+    }
+    if current_streak >= STREAK_DAYS {
+        earned.push(Achievement::SevenDayStreak);
+    }
+    if gamestate.won && !moves.iter().any(|direction| *direction == Direction::UP) {
+        earned.push(Achievement::NoUpWin);
+    }
+    earned
+}
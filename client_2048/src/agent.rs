@@ -1,14 +1,21 @@
-use crate::at_repo_sync::{AtRepoSync, AtRepoSyncError};
+use atrium_api::types::Collection;
+use crate::achievements::{Achievement, earned_by_game};
+use crate::at_repo_sync::{AtRepoSync, AtRepoSyncError, empty_player_stats};
 use crate::idb::{
-    DB_NAME, GAME_STORE, RecordStorageWrapper, StorageError, object_get, object_get_index,
+    DB_NAME, GAME_STORE, RecordStorageWrapper, SELF_KEY, StorageError, object_get,
+    object_get_all, object_get_index, open_player_db,
 };
 use crate::oauth_client::oauth_client;
+use crate::session::has_saved_session;
+use crate::solver::suggest_move;
+use crate::streaks::{games_per_day, local_day};
 use atrium_api::agent::Agent;
 use atrium_api::types::LimitedU32;
 use atrium_api::types::string::{Datetime, Did, RecordKey, Tid};
 use indexed_db_futures::database::Database;
 use js_sys::Uint8Array;
 use serde::{Deserialize, Serialize};
+use twothousand_forty_eight::direction::Direction;
 use twothousand_forty_eight::unified::game::GameState;
 use twothousand_forty_eight::unified::hash::Hashable;
 use twothousand_forty_eight::unified::reconstruction::Reconstructable;
@@ -16,7 +23,9 @@ use twothousand_forty_eight::v2::recording::SeededRecording;
 use types_2048::blue;
 use types_2048::blue::_2048::defs::SyncStatusData;
 use types_2048::blue::_2048::game;
-use types_2048::blue::_2048::player::stats::RecordData;
+use types_2048::blue::_2048::player::stats::{DirectionCountsData, ModeStatsData, RecordData};
+use types_2048::game_mode::GameMode;
+use types_2048::streaks::PlayStreaks;
 use wasm_bindgen::JsValue;
 use yew_agent::Codec;
 use yew_agent::prelude::*;
@@ -42,83 +51,131 @@ impl Codec for Postcard {
     }
 }
 
+/// How much active time a game took. Time with the page hidden is not counted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayTime {
+    pub duration_ms: i64,
+    /// Active time when the first 2048 block was found
+    pub twenty_forty_eight_ms: Option<i64>,
+}
+
+/// A finished game to be saved
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CompletedGame {
+    /// Seeded recording as a string
+    pub game_history: String,
+    /// The users did if they are signed in
+    pub did: Option<Did>,
+    /// Hints or auto-play were used
+    pub assisted: bool,
+    pub mode: GameMode,
+    pub play_time: PlayTime,
+    /// Their login has expired so it is only saved locally for now
+    pub paused: bool,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum StorageRequest {
-    ///(Seeded recording as a string, the users did if they are signed in)
-    GameCompleted(String, Option<Did>),
+    GameCompleted(CompletedGame),
     TryToSyncRemotely(RecordKey, Option<Did>),
+    ///Sends everything saved locally while the account's login was expired
+    SyncPending(Did),
+    ///Seeded recording as a string of the game to find the best next move for
+    SuggestMove(String),
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum StorageResponse {
     Success,
     AlreadySynced,
+    ///Saved locally only since the login has expired, it syncs once they login again
+    SyncPaused,
     Error(StorageError),
     RepoError(AtRepoSyncError),
+    ///The best next move, None if there is not one
+    SuggestedMove(Option<Direction>),
 }
 
 #[oneshot]
 pub async fn StorageTask(request: StorageRequest) -> StorageResponse {
-    let _db = match Database::open(DB_NAME).await {
-        Ok(db) => db,
-        Err(err) => {
+    //Suggesting a move does not need the db so it skips opening it
+    if !matches!(request, StorageRequest::SuggestMove(_)) {
+        if let Err(err) = Database::open(DB_NAME).await {
             return StorageResponse::Error(StorageError::OpenDbError(err.to_string()));
         }
-    };
+    }
 
     let response = match request {
-        StorageRequest::GameCompleted(game_history, did) => {
-            handle_game_completed(game_history, did).await
+        StorageRequest::GameCompleted(completed_game) => {
+            handle_game_completed(completed_game).await
         }
         StorageRequest::TryToSyncRemotely(record_key, did) => match did {
-            None => Err(AtRepoSyncError::Error(String::from(
-                "There should of been a DID and the user logged in",
-            ))),
+            //Only logged in players can sync
+            None => Err(AtRepoSyncError::AuthErrorNeedToReLogin),
             Some(did) => remote_sync_game(record_key, did).await,
         },
+        StorageRequest::SyncPending(did) => sync_pending(did).await,
+        StorageRequest::SuggestMove(game_history) => Ok(suggest_next_move(&game_history)),
     };
     response.unwrap_or_else(|error| StorageResponse::RepoError(error))
 }
 
+/// Replays the game so far and finds the best next move for it
+fn suggest_next_move(game_history: &str) -> StorageResponse {
+    match game_history.parse::<SeededRecording>() {
+        Ok(seeded_recording) => match GameState::from_reconstructable_ruleset(&seeded_recording) {
+            Ok(gamestate) => StorageResponse::SuggestedMove(suggest_move(&gamestate)),
+            Err(err) => StorageResponse::Error(StorageError::Error(err.to_string())),
+        },
+        Err(err) => StorageResponse::Error(StorageError::Error(err.to_string())),
+    }
+}
+
 pub async fn handle_game_completed(
-    game_history: String,
-    did: Option<Did>,
+    completed_game: CompletedGame,
 ) -> Result<StorageResponse, AtRepoSyncError> {
+    let CompletedGame {
+        game_history,
+        did,
+        assisted,
+        mode,
+        play_time,
+        paused,
+    } = completed_game;
     let seeded_recording: SeededRecording = match game_history.clone().parse() {
         Ok(seeded_recording) => seeded_recording,
         Err(err) => {
-            return Err(AtRepoSyncError::Error(err.to_string()));
+            return Err(AtRepoSyncError::ValidationFailed(err.to_string()));
         }
     };
-    let at_repo_sync = match did {
+    let mut at_repo_sync = match did {
         None => AtRepoSync::new_local_repo(),
+        Some(did) if paused => AtRepoSync::new_paused_repo(did),
         Some(did) => {
             let oauth_client = oauth_client();
-            let session = match oauth_client.restore(&did).await {
-                Ok(session) => session,
+            match oauth_client.restore(&did).await {
+                Ok(session) => AtRepoSync::new_logged_in_repo(Agent::new(session), did),
+                //Logged out somewhere else, the game is still theirs
+                Err(_) if !has_saved_session(&did).await => AtRepoSync::new_paused_repo(did),
                 Err(err) => {
                     log::error!("{:?}", err);
-                    return Err(AtRepoSyncError::Error(err.to_string()));
+                    return Err(AtRepoSyncError::AuthErrorNeedToReLogin);
                 }
-            };
-
-            let agent = Agent::new(session);
-
-            AtRepoSync::new_logged_in_repo(agent, did)
+            }
         }
     };
 
-    let db = match Database::open(DB_NAME).await {
+    let db = match open_player_db(at_repo_sync.users_did()).await {
         Ok(db) => db,
         Err(err) => {
-            return Err(AtRepoSyncError::Error(err.to_string()));
+            return Err(AtRepoSyncError::from(err));
         }
     };
 
     let already_saved: Option<RecordStorageWrapper<game::RecordData>> =
         object_get_index(db, GAME_STORE, &seeded_recording.game_hash())
             .await
-            .map_err(|err| AtRepoSyncError::Error(err.to_string()))?;
+            .map_err(AtRepoSyncError::from)?;
     if let Some(already_saved) = already_saved {
         if already_saved.record.sync_status.synced_with_at_repo || !at_repo_sync.can_remote_sync() {
             log::info!("already saved or cannot sync");
@@ -134,14 +191,20 @@ pub async fn handle_game_completed(
         Ok(gamestate) => gamestate,
         Err(e) => {
             log::error!("Error reconstructing game: {:?}", e.to_string());
-            return Err(AtRepoSyncError::Error(e.to_string()));
+            return Err(AtRepoSyncError::ValidationFailed(e.to_string()));
         }
     };
 
     let record = blue::_2048::game::RecordData {
-        completed: gamestate.over,
+        assisted: assisted.then_some(true),
+        //Timed and move limited games are done once they hit their limit
+        completed: gamestate.over || mode != GameMode::Classic,
         created_at: Datetime::now(),
         current_score: gamestate.score_current as i64,
+        duration_ms: Some(play_time.duration_ms),
+        limit: mode.record_limit(),
+        mode: mode.record_mode(),
+        move_count: Some(seeded_recording.moves.len() as i64),
         seeded_recording: game_history,
         sync_status: SyncStatusData {
             created_at: Datetime::now(),
@@ -151,30 +214,64 @@ pub async fn handle_game_completed(
             updated_at: Datetime::now(),
         }
         .into(),
+        time_to_find_twenty_forty_eight_ms: play_time.twenty_forty_eight_ms,
         won: gamestate.won,
     };
 
-    // if at_repo_sync.can_remote_sync() {
-    let stats_sync = at_repo_sync.sync_stats().await;
-    if stats_sync.is_err() {
-        if at_repo_sync.can_remote_sync() {
-            match stats_sync.err() {
-                None => {}
-                Some(err) => {
-                    log::error!("Error syncing stats: {:?}", err);
-                    if let AtRepoSyncError::AuthErrorNeedToReLogin = err {
-                        return Err(AtRepoSyncError::AuthErrorNeedToReLogin);
-                    }
-                }
-            }
+    if let Err(err) = at_repo_sync.sync_stats().await {
+        log::error!("Error syncing stats: {:?}", err);
+        //The login expired since it was last checked. Nothing has been saved yet so it carries on
+        //locally for them
+        if let (AtRepoSyncError::AuthErrorNeedToReLogin, Some(did)) =
+            (&err, at_repo_sync.users_did())
+        {
+            at_repo_sync = AtRepoSync::new_paused_repo(did.clone());
+            at_repo_sync.sync_stats().await?;
         }
     }
 
-    let stats = match calculate_new_stats(&seeded_recording, &at_repo_sync, gamestate).await {
+    let mut stats = match calculate_new_stats(
+        &seeded_recording,
+        &at_repo_sync,
+        gamestate.clone(),
+        mode,
+        play_time,
+    )
+    .await
+    {
         Ok(value) => value,
         Err(value) => return value,
     };
 
+    //Streaks come from when every game saved for this player was played, including this one
+    let db = open_player_db(at_repo_sync.users_did())
+        .await
+        .map_err(AtRepoSyncError::from)?;
+    let saved_games = object_get_all::<RecordStorageWrapper<game::RecordData>>(db, GAME_STORE)
+        .await
+        .map_err(AtRepoSyncError::from)?;
+    let days = games_per_day(
+        saved_games
+            .iter()
+            .map(|game| js_sys::Date::parse(game.record.created_at.as_str()))
+            .chain([js_sys::Date::now()]),
+    );
+    let streaks = PlayStreaks::from_days(days.into_keys(), local_day(js_sys::Date::now()));
+    stats.current_streak = Some(streaks.current as i64);
+    stats.longest_streak = Some(
+        stats
+            .longest_streak
+            .unwrap_or(0)
+            .max(streaks.longest as i64),
+    );
+    let games_played = stats.games_played
+        + stats
+            .mode_stats
+            .iter()
+            .flatten()
+            .map(|mode_stats| mode_stats.games_played)
+            .sum::<i64>();
+
     at_repo_sync.update_a_player_stats(stats).await?;
 
     let tid = Tid::now(LimitedU32::MIN);
@@ -182,10 +279,68 @@ pub async fn handle_game_completed(
 
     //Using create_a_new_game because it will update local and create remote for now, may change name later
     at_repo_sync
-        .create_a_new_game(record, record_key, seeded_recording.game_hash())
+        .create_a_new_game(record, record_key.clone(), seeded_recording.game_hash())
         .await?;
 
-    Ok(StorageResponse::Success)
+    //Hints and auto-play do not count towards achievements
+    if !assisted {
+        //The game is already saved, so a problem here is only logged
+        if let Err(err) = unlock_achievements(
+            &at_repo_sync,
+            &gamestate,
+            &seeded_recording,
+            games_played,
+            streaks.current,
+            record_key,
+        )
+        .await
+        {
+            log::error!("Error unlocking achievements: {:?}", err);
+            if let AtRepoSyncError::AuthErrorNeedToReLogin = err {
+                return Err(err);
+            }
+        }
+    }
+
+    match at_repo_sync.is_paused() {
+        true => Ok(StorageResponse::SyncPaused),
+        false => Ok(StorageResponse::Success),
+    }
+}
+
+/// Saves any achievements the finished game earned that the player does not already have
+async fn unlock_achievements(
+    at_repo_sync: &AtRepoSync,
+    gamestate: &GameState,
+    seeded_recording: &SeededRecording,
+    games_played: i64,
+    current_streak: usize,
+    record_key: RecordKey,
+) -> Result<(), AtRepoSyncError> {
+    //Pulls down ones from other devices first so they are not unlocked twice
+    at_repo_sync.sync_achievements().await?;
+    let unlocked = at_repo_sync
+        .get_local_achievements()
+        .await?
+        .into_iter()
+        .filter_map(|achievement| Achievement::from_id(&achievement.achievement))
+        .collect::<Vec<_>>();
+
+    for achievement in earned_by_game(
+        gamestate,
+        &seeded_recording.moves,
+        games_played,
+        current_streak,
+    ) {
+        if unlocked.contains(&achievement) {
+            continue;
+        }
+        log::info!("Unlocked achievement: {}", achievement.id());
+        at_repo_sync
+            .unlock_an_achievement(achievement.id(), Some(record_key.clone()))
+            .await?;
+    }
+    Ok(())
 }
 
 pub async fn remote_sync_game(
@@ -200,14 +355,14 @@ pub async fn remote_sync_game(
         }
         Err(err) => {
             log::error!("{:?}", err);
-            return Err(AtRepoSyncError::Error(err.to_string()));
+            return Err(AtRepoSyncError::AuthErrorNeedToReLogin);
         }
     };
 
-    let db = match Database::open(DB_NAME).await {
+    let db = match open_player_db(at_repo_sync.users_did()).await {
         Ok(db) => db,
         Err(err) => {
-            return Err(AtRepoSyncError::Error(err.to_string()));
+            return Err(AtRepoSyncError::from(err));
         }
     };
 
@@ -218,32 +373,22 @@ pub async fn remote_sync_game(
             Ok(game) => match game {
                 Some(game) => game,
                 None => {
-                    return Err(AtRepoSyncError::Error("Game not found locally".to_string()));
+                    return Err(AtRepoSyncError::RecordNotFound);
                 }
             },
-            Err(err) => Err(AtRepoSyncError::Error(err.to_string()))?,
+            Err(err) => Err(AtRepoSyncError::from(err))?,
         };
 
-    let seeded_recording: SeededRecording = match local_game.record.seeded_recording.clone().parse()
-    {
-        Ok(seeded_recording) => seeded_recording,
-        Err(err) => {
-            return Err(AtRepoSyncError::Error(err.to_string()));
-        }
-    };
-
-    let gamestate = match GameState::from_reconstructable_ruleset(&seeded_recording) {
-        Ok(gamestate) => gamestate,
-        Err(e) => {
-            log::error!("Error reconstructing game: {:?}", e.to_string());
-            return Err(AtRepoSyncError::Error(e.to_string()));
-        }
-    };
-
+    let (seeded_recording, gamestate, mode, play_time) = replay_saved_game(&local_game.record)?;
     let record = blue::_2048::game::RecordData {
-        completed: gamestate.over,
+        assisted: local_game.record.assisted,
+        completed: gamestate.over || mode != GameMode::Classic,
         created_at: Datetime::now(),
         current_score: gamestate.score_current as i64,
+        duration_ms: Some(play_time.duration_ms),
+        limit: mode.record_limit(),
+        mode: mode.record_mode(),
+        move_count: Some(seeded_recording.moves.len() as i64),
         seeded_recording: local_game.record.seeded_recording,
         sync_status: SyncStatusData {
             created_at: Datetime::now(),
@@ -253,6 +398,7 @@ pub async fn remote_sync_game(
             updated_at: Datetime::now(),
         }
         .into(),
+        time_to_find_twenty_forty_eight_ms: play_time.twenty_forty_eight_ms,
         won: gamestate.won,
     };
 
@@ -278,7 +424,10 @@ pub async fn remote_sync_game(
         }
     }
 
-    let stats = match calculate_new_stats(&seeded_recording, &at_repo_sync, gamestate).await {
+    let stats =
+        match calculate_new_stats(&seeded_recording, &at_repo_sync, gamestate, mode, play_time)
+            .await
+        {
         Ok(value) => value,
         Err(value) => return value,
     };
@@ -288,18 +437,143 @@ pub async fn remote_sync_game(
     Ok(StorageResponse::Success)
 }
 
+/// Plays a saved game back to get what its stats are worked out from
+fn replay_saved_game(
+    record: &game::RecordData,
+) -> Result<(SeededRecording, GameState, GameMode, PlayTime), AtRepoSyncError> {
+    let seeded_recording: SeededRecording = match record.seeded_recording.clone().parse() {
+        Ok(seeded_recording) => seeded_recording,
+        Err(err) => {
+            return Err(AtRepoSyncError::ValidationFailed(err.to_string()));
+        }
+    };
+
+    let gamestate = match GameState::from_reconstructable_ruleset(&seeded_recording) {
+        Ok(gamestate) => gamestate,
+        Err(e) => {
+            log::error!("Error reconstructing game: {:?}", e.to_string());
+            return Err(AtRepoSyncError::ValidationFailed(e.to_string()));
+        }
+    };
+
+    let mode = GameMode::from_record(record.mode.as_deref(), record.limit).unwrap_or_default();
+    let play_time = PlayTime {
+        duration_ms: record.duration_ms.unwrap_or(0),
+        twenty_forty_eight_ms: record.time_to_find_twenty_forty_eight_ms,
+    };
+    Ok((seeded_recording, gamestate, mode, play_time))
+}
+
+/// Sends the profile, games and achievements saved while offline or while the account's login was
+/// expired. The local stats can't be sent as is, the remote ones may have games from other devices
+/// in them. So the games sent here are added to the remote stats instead
+pub async fn sync_pending(did: Did) -> Result<StorageResponse, AtRepoSyncError> {
+    let local_repo = AtRepoSync::new_paused_repo(did.clone());
+    let profile = local_repo
+        .get_local_player_profile()
+        .await?
+        .filter(|profile| !profile.sync_status.synced_with_at_repo);
+    let stats = local_repo
+        .get_local_player_stats()
+        .await?
+        .filter(|stats| !stats.sync_status.synced_with_at_repo);
+    let achievements_pending = local_repo
+        .get_local_achievements()
+        .await?
+        .iter()
+        .any(|achievement| !achievement.sync_status.synced_with_at_repo);
+    let db = open_player_db(Some(&did))
+        .await
+        .map_err(AtRepoSyncError::from)?;
+    let games = object_get_all::<RecordStorageWrapper<game::RecordData>>(db, GAME_STORE)
+        .await
+        .map_err(AtRepoSyncError::from)?
+        .into_iter()
+        .filter(|game| !game.record.sync_status.synced_with_at_repo)
+        .collect::<Vec<_>>();
+    if profile.is_none() && stats.is_none() && games.is_empty() && !achievements_pending {
+        return Ok(StorageResponse::AlreadySynced);
+    }
+
+    let session = oauth_client()
+        .restore(&did)
+        .await
+        .map_err(|err| {
+            log::error!("{:?}", err);
+            AtRepoSyncError::AuthErrorNeedToReLogin
+        })?;
+    let at_repo_sync = AtRepoSync::new_logged_in_repo(Agent::new(session), did);
+
+    if let Some(profile) = profile {
+        at_repo_sync.update_a_player_profile(profile).await?;
+    }
+    let mut synced_games = vec![];
+    for game in games {
+        match at_repo_sync
+            .create_a_new_game(game.record.clone(), game.rkey, game.index_hash)
+            .await
+        {
+            //Offline or rate limited games stay as not synced for the next try
+            Ok(_) => synced_games.push(game.record),
+            Err(AtRepoSyncError::AuthErrorNeedToReLogin) => {
+                return Err(AtRepoSyncError::AuthErrorNeedToReLogin);
+            }
+            //Left as not synced, it can be tried again from the history page
+            Err(err) => log::error!("Error syncing a game saved while logged out: {:?}", err),
+        }
+    }
+    if let Some(local_stats) = stats {
+        let stats = merge_with_remote_stats(&at_repo_sync, local_stats, &synced_games).await?;
+        at_repo_sync.update_a_player_stats(stats).await?;
+    }
+    if achievements_pending {
+        at_repo_sync.sync_achievements().await?;
+    }
+    Ok(StorageResponse::Success)
+}
+
+/// The remote stats with the games that were just synced added in. Streaks come from every game
+/// saved on this device, so those are kept from the local stats
+async fn merge_with_remote_stats(
+    at_repo_sync: &AtRepoSync,
+    local_stats: RecordData,
+    synced_games: &[game::RecordData],
+) -> Result<RecordData, AtRepoSyncError> {
+    let mut stats = match at_repo_sync
+        .get_remote_record::<RecordData>(blue::_2048::player::Stats::NSID, SELF_KEY.parse().unwrap())
+        .await
+    {
+        Ok(remote_stats) => remote_stats,
+        Err(AtRepoSyncError::RecordNotFound) => empty_player_stats(),
+        Err(err) => return Err(err),
+    };
+    for game in synced_games {
+        match replay_saved_game(game) {
+            Ok((seeded_recording, gamestate, mode, play_time)) => {
+                stats = add_game_to_stats(stats, &seeded_recording, gamestate, mode, play_time)?;
+            }
+            //Still synced, it just won't count towards the stats
+            Err(err) => log::error!("Error counting a synced game towards the stats: {:?}", err),
+        }
+    }
+    stats.current_streak = local_stats.current_streak;
+    stats.longest_streak = stats.longest_streak.max(local_stats.longest_streak);
+    stats.sync_status = local_stats.sync_status;
+    Ok(stats)
+}
+
 async fn calculate_new_stats(
     seeded_recording: &SeededRecording,
     at_repo_sync: &AtRepoSync,
     gamestate: GameState,
+    mode: GameMode,
+    play_time: PlayTime,
 ) -> Result<RecordData, Result<StorageResponse, AtRepoSyncError>> {
-    let mut stats = match at_repo_sync.get_local_player_stats().await {
+    let stats = match at_repo_sync.get_local_player_stats().await {
         Ok(stats) => match stats {
             None => {
-                return Err(Err(AtRepoSyncError::Error(
-                    "No stats found. Good chance they were never created if syncing is off. Or something much worse now."
-                        .to_string(),
-                )));
+                //Good chance they were never created if syncing is off
+                return Err(Err(AtRepoSyncError::RecordNotFound));
             }
             Some(stats) => stats,
         },
@@ -307,6 +581,72 @@ async fn calculate_new_stats(
             return Err(Err(err));
         }
     };
+    add_game_to_stats(stats, seeded_recording, gamestate, mode, play_time).map_err(Err)
+}
+
+/// Counts one finished game towards the stats
+fn add_game_to_stats(
+    mut stats: RecordData,
+    seeded_recording: &SeededRecording,
+    gamestate: GameState,
+    mode: GameMode,
+    play_time: PlayTime,
+) -> Result<RecordData, AtRepoSyncError> {
+    //Play time and moves count no matter the mode
+    stats.total_play_time_ms = Some(stats.total_play_time_ms.unwrap_or(0) + play_time.duration_ms);
+    stats.total_moves = Some(stats.total_moves.unwrap_or(0) + seeded_recording.moves.len() as i64);
+    let mut direction_counts = stats.direction_counts.take().unwrap_or_else(|| {
+        DirectionCountsData {
+            down: 0,
+            left: 0,
+            right: 0,
+            up: 0,
+        }
+        .into()
+    });
+    for direction in &seeded_recording.moves {
+        match direction {
+            Direction::UP => direction_counts.up += 1,
+            Direction::DOWN => direction_counts.down += 1,
+            Direction::LEFT => direction_counts.left += 1,
+            Direction::RIGHT => direction_counts.right += 1,
+            _ => {}
+        }
+    }
+    stats.direction_counts = Some(direction_counts);
+
+    //Timed and move limited games get their own stats so they do not drag down the classic ones
+    if mode != GameMode::Classic {
+        let score = gamestate.score_current as i64;
+        let limit = mode.record_limit().unwrap_or(0);
+        let mut all_mode_stats = stats.mode_stats.take().unwrap_or_default();
+        match all_mode_stats
+            .iter_mut()
+            .find(|mode_stats| mode_stats.mode == mode.as_str() && mode_stats.limit == limit)
+        {
+            Some(mode_stats) => {
+                mode_stats.games_played += 1;
+                mode_stats.total_score += score;
+                mode_stats.average_score = mode_stats.total_score / mode_stats.games_played;
+                if score > mode_stats.highest_score {
+                    mode_stats.highest_score = score;
+                }
+            }
+            None => all_mode_stats.push(
+                ModeStatsData {
+                    average_score: score,
+                    games_played: 1,
+                    highest_score: score,
+                    limit,
+                    mode: mode.as_str().to_string(),
+                    total_score: score,
+                }
+                .into(),
+            ),
+        }
+        stats.mode_stats = Some(all_mode_stats);
+        return Ok(stats);
+    }
 
     let highest_block_this_game = gamestate
         .board
@@ -330,10 +670,19 @@ async fn calculate_new_stats(
         stats.highest_score = gamestate.score_current as i64;
     }
 
+    if let Some(found_ms) = play_time.twenty_forty_eight_ms {
+        if stats
+            .fastest_twenty_forty_eight_ms
+            .is_none_or(|fastest| found_ms < fastest)
+        {
+            stats.fastest_twenty_forty_eight_ms = Some(found_ms);
+        }
+    }
+
     let reconstruction = match seeded_recording.reconstruct() {
         Ok(reconstruction) => reconstruction,
         Err(err) => {
-            return Err(Err(AtRepoSyncError::Error(err.to_string())));
+            return Err(AtRepoSyncError::ValidationFailed(err.to_string()));
         }
     };
 
@@ -0,0 +1,206 @@
+use atrium_api::did_doc::DidDocument;
+use atrium_api::types::string::{Did, Handle};
+use gloo::net::http::Request;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::fmt::Display;
+use types_2048::game_mode::GameMode;
+
+/// The appview is served from the same host behind the /api path
+const APPVIEW_API: &str = "/api";
+/// The appview's xrpc methods, also on the same host
+const APPVIEW_XRPC: &str = "/xrpc";
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderboardEntry {
+    pub rank: usize,
+    pub did: String,
+    pub handle: Option<String>,
+    pub score: usize,
+    pub highest_tile: usize,
+    pub seed: u32,
+    pub uri: String,
+    pub rkey: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderboardResponse {
+    pub entries: Vec<LeaderboardEntry>,
+    pub cursor: Option<String>,
+    pub viewer: Option<LeaderboardEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameView {
+    pub did: String,
+    pub handle: Option<String>,
+    pub uri: String,
+    pub rkey: String,
+    pub score: usize,
+    pub highest_tile: usize,
+    pub seed: u32,
+    pub won: bool,
+    pub moves: usize,
+    pub seeded_recording: String,
+    pub mode: String,
+    pub mode_limit: Option<u32>,
+    pub created_at: String,
+}
+
+impl GameView {
+    pub fn game_mode(&self) -> GameMode {
+        GameMode::from_record(Some(&self.mode), self.mode_limit.map(i64::from)).unwrap_or_default()
+    }
+}
+
+/// The leaderboards the appview ranks games on
+#[derive(Clone, PartialEq, Default, Debug)]
+pub enum Board {
+    #[default]
+    Global,
+    Daily,
+    Seed(u32),
+    /// The player and everyone they follow
+    Friends(String),
+}
+
+impl Board {
+    fn path(&self) -> String {
+        match self {
+            Board::Global => "leaderboard/global".to_string(),
+            Board::Daily => "leaderboard/daily".to_string(),
+            Board::Seed(seed) => format!("leaderboard/seed/{}", seed),
+            Board::Friends(did) => format!("leaderboard/friends/{}", did),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AppViewError {
+    /// The appview does not have what was asked for
+    NotFound,
+    Error(String),
+}
+
+impl Display for AppViewError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppViewError::NotFound => write!(f, "Not found"),
+            AppViewError::Error(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// Gets a page of a leaderboard for a game mode. Pass in the cursor from the last page to get the
+/// next one
+pub async fn get_leaderboard(
+    board: &Board,
+    mode: GameMode,
+    cursor: Option<String>,
+    viewer: Option<String>,
+) -> Result<LeaderboardResponse, AppViewError> {
+    let mut query = vec![("mode", mode.as_str().to_string())];
+    if let Some(limit) = mode.limit() {
+        query.push(("modeLimit", limit.to_string()));
+    }
+    if let Some(cursor) = cursor {
+        query.push(("cursor", cursor));
+    }
+    if let Some(viewer) = viewer {
+        query.push(("viewer", viewer));
+    }
+    let request_url = format!("{}/{}", APPVIEW_API, board.path());
+    let resp = Request::get(request_url.as_str())
+        .query(query)
+        .send()
+        .await
+        .map_err(|err| AppViewError::Error(err.to_string()))?;
+    if !resp.ok() {
+        return Err(AppViewError::Error(format!(
+            "The leaderboard returned an error: {}",
+            resp.status_text()
+        )));
+    }
+    resp.json::<LeaderboardResponse>()
+        .await
+        .map_err(|err| AppViewError::Error(err.to_string()))
+}
+
+/// Gets a single game the appview has indexed
+pub async fn get_game(did: &str, rkey: &str) -> Result<GameView, AppViewError> {
+    let request_url = format!("{}/game/{}/{}", APPVIEW_API, did, rkey);
+    let resp = Request::get(request_url.as_str())
+        .send()
+        .await
+        .map_err(|err| AppViewError::Error(err.to_string()))?;
+    if resp.status() == 404 {
+        return Err(AppViewError::NotFound);
+    }
+    if !resp.ok() {
+        return Err(AppViewError::Error(format!(
+            "Error loading the game: {}",
+            resp.status_text()
+        )));
+    }
+    resp.json::<GameView>()
+        .await
+        .map_err(|err| AppViewError::Error(err.to_string()))
+}
+
+#[derive(Deserialize)]
+struct ResolveHandleResponse {
+    did: Did,
+}
+
+#[derive(Deserialize)]
+struct ResolveDidResponse {
+    #[serde(rename = "didDoc")]
+    did_doc: DidDocument,
+}
+
+/// Asks the appview for one of the com.atproto.identity lookups. It answers 400 when there is
+/// nothing to find
+async fn identity_lookup<T: DeserializeOwned>(
+    method: &str,
+    param: (&str, &str),
+) -> Result<T, AppViewError> {
+    let request_url = format!("{}/{}", APPVIEW_XRPC, method);
+    let resp = Request::get(request_url.as_str())
+        .query([param])
+        .send()
+        .await
+        .map_err(|err| AppViewError::Error(err.to_string()))?;
+    if resp.status() == 400 {
+        return Err(AppViewError::NotFound);
+    }
+    if !resp.ok() {
+        return Err(AppViewError::Error(format!(
+            "Error resolving through the appview: {}",
+            resp.status_text()
+        )));
+    }
+    resp.json::<T>()
+        .await
+        .map_err(|err| AppViewError::Error(err.to_string()))
+}
+
+/// Resolves a handle with the appview, which can use real DNS and caches the answer
+pub async fn resolve_handle(handle: &Handle) -> Result<Did, AppViewError> {
+    identity_lookup::<ResolveHandleResponse>(
+        "com.atproto.identity.resolveHandle",
+        ("handle", handle.as_str()),
+    )
+    .await
+    .map(|response| response.did)
+}
+
+/// Gets a did document through the appview's cache of plc.directory and did:web
+pub async fn resolve_did(did: &Did) -> Result<DidDocument, AppViewError> {
+    identity_lookup::<ResolveDidResponse>("com.atproto.identity.resolveDid", ("did", did.as_str()))
+        .await
+        .map(|response| response.did_doc)
+}
@@ -0,0 +1,293 @@
+use crate::at_repo_sync::{AtRepoSync, AtRepoSyncError};
+use crate::idb::{FRIENDS_STATS_STORE, SELF_KEY, object_get, open_player_db, transaction_put};
+use crate::resolver::CachingDidResolver;
+use atrium_api::types::string::{AtIdentifier, Datetime, Did};
+use atrium_api::types::{Collection, LimitedNonZeroU8};
+use atrium_common::resolver::Resolver;
+use futures::future::join_all;
+use gloo::net::http::Request;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use types_2048::blue::_2048::player;
+
+/// How long a friend's stats are used from the cache before asking their PDS again
+const FRIENDS_CACHE_MINUTES: i64 = 30;
+/// Stops someone with a huge social graph from making thousands of requests
+const MAX_GRAPH_PAGES: usize = 5;
+/// How many friends' PDSs to ask at once
+const CONCURRENT_REQUESTS: usize = 10;
+
+/// Which part of the player's social graph to compare stats with.
+/// Saved on the profile as defaultSocial
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum Social {
+    Followers,
+    #[default]
+    Following,
+    Mutuals,
+}
+
+impl Social {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Social::Followers => "followers",
+            Social::Following => "following",
+            Social::Mutuals => "mutuals",
+        }
+    }
+
+    pub fn from_profile(profile: &player::profile::RecordData) -> Self {
+        match profile.default_social.as_deref() {
+            Some("followers") => Social::Followers,
+            Some("mutuals") => Social::Mutuals,
+            _ => Social::Following,
+        }
+    }
+}
+
+impl Display for Social {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Social::Followers => write!(f, "Followers"),
+            Social::Following => write!(f, "Following"),
+            Social::Mutuals => write!(f, "Mutuals"),
+        }
+    }
+}
+
+/// A friend's stats as cached in the friends:stats store
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FriendStats {
+    pub did: String,
+    pub handle: String,
+    /// None if they have not played yet or have soloPlay set
+    pub stats: Option<player::stats::RecordData>,
+    pub fetched_at: Datetime,
+}
+
+/// Just the part of a com.atproto.repo.getRecord response we need
+#[derive(Deserialize)]
+struct GetRecordResponse<T> {
+    value: T,
+}
+
+impl AtRepoSync {
+    /// Gets the dids and handles for the chosen part of the player's social graph
+    pub async fn get_social_graph(
+        &self,
+        social: &Social,
+    ) -> Result<Vec<(Did, String)>, AtRepoSyncError> {
+        match social {
+            Social::Followers => self.get_followers().await,
+            Social::Following => self.get_follows().await,
+            Social::Mutuals => {
+                let followers = self.get_followers().await?;
+                let follows = self.get_follows().await?;
+                Ok(follows
+                    .into_iter()
+                    .filter(|(did, _)| followers.iter().any(|(follower, _)| follower == did))
+                    .collect())
+            }
+        }
+    }
+
+    async fn get_follows(&self) -> Result<Vec<(Did, String)>, AtRepoSyncError> {
+        let (client, did) = match (&self.client, &self.users_did) {
+            (Some(client), Some(did)) => (client, did),
+            _ => return Err(AtRepoSyncError::AuthErrorNeedToReLogin),
+        };
+        let mut follows = vec![];
+        let mut cursor = None;
+        for _ in 0..MAX_GRAPH_PAGES {
+            let output = client
+                .api
+                .app
+                .bsky
+                .graph
+                .get_follows(
+                    atrium_api::app::bsky::graph::get_follows::ParametersData {
+                        actor: AtIdentifier::Did(did.clone()),
+                        cursor: cursor.clone(),
+                        limit: LimitedNonZeroU8::try_from(100_u8).ok(),
+                    }
+                    .into(),
+                )
+                .await
+                .map_err(AtRepoSyncError::from)?;
+            follows.extend(
+                output
+                    .follows
+                    .iter()
+                    .map(|follow| (follow.did.clone(), follow.handle.to_string())),
+            );
+            match output.cursor.clone() {
+                Some(next) if !output.follows.is_empty() => cursor = Some(next),
+                _ => break,
+            }
+        }
+        Ok(follows)
+    }
+
+    async fn get_followers(&self) -> Result<Vec<(Did, String)>, AtRepoSyncError> {
+        let (client, did) = match (&self.client, &self.users_did) {
+            (Some(client), Some(did)) => (client, did),
+            _ => return Err(AtRepoSyncError::AuthErrorNeedToReLogin),
+        };
+        let mut followers = vec![];
+        let mut cursor = None;
+        for _ in 0..MAX_GRAPH_PAGES {
+            let output = client
+                .api
+                .app
+                .bsky
+                .graph
+                .get_followers(
+                    atrium_api::app::bsky::graph::get_followers::ParametersData {
+                        actor: AtIdentifier::Did(did.clone()),
+                        cursor: cursor.clone(),
+                        limit: LimitedNonZeroU8::try_from(100_u8).ok(),
+                    }
+                    .into(),
+                )
+                .await
+                .map_err(AtRepoSyncError::from)?;
+            followers.extend(
+                output
+                    .followers
+                    .iter()
+                    .map(|follower| (follower.did.clone(), follower.handle.to_string())),
+            );
+            match output.cursor.clone() {
+                Some(next) if !output.followers.is_empty() => cursor = Some(next),
+                _ => break,
+            }
+        }
+        Ok(followers)
+    }
+
+    /// Stats for everyone in the chosen part of the player's social graph. Uses the cache unless
+    /// refresh is set or the cached stats are old
+    pub async fn get_friends_stats(
+        &self,
+        social: &Social,
+        refresh: bool,
+    ) -> Result<Vec<FriendStats>, AtRepoSyncError> {
+        let friends = self.get_social_graph(social).await?;
+        let db = open_player_db(self.users_did.as_ref())
+            .await
+            .map_err(AtRepoSyncError::from)?;
+
+        let mut friends_stats = vec![];
+        let mut to_fetch = vec![];
+        for (did, handle) in friends {
+            let cached = match refresh {
+                true => None,
+                false => object_get::<FriendStats>(db.clone(), FRIENDS_STATS_STORE, did.as_str())
+                    .await
+                    .unwrap_or(None),
+            };
+            match cached {
+                Some(cached) if !is_stale(&cached) => friends_stats.push(cached),
+                _ => to_fetch.push((did, handle)),
+            }
+        }
+
+        for chunk in to_fetch.chunks(CONCURRENT_REQUESTS) {
+            let fetched = join_all(
+                chunk
+                    .iter()
+                    .map(|(did, handle)| fetch_friend_stats(did.clone(), handle.clone())),
+            )
+            .await;
+            for friend_stats in fetched {
+                if let Err(err) =
+                    transaction_put(db.clone(), friend_stats.clone(), FRIENDS_STATS_STORE, None)
+                        .await
+                {
+                    log::error!("Error caching a friend's stats: {:?}", err.to_string());
+                }
+                friends_stats.push(friend_stats);
+            }
+        }
+        Ok(friends_stats)
+    }
+}
+
+fn is_stale(friend_stats: &FriendStats) -> bool {
+    let age = Datetime::now()
+        .as_ref()
+        .signed_duration_since(*friend_stats.fetched_at.as_ref());
+    age.num_minutes() >= FRIENDS_CACHE_MINUTES
+}
+
+/// Reads a friend's profile and stats records straight from their PDS. Any errors just mean
+/// there are no stats to show for them
+async fn fetch_friend_stats(did: Did, handle: String) -> FriendStats {
+    let stats = match get_pds_url(&did).await {
+        Some(pds_url) => {
+            let profile = get_public_record::<player::profile::RecordData>(
+                &pds_url,
+                &did,
+                player::Profile::NSID,
+            )
+            .await;
+            match profile {
+                Some(profile) if profile.solo_play => None,
+                _ => get_public_record::<player::stats::RecordData>(
+                    &pds_url,
+                    &did,
+                    player::Stats::NSID,
+                )
+                .await,
+            }
+        }
+        None => None,
+    };
+    FriendStats {
+        did: did.to_string(),
+        handle,
+        stats,
+        fetched_at: Datetime::now(),
+    }
+}
+
+async fn get_pds_url(did: &Did) -> Option<String> {
+    match CachingDidResolver::default().resolve(did).await {
+        Ok(doc) => doc.service.and_then(|services| {
+            services
+                .into_iter()
+                .find(|service| service.r#type == "AtprotoPersonalDataServer")
+                .map(|service| service.service_endpoint)
+        }),
+        Err(err) => {
+            log::error!("Error resolving did: {}", err);
+            None
+        }
+    }
+}
+
+async fn get_public_record<T>(pds_url: &str, did: &Did, collection: &str) -> Option<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let request_url = format!(
+        "{}/xrpc/com.atproto.repo.getRecord",
+        pds_url.trim_end_matches('/')
+    );
+    let resp = Request::get(request_url.as_str())
+        .query([
+            ("repo", did.as_str()),
+            ("collection", collection),
+            ("rkey", SELF_KEY),
+        ])
+        .send()
+        .await
+        .ok()?;
+    if !resp.ok() {
+        return None;
+    }
+    resp.json::<GetRecordResponse<T>>()
+        .await
+        .ok()
+        .map(|response| response.value)
+}
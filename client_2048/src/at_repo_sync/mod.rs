@@ -1,17 +1,15 @@
 use crate::atrium_stores::IndexDBSessionStore;
 use crate::idb::{
-    DB_NAME, GAME_STORE, PROFILE_STORE, RecordStorageWrapper, SELF_KEY, STATS_STORE, StorageError,
-    object_get, transaction_put,
+    ACHIEVEMENTS_STORE, GAME_STORE, PROFILE_STORE, RecordStorageWrapper, SELF_KEY, STATS_STORE,
+    StorageError, object_get, object_get_all, open_player_db, transaction_put,
 };
-use crate::resolver::ApiDNSTxtResolver;
+use crate::resolver::{CachingDidResolver, OriginHandleResolver};
 use atrium_api::agent::Agent;
 use atrium_api::types::string::{AtIdentifier, Datetime, Did, RecordKey};
-use atrium_api::types::{Collection, LimitedNonZeroU8};
-use atrium_identity::did::CommonDidResolver;
-use atrium_identity::handle::AtprotoHandleResolver;
+use atrium_api::types::{Collection, LimitedNonZeroU8, Unknown};
 use atrium_oauth::{DefaultHttpClient, OAuthSession};
 use atrium_xrpc::Error::Authentication;
-use indexed_db_futures::database::Database;
+use atrium_xrpc::error::XrpcErrorKind;
 use serde::{Deserialize, Serialize};
 use std::rc::Rc;
 use types_2048::blue;
@@ -21,11 +19,13 @@ use types_2048::record::KnownRecord;
 
 use xxhash_rust::const_xxh3::xxh3_64 as const_xxh3;
 
+pub mod friends;
+
 type AgentType = Agent<
     OAuthSession<
         DefaultHttpClient,
-        CommonDidResolver<DefaultHttpClient>,
-        AtprotoHandleResolver<ApiDNSTxtResolver, DefaultHttpClient>,
+        CachingDidResolver,
+        OriginHandleResolver,
         IndexDBSessionStore,
     >,
 >;
@@ -34,10 +34,121 @@ type AgentType = Agent<
 pub enum AtRepoSyncError {
     LocalIsNewer,
     RemoteIsNewer,
-    AtRepoCallError(String),
-    LocalRepoError(String),
-    Error(String),
+    LocalRepoError(StorageError),
+    /// Not logged in, or the login could not be restored or refreshed
     AuthErrorNeedToReLogin,
+    /// Could not reach the PDS, most likely the device is offline
+    NetworkOffline,
+    /// The PDS is asking us to slow down
+    RateLimited,
+    RecordNotFound,
+    /// The record changed in the AT repo since it was last read
+    InvalidSwap,
+    /// The record is not valid. The PDS would not accept it, or the game in it does not replay
+    ValidationFailed(String),
+    /// The browser will not let IndexedDB store any more
+    QuotaExceeded,
+    /// A record in the AT repo does not match the lexicon
+    MalformedRecord(String),
+    /// The PDS sent back an error there is no handling for
+    PdsError { status: u16, error: String },
+}
+
+impl AtRepoSyncError {
+    /// Worth trying again later without the player doing anything. Everything else needs them to
+    /// act, or will keep failing the same way
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            AtRepoSyncError::NetworkOffline
+                | AtRepoSyncError::RateLimited
+                | AtRepoSyncError::PdsError { status: 500.., .. }
+        )
+    }
+
+    /// What to tell the player, and what they can do about it
+    pub fn message(&self) -> String {
+        match self {
+            AtRepoSyncError::AuthErrorNeedToReLogin => {
+                String::from("Your login has expired. Login again and it will sync.")
+            }
+            AtRepoSyncError::NetworkOffline => String::from(
+                "You look to be offline. It is saved on this device and will sync when you are back online.",
+            ),
+            AtRepoSyncError::RateLimited => String::from(
+                "Your PDS is busy right now. It is saved on this device and will sync in a few minutes.",
+            ),
+            AtRepoSyncError::RecordNotFound => {
+                String::from("It could not be found, it may have been deleted.")
+            }
+            AtRepoSyncError::InvalidSwap => String::from(
+                "It was changed from another device. Refresh the page to get the latest and try again.",
+            ),
+            AtRepoSyncError::ValidationFailed(_) => String::from(
+                "It is not valid, so it can't be saved. Please let us know on GitHub so we can fix it.",
+            ),
+            AtRepoSyncError::QuotaExceeded => String::from(
+                "Your browser is out of storage for at://2048. Free up some space and try again.",
+            ),
+            AtRepoSyncError::MalformedRecord(_) => String::from(
+                "A record in your AT repo could not be read, it may have been written by another app.",
+            ),
+            err => format!("Something went wrong: {}", err),
+        }
+    }
+}
+
+/// Sorts out the errors the PDS can send back so callers can decide what to do with them
+impl<E: std::fmt::Debug + std::fmt::Display> From<atrium_xrpc::Error<E>> for AtRepoSyncError {
+    fn from(err: atrium_xrpc::Error<E>) -> Self {
+        match err {
+            Authentication(_) => AtRepoSyncError::AuthErrorNeedToReLogin,
+            atrium_xrpc::Error::XrpcResponse(response) => {
+                let error_name = match &response.error {
+                    Some(XrpcErrorKind::Custom(err)) => err.to_string(),
+                    Some(XrpcErrorKind::Undefined(body)) => body.error.clone().unwrap_or_default(),
+                    None => String::new(),
+                };
+                //Custom errors are written as Name: message
+                let error_name = error_name.split(':').next().unwrap_or_default();
+                match (response.status.as_u16(), error_name) {
+                    (401, _) | (_, "ExpiredToken") | (_, "InvalidToken") => {
+                        AtRepoSyncError::AuthErrorNeedToReLogin
+                    }
+                    (429, _) => AtRepoSyncError::RateLimited,
+                    (_, "RecordNotFound") => AtRepoSyncError::RecordNotFound,
+                    (_, "InvalidSwap") => AtRepoSyncError::InvalidSwap,
+                    (400, "InvalidRequest") => {
+                        AtRepoSyncError::ValidationFailed(response.to_string())
+                    }
+                    (status, _) => AtRepoSyncError::PdsError {
+                        status,
+                        error: response.to_string(),
+                    },
+                }
+            }
+            //fetch only fails like this when it never got a response
+            atrium_xrpc::Error::HttpClient(_) => AtRepoSyncError::NetworkOffline,
+            //The request could not be built from what was given
+            err @ (atrium_xrpc::Error::HttpRequest(_) | atrium_xrpc::Error::SerdeHtmlForm(_)) => {
+                AtRepoSyncError::ValidationFailed(err.to_string())
+            }
+            //The response did not match the lexicon
+            err @ (atrium_xrpc::Error::SerdeJson(_)
+            | atrium_xrpc::Error::UnexpectedResponseType) => {
+                AtRepoSyncError::MalformedRecord(err.to_string())
+            }
+        }
+    }
+}
+
+impl From<StorageError> for AtRepoSyncError {
+    fn from(err: StorageError) -> Self {
+        match err {
+            StorageError::QuotaExceeded => AtRepoSyncError::QuotaExceeded,
+            err => AtRepoSyncError::LocalRepoError(err),
+        }
+    }
 }
 
 pub trait AtRepoSyncTrait {
@@ -56,14 +167,22 @@ impl std::fmt::Display for AtRepoSyncError {
         match self {
             AtRepoSyncError::LocalIsNewer => write!(f, "Local is newer"),
             AtRepoSyncError::RemoteIsNewer => write!(f, "Remote is newer"),
-            AtRepoSyncError::AtRepoCallError(err) => write!(f, "AtRepoCallError: {}", err),
-            AtRepoSyncError::Error(err) => write!(f, "ThereWasAnError: {}", err),
             AtRepoSyncError::LocalRepoError(err) => {
-                write!(f, "LocalRepoError: {}", err)
+                write!(f, "LocalRepoError: {}", err.to_string())
             }
             AtRepoSyncError::AuthErrorNeedToReLogin => {
                 write!(f, "There was an error with the auth, need to relogin")
             }
+            AtRepoSyncError::NetworkOffline => write!(f, "Could not reach the PDS"),
+            AtRepoSyncError::RateLimited => write!(f, "Rate limited by the PDS"),
+            AtRepoSyncError::RecordNotFound => write!(f, "Record not found"),
+            AtRepoSyncError::InvalidSwap => write!(f, "Record changed since it was read"),
+            AtRepoSyncError::ValidationFailed(err) => write!(f, "ValidationFailed: {}", err),
+            AtRepoSyncError::QuotaExceeded => write!(f, "IndexedDB quota exceeded"),
+            AtRepoSyncError::MalformedRecord(err) => write!(f, "MalformedRecord: {}", err),
+            AtRepoSyncError::PdsError { status, error } => {
+                write!(f, "PdsError {}: {}", status, error)
+            }
         }
     }
 }
@@ -98,11 +217,30 @@ impl AtRepoSync
         }
     }
 
+    /// A local only repo for a logged in account whose login has expired. Everything is saved to
+    /// their local stores as not synced, and sent to the AT repo once they login again
+    pub fn new_paused_repo(did: Did) -> Self {
+        Self {
+            client: None,
+            users_did: Some(did),
+        }
+    }
+
+    /// Whose local stores this repo reads and writes, None is the anonymous player
+    pub fn users_did(&self) -> Option<&Did> {
+        self.users_did.as_ref()
+    }
+
     /// Means that the repo can sync remotely to the AT repo and the user is logged in
     pub fn can_remote_sync(&self) -> bool {
         self.client.is_some() && self.users_did.is_some()
     }
 
+    /// Means the user is logged in but remote syncing is paused till they login again
+    pub fn is_paused(&self) -> bool {
+        self.client.is_none() && self.users_did.is_some()
+    }
+
     fn _set_synced_status<Record>(&self, record: Record, synced: bool) -> Option<KnownRecord>
     where
         Record: Serialize + Into<KnownRecord>,
@@ -132,18 +270,29 @@ impl AtRepoSync
                 record.sync_status.hash = format!("{:x}", hash);
                 Some(KnownRecord::Blue2048PlayerStats(record))
             }
+            KnownRecord::Blue2048PlayerAchievement(mut record) => {
+                record.sync_status.synced_with_at_repo = synced;
+                record.sync_status.updated_at = Datetime::now();
+                let string_data = serde_json::to_string(&record).unwrap();
+                let hash = const_xxh3(string_data.as_bytes());
+                record.sync_status.hash = format!("{:x}", hash);
+                Some(KnownRecord::Blue2048PlayerAchievement(record))
+            }
             //Not every record is a write or needs a hash set
             _ => None,
         }
     }
 
-    pub async fn get_remote_record<Record: std::convert::From<atrium_api::types::Unknown>>(
+    pub async fn get_remote_record<Record>(
         &self,
         collection: &str,
         key: RecordKey,
-    ) -> Result<Record, AtRepoSyncError> {
+    ) -> Result<Record, AtRepoSyncError>
+    where
+        Record: TryFrom<Unknown, Error = atrium_api::error::Error>,
+    {
         match &self.client {
-            None => Err(AtRepoSyncError::Error("No client".to_string())),
+            None => Err(AtRepoSyncError::AuthErrorNeedToReLogin),
             Some(client) => {
                 match client
                     .api
@@ -161,8 +310,9 @@ impl AtRepoSync
                     )
                     .await
                 {
-                    Ok(result) => Ok(result.value.clone().into()),
-                    Err(err) => Err(AtRepoSyncError::AtRepoCallError(err.to_string())),
+                    Ok(result) => Record::try_from(result.value.clone())
+                        .map_err(|err| AtRepoSyncError::MalformedRecord(err.to_string())),
+                    Err(err) => Err(err.into()),
                 }
             }
         }
@@ -173,6 +323,7 @@ impl AtRepoSync
     ) -> Result<player::profile::RecordData, AtRepoSyncError> {
         let mut new_user_profile = player::profile::RecordData {
             created_at: Datetime::now(),
+            default_social: None,
             solo_play: false,
             sync_status: _2048::defs::SyncStatusData {
                 created_at: Datetime::now(),
@@ -214,17 +365,21 @@ impl AtRepoSync
                         synced_with_at_repo = true;
                     }
                     Err(err) => {
-                        //Just going to log errors "quietly" as I figure out how to handle them
                         log::error!("{:?}", err);
+                        match AtRepoSyncError::from(err) {
+                            //Offline or rate limited, it is kept locally as not synced and sent later
+                            err if err.is_retryable() => {}
+                            err => return Err(err),
+                        }
                     }
                 }
             }
         }
 
-        let db = match Database::open(DB_NAME).await {
+        let db = match open_player_db(self.users_did.as_ref()).await {
             Ok(db) => db,
             Err(err) => {
-                return Err(AtRepoSyncError::Error(err.to_string()));
+                return Err(AtRepoSyncError::from(err));
             }
         };
 
@@ -244,34 +399,107 @@ impl AtRepoSync
         .await
         {
             Ok(_) => Ok(new_user_profile),
-            Err(err) => Err(AtRepoSyncError::Error(err.to_string())),
+            Err(err) => Err(AtRepoSyncError::from(err)),
         }
     }
 
     pub async fn get_local_player_profile(
         &self,
     ) -> Result<Option<player::profile::RecordData>, AtRepoSyncError> {
-        let db = match Database::open(DB_NAME).await {
+        let db = match open_player_db(self.users_did.as_ref()).await {
             Ok(db) => db,
             Err(err) => {
-                return Err(AtRepoSyncError::Error(err.to_string()));
+                return Err(AtRepoSyncError::from(err));
             }
         };
 
-        match object_get::<player::profile::RecordData>(db, PROFILE_STORE, SELF_KEY).await {
-            Ok(profile) => Ok(profile),
+        object_get::<player::profile::RecordData>(db, PROFILE_STORE, SELF_KEY)
+            .await
+            .map_err(AtRepoSyncError::from)
+    }
+
+    pub async fn update_a_player_profile(
+        &self,
+        mut new_profile: player::profile::RecordData,
+    ) -> Result<(), AtRepoSyncError> {
+        new_profile.sync_status.updated_at = Datetime::now();
+        let string_data = serde_json::to_string(&new_profile).unwrap();
+        let hash = const_xxh3(string_data.as_bytes());
+        new_profile.sync_status.hash = format!("{:x}", hash);
+
+        let new_profile_record: KnownRecord = new_profile.clone().into();
+        let mut synced_with_at_repo = false;
+        match &self.client {
+            None => {}
+            Some(client) => {
+                let put_request = client
+                    .api
+                    .com
+                    .atproto
+                    .repo
+                    .put_record(
+                        atrium_api::com::atproto::repo::put_record::InputData {
+                            collection: blue::_2048::player::Profile::NSID.parse().unwrap(),
+                            record: new_profile_record.into(),
+                            //TODO unwrap is not best, but ideally if we have a client we should have a did
+                            repo: AtIdentifier::Did(self.users_did.clone().unwrap()),
+                            rkey: SELF_KEY.parse().unwrap(),
+                            swap_commit: None,
+                            swap_record: None,
+                            validate: None,
+                        }
+                        .into(),
+                    )
+                    .await;
+                match put_request {
+                    Ok(_) => {
+                        synced_with_at_repo = true;
+                    }
+                    Err(err) => {
+                        log::error!("{:?}", err);
+                        match AtRepoSyncError::from(err) {
+                            //Offline or rate limited, it is kept locally as not synced and sent later
+                            err if err.is_retryable() => {}
+                            err => return Err(err),
+                        }
+                    }
+                }
+            }
+        }
+
+        let db = match open_player_db(self.users_did.as_ref()).await {
+            Ok(db) => db,
             Err(err) => {
-                return Err(AtRepoSyncError::Error(err.to_string()));
+                return Err(AtRepoSyncError::from(err));
             }
+        };
+
+        new_profile.sync_status.synced_with_at_repo = synced_with_at_repo;
+        //Since it did not sync with the at repo we need to update the hash
+        if !synced_with_at_repo {
+            let string_data = serde_json::to_string(&new_profile).unwrap();
+            let hash = const_xxh3(string_data.as_bytes());
+            new_profile.sync_status.hash = format!("{:x}", hash);
+        }
+        match transaction_put(db, new_profile, PROFILE_STORE, Some(SELF_KEY.to_string())).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(AtRepoSyncError::from(err)),
         }
     }
 
     pub async fn sync_profiles(&self) -> Result<(), AtRepoSyncError> {
         let local_profile = self.get_local_player_profile().await?;
-        let db = match Database::open(DB_NAME).await {
+        //Nothing to sync with, just makes sure there is a local one to update
+        if !self.can_remote_sync() {
+            if local_profile.is_none() {
+                self.create_a_new_player_profile().await?;
+            }
+            return Ok(());
+        }
+        let db = match open_player_db(self.users_did.as_ref()).await {
             Ok(db) => db,
             Err(err) => {
-                return Err(AtRepoSyncError::Error(err.to_string()));
+                return Err(AtRepoSyncError::from(err));
             }
         };
 
@@ -285,6 +513,8 @@ impl AtRepoSync
             // There is a remote profile
             Ok(remote_profile) => match local_profile {
                 //There is a local profile and a remote one
+                //Changes made offline or while logged out only, they go up on the next update
+                Some(local_profile) if !local_profile.sync_status.synced_with_at_repo => Ok(()),
                 Some(local_profile) => {
                     if local_profile.sync_status.hash != remote_profile.sync_status.hash {
                         //Just taking remote over all rn
@@ -295,7 +525,7 @@ impl AtRepoSync
                             Some(SELF_KEY.to_string()),
                         )
                         .await
-                        .map_err(|err| AtRepoSyncError::LocalRepoError(err.to_string()))
+                        .map_err(AtRepoSyncError::from)
                     } else {
                         Ok(())
                     }
@@ -308,14 +538,15 @@ impl AtRepoSync
                     Some(SELF_KEY.to_string()),
                 )
                 .await
-                .map_err(|err| AtRepoSyncError::LocalRepoError(err.to_string())),
+                .map_err(AtRepoSyncError::from),
             },
-            //There is no remote profile or was unable to get it
-            Err(_err) => {
-                //Not right but assuming I just create a new one
+            //There is no remote one yet
+            Err(AtRepoSyncError::RecordNotFound) => {
                 self.create_a_new_player_profile().await?;
                 Ok(())
             }
+            //Anything else would mean overwriting the local one without being able to save it remotely
+            Err(err) => Err(err),
         }
     }
 
@@ -323,23 +554,7 @@ impl AtRepoSync
     pub async fn create_a_new_player_stats(
         &self,
     ) -> Result<player::stats::RecordData, AtRepoSyncError> {
-        let mut new_player_stats = player::stats::RecordData {
-            average_score: 0,
-            created_at: Datetime::now(),
-            games_played: 0,
-            highest_number_block: 0,
-            highest_score: 0,
-            least_moves_to_find_twenty_forty_eight: 0,
-            sync_status: _2048::defs::SyncStatusData {
-                created_at: Datetime::now(),
-                hash: "".to_string(),
-                synced_with_at_repo: true,
-                updated_at: Datetime::now(),
-            }
-            .into(),
-            times_twenty_forty_eight_been_found: 0,
-            total_score: 0,
-        };
+        let mut new_player_stats = empty_player_stats();
         let string_data = serde_json::to_string(&new_player_stats).unwrap();
         let hash = const_xxh3(string_data.as_bytes());
         new_player_stats.sync_status.hash = format!("{:x}", hash);
@@ -372,17 +587,21 @@ impl AtRepoSync
                         synced_with_at_repo = true;
                     }
                     Err(err) => {
-                        //Just going to log errors "quietly" as I figure out how to handle them
                         log::error!("{:?}", err);
+                        match AtRepoSyncError::from(err) {
+                            //Offline or rate limited, it is kept locally as not synced and sent later
+                            err if err.is_retryable() => {}
+                            err => return Err(err),
+                        }
                     }
                 }
             }
         }
 
-        let db = match Database::open(DB_NAME).await {
+        let db = match open_player_db(self.users_did.as_ref()).await {
             Ok(db) => db,
             Err(err) => {
-                return Err(AtRepoSyncError::Error(err.to_string()));
+                return Err(AtRepoSyncError::from(err));
             }
         };
 
@@ -402,7 +621,7 @@ impl AtRepoSync
         .await
         {
             Ok(_) => Ok(new_player_stats),
-            Err(err) => Err(AtRepoSyncError::Error(err.to_string())),
+            Err(err) => Err(AtRepoSyncError::from(err)),
         }
     }
 
@@ -411,7 +630,12 @@ impl AtRepoSync
         mut new_stats: player::stats::RecordData,
     ) -> Result<(), AtRepoSyncError> {
         //TODO probably not most efficient but call a sync before
-        self.sync_stats().await?;
+        match self.sync_stats().await {
+            Ok(_) => {}
+            //Saved locally as not synced and sent on a later sync
+            Err(err) if err.is_retryable() => log::warn!("Could not sync stats first: {}", err),
+            Err(err) => return Err(err),
+        }
         new_stats.sync_status.updated_at = Datetime::now();
         let string_data = serde_json::to_string(&new_stats).unwrap();
         let hash = const_xxh3(string_data.as_bytes());
@@ -447,23 +671,20 @@ impl AtRepoSync
                     }
                     Err(err) => {
                         log::error!("{:?}", err);
-                        match atrium_xrpc::Error::from(err) {
-                            Authentication(_) => {
-                                return Err(AtRepoSyncError::AuthErrorNeedToReLogin);
-                            }
-                            _ => {
-                                //Just going to log errors "quietly" as I figure out how to handle them
-                            }
+                        match AtRepoSyncError::from(err) {
+                            //Offline or rate limited, it is kept locally as not synced and sent later
+                            err if err.is_retryable() => {}
+                            err => return Err(err),
                         }
                     }
                 }
             }
         }
 
-        let db = match Database::open(DB_NAME).await {
+        let db = match open_player_db(self.users_did.as_ref()).await {
             Ok(db) => db,
             Err(err) => {
-                return Err(AtRepoSyncError::Error(err.to_string()));
+                return Err(AtRepoSyncError::from(err));
             }
         };
 
@@ -483,32 +704,39 @@ impl AtRepoSync
         .await
         {
             Ok(_) => Ok(()),
-            Err(err) => Err(AtRepoSyncError::Error(err.to_string())),
+            Err(err) => Err(AtRepoSyncError::from(err)),
         }
     }
 
     pub async fn get_local_player_stats(
         &self,
     ) -> Result<Option<player::stats::RecordData>, AtRepoSyncError> {
-        let db = match Database::open(DB_NAME).await {
+        let db = match open_player_db(self.users_did.as_ref()).await {
             Ok(db) => db,
             Err(err) => {
-                return Err(AtRepoSyncError::Error(err.to_string()));
+                return Err(AtRepoSyncError::from(err));
             }
         };
 
         match object_get::<player::stats::RecordData>(db, STATS_STORE, SELF_KEY).await {
             Ok(stats) => Ok(stats),
-            Err(err) => Err(AtRepoSyncError::Error(err.to_string())),
+            Err(err) => Err(AtRepoSyncError::from(err)),
         }
     }
 
     pub async fn sync_stats(&self) -> Result<(), AtRepoSyncError> {
         let local_stats = self.get_local_player_stats().await?;
-        let db = match Database::open(DB_NAME).await {
+        //Nothing to sync with, just makes sure there is a local one to update
+        if !self.can_remote_sync() {
+            if local_stats.is_none() {
+                self.create_a_new_player_stats().await?;
+            }
+            return Ok(());
+        }
+        let db = match open_player_db(self.users_did.as_ref()).await {
             Ok(db) => db,
             Err(err) => {
-                return Err(AtRepoSyncError::Error(err.to_string()));
+                return Err(AtRepoSyncError::from(err));
             }
         };
 
@@ -522,6 +750,8 @@ impl AtRepoSync
             // There is a remote profile
             Ok(remote_stats) => match local_stats {
                 //There is a local profile and a remote one
+                //Changes made offline or while logged out only, they go up on the next update
+                Some(local_profile) if !local_profile.sync_status.synced_with_at_repo => Ok(()),
                 Some(local_profile) => {
                     if local_profile.sync_status.hash != remote_stats.sync_status.hash {
                         //Just taking remote over all rn
@@ -532,7 +762,7 @@ impl AtRepoSync
                             Some(SELF_KEY.to_string()),
                         )
                         .await
-                        .map_err(|err| AtRepoSyncError::LocalRepoError(err.to_string()))
+                        .map_err(AtRepoSyncError::from)
                     } else {
                         Ok(())
                     }
@@ -544,17 +774,207 @@ impl AtRepoSync
                     Some(SELF_KEY.to_string()),
                 )
                 .await
-                .map_err(|err| AtRepoSyncError::LocalRepoError(err.to_string())),
+                .map_err(AtRepoSyncError::from),
             },
-            //There is no remote profile or was unable to get it
-            Err(_err) => {
-                //Not right but assuming I just create a new one
+            //There is no remote one yet
+            Err(AtRepoSyncError::RecordNotFound) => {
                 self.create_a_new_player_stats().await?;
                 Ok(())
             }
+            //Anything else would mean overwriting the local one without being able to save it remotely
+            Err(err) => Err(err),
+        }
+    }
+
+    pub async fn get_local_achievements(
+        &self,
+    ) -> Result<Vec<player::achievement::RecordData>, AtRepoSyncError> {
+        let db = match open_player_db(self.users_did.as_ref()).await {
+            Ok(db) => db,
+            Err(err) => {
+                return Err(AtRepoSyncError::from(err));
+            }
+        };
+
+        object_get_all::<player::achievement::RecordData>(db, ACHIEVEMENTS_STORE)
+            .await
+            .map_err(AtRepoSyncError::from)
+    }
+
+    /// Puts an achievement in the AT repo using its id as the record key, so unlocking it twice just overwrites it.
+    /// Returns if it made it to the AT repo
+    async fn put_remote_achievement(
+        &self,
+        achievement: &player::achievement::RecordData,
+    ) -> Result<bool, AtRepoSyncError> {
+        let Some(client) = &self.client else {
+            return Ok(false);
+        };
+        let rkey = achievement.achievement.parse().map_err(|err: &str| {
+            AtRepoSyncError::MalformedRecord(format!(
+                "{} is not a valid record key: {}",
+                achievement.achievement, err
+            ))
+        })?;
+        let achievement_record: KnownRecord = achievement.clone().into();
+        let put_request = client
+            .api
+            .com
+            .atproto
+            .repo
+            .put_record(
+                atrium_api::com::atproto::repo::put_record::InputData {
+                    collection: blue::_2048::player::Achievement::NSID.parse().unwrap(),
+                    record: achievement_record.into(),
+                    //TODO unwrap is not best, but ideally if we have a client we should have a did
+                    repo: AtIdentifier::Did(self.users_did.clone().unwrap()),
+                    rkey,
+                    swap_commit: None,
+                    swap_record: None,
+                    validate: None,
+                }
+                .into(),
+            )
+            .await;
+        match put_request {
+            Ok(_) => Ok(true),
+            Err(err) => {
+                log::error!("{:?}", err);
+                match AtRepoSyncError::from(err) {
+                    //Offline or rate limited, it is kept locally as not synced and sent later
+                    err if err.is_retryable() => Ok(false),
+                    err => Err(err),
+                }
+            }
+        }
+    }
+
+    /// Saves a newly unlocked achievement locally and to the AT repo if logged in
+    pub async fn unlock_an_achievement(
+        &self,
+        achievement_id: &str,
+        game: Option<RecordKey>,
+    ) -> Result<player::achievement::RecordData, AtRepoSyncError> {
+        let mut new_achievement = player::achievement::RecordData {
+            achievement: achievement_id.to_string(),
+            created_at: Datetime::now(),
+            game,
+            sync_status: _2048::defs::SyncStatusData {
+                created_at: Datetime::now(),
+                hash: "".to_string(),
+                synced_with_at_repo: true,
+                updated_at: Datetime::now(),
+            }
+            .into(),
+        };
+        let string_data = serde_json::to_string(&new_achievement).unwrap();
+        let hash = const_xxh3(string_data.as_bytes());
+        new_achievement.sync_status.hash = format!("{:x}", hash);
+
+        let synced_with_at_repo = self.put_remote_achievement(&new_achievement).await?;
+
+        let db = match open_player_db(self.users_did.as_ref()).await {
+            Ok(db) => db,
+            Err(err) => {
+                return Err(AtRepoSyncError::from(err));
+            }
+        };
+
+        new_achievement.sync_status.synced_with_at_repo = synced_with_at_repo;
+        //Since it did not sync with the at repo we need to update the hash
+        if !synced_with_at_repo {
+            let string_data = serde_json::to_string(&new_achievement).unwrap();
+            let hash = const_xxh3(string_data.as_bytes());
+            new_achievement.sync_status.hash = format!("{:x}", hash);
+        }
+        match transaction_put(db, new_achievement.clone(), ACHIEVEMENTS_STORE, None).await {
+            Ok(_) => Ok(new_achievement),
+            Err(err) => Err(AtRepoSyncError::from(err)),
         }
     }
 
+    /// Saves achievements unlocked on other devices locally and pushes up any that were unlocked while offline
+    pub async fn sync_achievements(&self) -> Result<(), AtRepoSyncError> {
+        let Some(client) = &self.client else {
+            return Ok(());
+        };
+        let local_achievements = self.get_local_achievements().await?;
+
+        let remote_achievements = match client
+            .api
+            .com
+            .atproto
+            .repo
+            .list_records(
+                atrium_api::com::atproto::repo::list_records::ParametersData {
+                    collection: blue::_2048::player::Achievement::NSID.parse().unwrap(),
+                    cursor: None,
+                    limit: LimitedNonZeroU8::try_from(100_u8).ok(),
+                    //HACK unwrapping the did for now since we know we have it since we have a client
+                    repo: AtIdentifier::Did(self.users_did.clone().unwrap()),
+                    reverse: None,
+                }
+                .into(),
+            )
+            .await
+        {
+            Ok(result) => result
+                .records
+                .iter()
+                .filter_map(|record| {
+                    player::achievement::RecordData::try_from(record.value.clone())
+                        .inspect_err(|err| {
+                            log::error!("Skipping malformed achievement {}: {}", record.uri, err)
+                        })
+                        .ok()
+                })
+                .collect::<Vec<_>>(),
+            Err(err) => {
+                log::error!("{:?}", err);
+                return Err(err.into());
+            }
+        };
+
+        for remote_achievement in &remote_achievements {
+            if local_achievements
+                .iter()
+                .any(|local| local.achievement == remote_achievement.achievement)
+            {
+                continue;
+            }
+            let db = open_player_db(self.users_did.as_ref())
+                .await
+                .map_err(AtRepoSyncError::from)?;
+            transaction_put(db, remote_achievement.clone(), ACHIEVEMENTS_STORE, None)
+                .await
+                .map_err(AtRepoSyncError::from)?;
+        }
+
+        for local_achievement in local_achievements {
+            if local_achievement.sync_status.synced_with_at_repo
+                || remote_achievements
+                    .iter()
+                    .any(|remote| remote.achievement == local_achievement.achievement)
+            {
+                continue;
+            }
+            if !self.put_remote_achievement(&local_achievement).await? {
+                continue;
+            }
+            if let Some(KnownRecord::Blue2048PlayerAchievement(synced)) =
+                self._set_synced_status(local_achievement, true)
+            {
+                let db = open_player_db(self.users_did.as_ref())
+                    .await
+                    .map_err(AtRepoSyncError::from)?;
+                transaction_put(db, synced.data, ACHIEVEMENTS_STORE, None)
+                    .await
+                    .map_err(AtRepoSyncError::from)?;
+            }
+        }
+        Ok(())
+    }
+
     pub async fn create_a_new_game(
         &self,
         mut new_game: blue::_2048::game::RecordData,
@@ -594,26 +1014,20 @@ impl AtRepoSync
                     }
                     Err(err) => {
                         log::error!("{:?}", err);
-                        match atrium_xrpc::Error::from(err) {
-                            Authentication(_) => {
-                                return Err(AtRepoSyncError::AuthErrorNeedToReLogin);
-                            }
-                            _ => {
-                                return Err(AtRepoSyncError::Error(String::from(
-                                    "Error saving the game remotely",
-                                )));
-                                //Just going to log errors "quietly" as I figure out how to handle them
-                            }
+                        match AtRepoSyncError::from(err) {
+                            //Offline or rate limited, it is kept locally as not synced and sent later
+                            err if err.is_retryable() => {}
+                            err => return Err(err),
                         }
                     }
                 }
             }
         }
 
-        let db = match Database::open(DB_NAME).await {
+        let db = match open_player_db(self.users_did.as_ref()).await {
             Ok(db) => db,
             Err(err) => {
-                return Err(AtRepoSyncError::Error(err.to_string()));
+                return Err(AtRepoSyncError::from(err));
             }
         };
 
@@ -633,7 +1047,7 @@ impl AtRepoSync
         };
         match transaction_put(db, local_game_record.clone(), GAME_STORE, None).await {
             Ok(_) => Ok(()),
-            Err(err) => Err(AtRepoSyncError::Error(err.to_string())),
+            Err(err) => Err(AtRepoSyncError::from(err)),
         }
     }
 
@@ -650,9 +1064,7 @@ impl AtRepoSync
     > {
         let client = match self.client.as_ref() {
             None => {
-                return Err(AtRepoSyncError::Error(String::from(
-                    "There was no client setup.",
-                )));
+                return Err(AtRepoSyncError::AuthErrorNeedToReLogin);
             }
             Some(client) => client,
         };
@@ -687,23 +1099,59 @@ impl AtRepoSync
                     result
                         .records
                         .iter()
-                        .map(|record| {
+                        .filter_map(|record| {
+                            //One bad record should not stop the rest of the history from loading
                             let rkey = parse_record_key(&record.uri)
-                                .map_err(|e| AtRepoSyncError::Error(e))
-                                .unwrap();
-                            Rc::new(RecordStorageWrapper {
+                                .inspect_err(|err| {
+                                    log::error!("Skipping game {}: {}", record.uri, err)
+                                })
+                                .ok()?;
+                            let game = blue::_2048::game::RecordData::try_from(record.value.clone())
+                                .inspect_err(|err| {
+                                    log::error!("Skipping malformed game {}: {}", record.uri, err)
+                                })
+                                .ok()?;
+                            Some(Rc::new(RecordStorageWrapper {
                                 rkey,
-                                record: record.value.clone().into(),
+                                record: game,
                                 //Leaving empty for now. We can get the hash, but atm we're not syncing
                                 //remote to local so would affect that
                                 index_hash: "".to_string(),
-                            })
+                            }))
                         })
                         .collect::<Vec<Rc<RecordStorageWrapper<blue::_2048::game::RecordData>>>>(),
                 ), result.cursor.clone()))
             }
-            Err(err) => Err(AtRepoSyncError::AtRepoCallError(err.to_string())),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Stats for someone who has not finished a game yet
+pub fn empty_player_stats() -> player::stats::RecordData {
+    player::stats::RecordData {
+        average_score: 0,
+        created_at: Datetime::now(),
+        current_streak: None,
+        direction_counts: None,
+        fastest_twenty_forty_eight_ms: None,
+        games_played: 0,
+        highest_number_block: 0,
+        highest_score: 0,
+        least_moves_to_find_twenty_forty_eight: 0,
+        longest_streak: None,
+        mode_stats: None,
+        sync_status: _2048::defs::SyncStatusData {
+            created_at: Datetime::now(),
+            hash: "".to_string(),
+            synced_with_at_repo: true,
+            updated_at: Datetime::now(),
         }
+        .into(),
+        times_twenty_forty_eight_been_found: 0,
+        total_moves: None,
+        total_play_time_ms: None,
+        total_score: 0,
     }
 }
 
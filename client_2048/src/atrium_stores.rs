@@ -33,6 +33,13 @@ impl Display for AuthStoreError {
 
 impl StdError for AuthStoreError {}
 
+async fn open_db() -> Result<Database, AuthStoreError> {
+    Database::open(DB_NAME).await.map_err(|e| {
+        log::error!("Error opening the database: {}", e);
+        AuthStoreError::DatabaseError(e.to_string())
+    })
+}
+
 ///Persistent session store in sqlite
 impl SessionStore for IndexDBSessionStore {}
 
@@ -54,7 +61,7 @@ where
     type Error = AuthStoreError;
     async fn get(&self, key: &K) -> Result<Option<V>, Self::Error> {
         let did = key.as_ref().to_string();
-        let db = Database::open(DB_NAME).await.unwrap();
+        let db = open_db().await?;
         match object_get::<V>(db.clone(), SESSIONS_STORE, &*did).await {
             Ok(Some(session)) => Ok(Some(session)),
             Ok(None) => Err(AuthStoreError::NoSessionFound),
@@ -67,7 +74,7 @@ where
 
     async fn set(&self, key: K, value: V) -> Result<(), Self::Error> {
         let did = key.as_ref().to_string();
-        let db = Database::open(DB_NAME).await.unwrap();
+        let db = open_db().await?;
         transaction_put(db.clone(), &value, SESSIONS_STORE, Some(did))
             .await
             .map_err(|e| AuthStoreError::DatabaseError(e.to_string()))
@@ -75,14 +82,14 @@ where
 
     async fn del(&self, _key: &K) -> Result<(), Self::Error> {
         let key = _key.as_ref().to_string();
-        let db = Database::open(DB_NAME).await.unwrap();
+        let db = open_db().await?;
         object_delete(db.clone(), SESSIONS_STORE, &*key)
             .await
             .map_err(|e| AuthStoreError::DatabaseError(e.to_string()))
     }
 
     async fn clear(&self) -> Result<(), Self::Error> {
-        let db = Database::open(DB_NAME).await.unwrap();
+        let db = open_db().await?;
         clear_store(db.clone(), SESSIONS_STORE)
             .await
             .map_err(|e| AuthStoreError::DatabaseError(e.to_string()))
@@ -107,7 +114,7 @@ where
     type Error = AuthStoreError;
     async fn get(&self, key: &K) -> Result<Option<V>, Self::Error> {
         let key = key.as_ref().to_string();
-        let db = Database::open(DB_NAME).await.unwrap();
+        let db = open_db().await?;
         match object_get::<V>(db.clone(), STATE_STORE, &*key).await {
             Ok(Some(session)) => Ok(Some(session)),
             Ok(None) => Err(AuthStoreError::NoSessionFound),
@@ -120,7 +127,7 @@ where
 
     async fn set(&self, key: K, value: V) -> Result<(), Self::Error> {
         let did = key.as_ref().to_string();
-        let db = Database::open(DB_NAME).await.unwrap();
+        let db = open_db().await?;
         match transaction_put(db.clone(), &value, STATE_STORE, Some(did))
             .await
             .map_err(|e| AuthStoreError::DatabaseError(e.to_string()))
@@ -132,14 +139,14 @@ where
 
     async fn del(&self, _key: &K) -> Result<(), Self::Error> {
         let key = _key.as_ref().to_string();
-        let db = Database::open(DB_NAME).await.unwrap();
+        let db = open_db().await?;
         object_delete(db.clone(), STATE_STORE, &*key)
             .await
             .map_err(|e| AuthStoreError::DatabaseError(e.to_string()))
     }
 
     async fn clear(&self) -> Result<(), Self::Error> {
-        let db = Database::open(DB_NAME).await.unwrap();
+        let db = open_db().await?;
         clear_store(db.clone(), STATE_STORE)
             .await
             .map_err(|e| AuthStoreError::DatabaseError(e.to_string()))
@@ -0,0 +1,346 @@
+use crate::idb::{GAME_STORE, RecordStorageWrapper, object_get_index, open_player_db};
+use crate::oauth_client::oauth_client;
+use atrium_api::agent::Agent;
+use atrium_api::app::bsky::embed::external;
+use atrium_api::app::bsky::feed::post;
+use atrium_api::app::bsky::richtext::facet;
+use atrium_api::types::string::{AtIdentifier, Datetime, Did};
+use atrium_api::types::{BlobRef, Collection, TryIntoUnknown, Union};
+use atrium_xrpc::Error::Authentication;
+use std::fmt::Display;
+use twothousand_forty_eight::board::Board;
+use types_2048::blue::_2048::game;
+use types_2048::board_card::{
+    BACKGROUND_COLOR, BOARD_COLOR, BOARD_RADIUS, CARD_HEIGHT, CARD_PADDING, CARD_WIDTH, FONT_FAMILY,
+    FOOTER_COLOR, FOOTER_TEXT, SCORE_LABEL_TEXT, SCORE_TEXT, TEXT_COLOR, TILE_RADIUS, TITLE_TEXT,
+    TileLayout, board_size, text_x, tile_colors,
+};
+use unicode_segmentation::UnicodeSegmentation;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+
+/// Longest a post can be, in graphemes like Bluesky counts them
+pub const MAX_POST_LENGTH: usize = 300;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PostError {
+    AuthErrorNeedToReLogin,
+    TooLong,
+    Error(String),
+}
+
+impl Display for PostError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PostError::AuthErrorNeedToReLogin => {
+                write!(f, "There was an error with the auth, need to relogin")
+            }
+            PostError::TooLong => write!(f, "Posts can be at most {} characters", MAX_POST_LENGTH),
+            PostError::Error(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// The link card shown under the post
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalLink {
+    pub uri: String,
+    pub title: String,
+    pub description: String,
+}
+
+/// What the player is about to post, after they have edited it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostDraft {
+    pub text: String,
+    pub link: ExternalLink,
+    /// A data url of a png to use as the link card's picture
+    pub image: Option<String>,
+}
+
+/// How long Bluesky considers the text to be
+pub fn post_length(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+/// Swaps the last link in the text that is exactly `from` for `to`. Other links, including longer
+/// ones that start with `from`, are left alone
+pub fn replace_link(text: &str, from: &str, to: &str) -> String {
+    let last_link = find_facets(text)
+        .into_iter()
+        .rfind(|(start, end, feature)| {
+            matches!(feature, FacetText::Link(_)) && &text[*start..*end] == from
+        });
+    match last_link {
+        Some((start, end, _)) => format!("{}{}{}", &text[..start], to, &text[end..]),
+        None => text.to_string(),
+    }
+}
+
+/// Link to the game on 2048.blue if it has been saved, so the card unfurls with the board
+pub async fn saved_game_link(did: &Did, game_hash: &str) -> Option<String> {
+    let db = open_player_db(Some(did)).await.ok()?;
+    let saved: RecordStorageWrapper<game::RecordData> =
+        object_get_index(db, GAME_STORE, game_hash).await.ok()??;
+    //Only games in the AT repo can be seen by the appview
+    saved
+        .record
+        .sync_status
+        .synced_with_at_repo
+        .then(|| format!("https://2048.blue/game/{}/{}", did.as_str(), saved.rkey.as_str()))
+}
+
+/// Creates an app.bsky.feed.post in the player's repo and returns its at uri
+pub async fn create_post(did: Did, draft: PostDraft) -> Result<String, PostError> {
+    if post_length(&draft.text) > MAX_POST_LENGTH {
+        return Err(PostError::TooLong);
+    }
+    let session = oauth_client()
+        .restore(&did)
+        .await
+        .map_err(|_| PostError::AuthErrorNeedToReLogin)?;
+    let agent = Agent::new(session);
+
+    let mut facets = vec![];
+    for (byte_start, byte_end, feature) in find_facets(&draft.text) {
+        let feature = match feature {
+            FacetText::Link(uri) => facet::MainFeaturesItem::Link(Box::new(
+                facet::LinkData { uri }.into(),
+            )),
+            FacetText::Mention(handle) => {
+                //Mentions of handles that do not resolve are left as plain text
+                let Ok(handle) = handle.parse() else {
+                    continue;
+                };
+                let Ok(resolved) = agent
+                    .api
+                    .com
+                    .atproto
+                    .identity
+                    .resolve_handle(
+                        atrium_api::com::atproto::identity::resolve_handle::ParametersData {
+                            handle,
+                        }
+                        .into(),
+                    )
+                    .await
+                else {
+                    continue;
+                };
+                facet::MainFeaturesItem::Mention(Box::new(
+                    facet::MentionData {
+                        did: resolved.did.clone(),
+                    }
+                    .into(),
+                ))
+            }
+        };
+        facets.push(
+            facet::MainData {
+                features: vec![Union::Refs(feature)],
+                index: facet::ByteSliceData {
+                    byte_end,
+                    byte_start,
+                }
+                .into(),
+            }
+            .into(),
+        );
+    }
+
+    let thumb = match draft.image {
+        None => None,
+        Some(data_url) => Some(upload_image(&agent, &data_url).await?),
+    };
+    let embed = external::MainData {
+        external: external::ExternalData {
+            description: draft.link.description,
+            thumb,
+            title: draft.link.title,
+            uri: draft.link.uri,
+        }
+        .into(),
+    };
+
+    let record = post::Record::from(post::RecordData {
+        created_at: Datetime::now(),
+        embed: Some(Union::Refs(post::RecordEmbedRefs::AppBskyEmbedExternalMain(
+            Box::new(embed.into()),
+        ))),
+        entities: None,
+        facets: (!facets.is_empty()).then_some(facets),
+        labels: None,
+        langs: None,
+        reply: None,
+        tags: None,
+        text: draft.text,
+    })
+    .try_into_unknown()
+    .map_err(|err| PostError::Error(err.to_string()))?;
+
+    match agent
+        .api
+        .com
+        .atproto
+        .repo
+        .create_record(
+            atrium_api::com::atproto::repo::create_record::InputData {
+                collection: atrium_api::app::bsky::feed::Post::NSID.parse().unwrap(),
+                record,
+                repo: AtIdentifier::Did(did),
+                rkey: None,
+                swap_commit: None,
+                validate: None,
+            }
+            .into(),
+        )
+        .await
+    {
+        Ok(output) => Ok(output.uri.clone()),
+        Err(Authentication(_)) => Err(PostError::AuthErrorNeedToReLogin),
+        Err(err) => Err(PostError::Error(err.to_string())),
+    }
+}
+
+async fn upload_image<T>(agent: &Agent<T>, data_url: &str) -> Result<BlobRef, PostError>
+where
+    T: atrium_api::agent::SessionManager + Send + Sync,
+{
+    let bytes = data_url_to_bytes(data_url)?;
+    match agent.api.com.atproto.repo.upload_blob(bytes).await {
+        Ok(output) => Ok(output.blob.clone()),
+        Err(Authentication(_)) => Err(PostError::AuthErrorNeedToReLogin),
+        Err(err) => Err(PostError::Error(err.to_string())),
+    }
+}
+
+fn data_url_to_bytes(data_url: &str) -> Result<Vec<u8>, PostError> {
+    let (_, base64) = data_url
+        .split_once(",")
+        .ok_or_else(|| PostError::Error(String::from("Not a data url")))?;
+    let decoded = gloo_utils::window()
+        .atob(base64)
+        .map_err(|err| PostError::Error(format!("{:?}", err)))?;
+    //atob gives back one char per byte
+    Ok(decoded.chars().map(|byte| byte as u8).collect())
+}
+
+enum FacetText {
+    Link(String),
+    Mention(String),
+}
+
+/// Finds links and @mentions in the text with their utf8 byte ranges
+fn find_facets(text: &str) -> Vec<(usize, usize, FacetText)> {
+    let mut found = vec![];
+    let mut word_start = None;
+    //Adds a space at the end so the last word is checked too
+    for (index, character) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (character.is_whitespace(), word_start) {
+            (false, None) => word_start = Some(index),
+            (true, Some(start)) => {
+                word_start = None;
+                let word = text[start..index]
+                    .trim_end_matches(['.', ',', '!', '?', ':', ';', ')']);
+                let end = start + word.len();
+                if word.starts_with("https://") || word.starts_with("http://") {
+                    found.push((start, end, FacetText::Link(word.to_string())));
+                } else if let Some(handle) = word.strip_prefix('@') {
+                    if handle.contains('.') {
+                        found.push((start, end, FacetText::Mention(handle.to_string())));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+/// Draws the board and score on a canvas and returns it as a png data url. Laid out like the
+/// appview's link preview card
+pub fn render_board_image(board: &Board, score: usize) -> Result<String, String> {
+    let canvas = gloo_utils::document()
+        .create_element("canvas")
+        .map_err(|err| format!("{:?}", err))?
+        .dyn_into::<HtmlCanvasElement>()
+        .map_err(|_| String::from("Could not create a canvas"))?;
+    canvas.set_width(CARD_WIDTH as u32);
+    canvas.set_height(CARD_HEIGHT as u32);
+    let context = canvas
+        .get_context("2d")
+        .map_err(|err| format!("{:?}", err))?
+        .ok_or_else(|| String::from("No 2d context"))?
+        .dyn_into::<CanvasRenderingContext2d>()
+        .map_err(|_| String::from("No 2d context"))?;
+
+    context.set_fill_style_str(BACKGROUND_COLOR);
+    context.fill_rect(0.0, 0.0, CARD_WIDTH, CARD_HEIGHT);
+
+    let board_size = board_size();
+    context.set_fill_style_str(BOARD_COLOR);
+    fill_round_rect(&context, CARD_PADDING, CARD_PADDING, board_size, BOARD_RADIUS)?;
+
+    let layout = TileLayout::new(board.width, board.height);
+    context.set_text_align("center");
+    context.set_text_baseline("middle");
+    for y in 0..board.height {
+        for x in 0..board.width {
+            let value = board.tiles[y][x].map(|tile| tile.value).unwrap_or(0);
+            let (background, text) = tile_colors(value);
+            let (tile_x, tile_y) = layout.position(x, y);
+            context.set_fill_style_str(background);
+            fill_round_rect(&context, tile_x, tile_y, layout.size, TILE_RADIUS)?;
+            if value > 0 {
+                context.set_fill_style_str(text);
+                context.set_font(&format!("bold {}px {}", layout.font_size(value), FONT_FAMILY));
+                context
+                    .fill_text(
+                        &value.to_string(),
+                        tile_x + layout.size / 2.0,
+                        tile_y + layout.size / 2.0,
+                    )
+                    .map_err(|err| format!("{:?}", err))?;
+            }
+        }
+    }
+
+    context.set_text_align("left");
+    context.set_text_baseline("alphabetic");
+    for (line, color, text) in [
+        (TITLE_TEXT, TEXT_COLOR, String::from("at://2048")),
+        (SCORE_LABEL_TEXT, TEXT_COLOR, String::from("Score")),
+        (SCORE_TEXT, TEXT_COLOR, score.to_string()),
+        (FOOTER_TEXT, FOOTER_COLOR, String::from("2048.blue")),
+    ] {
+        context.set_fill_style_str(color);
+        context.set_font(&format!(
+            "{}{}px {}",
+            if line.bold { "bold " } else { "" },
+            line.font_size,
+            FONT_FAMILY
+        ));
+        context
+            .fill_text(&text, text_x(), line.y)
+            .map_err(|err| format!("{:?}", err))?;
+    }
+
+    canvas
+        .to_data_url_with_type("image/png")
+        .map_err(|err| format!("{:?}", err))
+}
+
+fn fill_round_rect(
+    context: &CanvasRenderingContext2d,
+    x: f64,
+    y: f64,
+    size: f64,
+    radius: f64,
+) -> Result<(), String> {
+    context.begin_path();
+    context
+        .round_rect_with_f64(x, y, size, size, radius)
+        .map_err(|err| format!("{:?}", err))?;
+    context.fill();
+    Ok(())
+}
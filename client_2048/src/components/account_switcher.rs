@@ -0,0 +1,220 @@
+use crate::Route;
+use crate::idb::{
+    DB_NAME, SESSIONS_STORE, STATE_STORE, clear_store, delete_player_db, object_delete,
+};
+use crate::oauth_client::oauth_client;
+use crate::store::UserStore;
+use atrium_api::types::string::Did;
+use indexed_db_futures::database::Database;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlElement;
+use yew::platform::spawn_local;
+use yew::prelude::*;
+use yew_router::prelude::*;
+use yewdux::use_store;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct AccountSwitcherProps {
+    /// Called after picking something so the mobile drawer can close
+    #[prop_or_default]
+    pub onselect: Callback<()>,
+}
+
+/// Lists every account logged in on this browser to switch between, along with playing as a guest,
+/// adding another account, and logging out of the active one
+#[function_component(AccountSwitcher)]
+pub fn account_switcher(props: &AccountSwitcherProps) -> Html {
+    let (user_store, dispatch) = use_store::<UserStore>();
+    let navigator = use_navigator();
+    let confirming_logout = use_state(|| false);
+
+    //The dropdown stays open while anything in it has focus
+    let close = {
+        let onselect = props.onselect.clone();
+        Callback::from(move |_: ()| {
+            if let Some(element) = gloo_utils::document()
+                .active_element()
+                .and_then(|element| element.dyn_into::<HtmlElement>().ok())
+            {
+                let _ = element.blur();
+            }
+            onselect.emit(());
+        })
+    };
+
+    let switch_onclick = |did: Option<Did>| {
+        let dispatch = dispatch.clone();
+        let close = close.clone();
+        Callback::from(move |_: MouseEvent| {
+            dispatch.reduce_mut(|store| store.switch_to(did.as_ref()));
+            close.emit(());
+        })
+    };
+
+    let add_onclick = {
+        let navigator = navigator.clone();
+        let close = close.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(navigator) = &navigator {
+                navigator.push(&Route::LoginPage);
+            }
+            close.emit(());
+        })
+    };
+
+    //Logging out asks what to do with the account's games first
+    let logout_onclick = {
+        let confirming_logout = confirming_logout.clone();
+        let close = close.clone();
+        Callback::from(move |_: MouseEvent| {
+            close.emit(());
+            confirming_logout.set(true);
+        })
+    };
+
+    let cancel_logout_onclick = {
+        let confirming_logout = confirming_logout.clone();
+        Callback::from(move |_: MouseEvent| confirming_logout.set(false))
+    };
+
+    let logout_dialog_onkeydown = {
+        let confirming_logout = confirming_logout.clone();
+        Callback::from(move |event: KeyboardEvent| {
+            if event.key() == "Escape" {
+                event.prevent_default();
+                confirming_logout.set(false);
+            }
+        })
+    };
+
+    let confirm_logout_onclick = |remove_local_data: bool| {
+        let user_store = user_store.clone();
+        let dispatch = dispatch.clone();
+        let confirming_logout = confirming_logout.clone();
+        Callback::from(move |_: MouseEvent| {
+            confirming_logout.set(false);
+            let Some(did) = user_store.did.clone() else {
+                return;
+            };
+            dispatch.reduce_mut(|store| store.remove_account(&did));
+            spawn_local(async move {
+                logout(&did, remove_local_data).await;
+            });
+        })
+    };
+
+    let active_label = match (&user_store.did, &user_store.handle) {
+        (Some(_), Some(handle)) => handle.to_string(),
+        (Some(did), None) => did.to_string(),
+        (None, _) => String::from("Guest"),
+    };
+
+    let accounts = user_store
+        .accounts
+        .iter()
+        .map(|account| {
+            let active = user_store.did.as_ref() == Some(&account.did);
+            let label = match &account.handle {
+                Some(handle) => handle.to_string(),
+                None => account.did.to_string(),
+            };
+            html! {
+                <li key={account.did.to_string()}>
+                    <a
+                        class={classes!(active.then_some("menu-active"))}
+                        aria-current={active.then_some("true")}
+                        onclick={switch_onclick(Some(account.did.clone()))}
+                    >
+                        { label }
+                    </a>
+                </li>
+            }
+        })
+        .collect::<Html>();
+
+    html! {
+        <div class="dropdown dropdown-end">
+            <div tabindex="0" role="button" aria-label="Switch account" class="cursor-pointer">
+                { active_label.clone() }
+            </div>
+            <ul
+                tabindex="0"
+                class="menu dropdown-content bg-base-100 rounded-box z-10 w-64 p-2 shadow-sm"
+            >
+                { accounts }
+                <li>
+                    <a
+                        class={classes!(user_store.did.is_none().then_some("menu-active"))}
+                        onclick={switch_onclick(None)}
+                    >
+                        { "Play as guest" }
+                    </a>
+                </li>
+                <li>
+                    <a onclick={add_onclick}>{ "Add account" }</a>
+                </li>
+                if user_store.did.is_some() {
+                    <li>
+                        <a onclick={logout_onclick}>{ "Logout" }</a>
+                    </li>
+                }
+            </ul>
+            if *confirming_logout {
+                <dialog
+                    class="modal modal-open"
+                    aria-modal="true"
+                    aria-labelledby="logout-dialog-title"
+                    onkeydown={logout_dialog_onkeydown}
+                >
+                    <div class="modal-box space-y-3">
+                        <h3 id="logout-dialog-title" class="font-bold text-lg">
+                            { format!("Logout of {}", active_label) }
+                        </h3>
+                        <p>
+                            { "Your games are still saved to your PDS either way. If this is a shared computer you can remove this account's games, stats, and achievements from it too." }
+                        </p>
+                        <div class="modal-action flex-wrap">
+                            <button class="btn btn-sm" onclick={cancel_logout_onclick}>
+                                { "Cancel" }
+                            </button>
+                            <button class="btn btn-sm btn-outline" onclick={confirm_logout_onclick(false)}>
+                                { "Keep local data" }
+                            </button>
+                            <button class="btn btn-sm btn-error" onclick={confirm_logout_onclick(true)}>
+                                { "Remove this account's data from this device" }
+                            </button>
+                        </div>
+                    </div>
+                </dialog>
+            }
+        </div>
+    }
+}
+
+/// Revokes the session at the authorization server so the tokens stop working, then forgets it and
+/// any logins still in progress. Optionally removes everything saved locally for the account
+async fn logout(did: &Did, remove_local_data: bool) {
+    if let Err(err) = oauth_client().revoke(did).await {
+        //Still forget the session locally even if the server could not be reached
+        log::error!("Error revoking the session: {:?}", err);
+        match Database::open(DB_NAME).await {
+            Ok(db) => object_delete(db, SESSIONS_STORE, did)
+                .await
+                .unwrap_or_else(|err| log::error!("{:?}", err)),
+            Err(err) => log::error!("{:?}", err),
+        }
+    }
+
+    match Database::open(DB_NAME).await {
+        Ok(db) => clear_store(db, STATE_STORE)
+            .await
+            .unwrap_or_else(|err| log::error!("{:?}", err)),
+        Err(err) => log::error!("{:?}", err),
+    }
+
+    if remove_local_data {
+        if let Err(err) = delete_player_db(did).await {
+            log::error!("Error removing local data: {}", err.to_string());
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use crate::streaks::{day_start_ms, local_day};
+use std::collections::BTreeMap;
+use yew::{Html, Properties, function_component, html};
+
+const CHART_WIDTH: f64 = 400.0;
+const CHART_HEIGHT: f64 = 200.0;
+/// Room under the plot for the labels
+const LABEL_HEIGHT: f64 = 20.0;
+/// Room above the plot so the top value label is not cut off
+const TOP_PADDING: f64 = 14.0;
+const BAR_GAP: f64 = 4.0;
+/// How many weeks back the activity heatmap goes
+const HEATMAP_WEEKS: i64 = 26;
+const HEATMAP_CELL: f64 = 12.0;
+const HEATMAP_GAP: f64 = 2.0;
+/// Room left of the grid for the weekday labels
+const HEATMAP_LABEL_WIDTH: f64 = 24.0;
+
+/// Shortens big numbers for axis labels. 1,500 is 1.5k
+pub fn short_number(value: f64) -> String {
+    match value {
+        value if value >= 1_000_000.0 => format!("{:.1}m", value / 1_000_000.0),
+        value if value >= 1_000.0 => format!("{:.1}k", value / 1_000.0),
+        value => format!("{}", value.round()),
+    }
+    .replace(".0", "")
+}
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct BarChartProps {
+    /// What the chart shows, read out by screen readers
+    pub title: String,
+    /// Label and value for each bar, drawn left to right
+    pub bars: Vec<(String, f64)>,
+}
+
+#[function_component(BarChart)]
+pub fn bar_chart(props: &BarChartProps) -> Html {
+    let max = props
+        .bars
+        .iter()
+        .map(|(_, value)| *value)
+        .fold(0.0, f64::max);
+    let plot_height = CHART_HEIGHT - LABEL_HEIGHT - TOP_PADDING;
+    let bar_width = CHART_WIDTH / props.bars.len().max(1) as f64;
+
+    html! {
+        <svg
+            class="w-full h-auto"
+            viewBox={format!("0 0 {} {}", CHART_WIDTH, CHART_HEIGHT)}
+            role="img"
+            aria-label={props.title.clone()}
+        >
+            <title>{ props.title.clone() }</title>
+            { props.bars.iter().enumerate().map(|(index, (label, value))| {
+                let height = match max > 0.0 {
+                    true => value / max * plot_height,
+                    false => 0.0,
+                };
+                let x = index as f64 * bar_width;
+                let y = TOP_PADDING + plot_height - height;
+                let center = x + bar_width / 2.0;
+                html! {
+                    <g>
+                        <rect
+                            class="fill-primary"
+                            x={(x + BAR_GAP / 2.0).to_string()}
+                            y={y.to_string()}
+                            width={(bar_width - BAR_GAP).max(1.0).to_string()}
+                            height={height.to_string()}
+                            rx="2"
+                        >
+                            <title>{ format!("{}: {}", label, value) }</title>
+                        </rect>
+                        if *value > 0.0 {
+                            <text
+                                class="fill-base-content"
+                                x={center.to_string()}
+                                y={(y - 3.0).to_string()}
+                                font-size="10"
+                                text-anchor="middle"
+                            >
+                                { short_number(*value) }
+                            </text>
+                        }
+                        <text
+                            class="fill-base-content"
+                            x={center.to_string()}
+                            y={(CHART_HEIGHT - 5.0).to_string()}
+                            font-size="10"
+                            text-anchor="middle"
+                        >
+                            { label }
+                        </text>
+                    </g>
+                }
+            }).collect::<Html>() }
+        </svg>
+    }
+}
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct LineChartProps {
+    /// What the chart shows, read out by screen readers
+    pub title: String,
+    /// Values drawn left to right at even spacing
+    pub points: Vec<f64>,
+}
+
+#[function_component(LineChart)]
+pub fn line_chart(props: &LineChartProps) -> Html {
+    let max = props.points.iter().copied().fold(0.0, f64::max);
+    let plot_height = CHART_HEIGHT - LABEL_HEIGHT - TOP_PADDING;
+    let step = match props.points.len() {
+        0 | 1 => 0.0,
+        len => CHART_WIDTH / (len - 1) as f64,
+    };
+    let coordinates = props
+        .points
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+            let y = match max > 0.0 {
+                true => TOP_PADDING + plot_height - value / max * plot_height,
+                false => TOP_PADDING + plot_height,
+            };
+            (index as f64 * step, y)
+        })
+        .collect::<Vec<_>>();
+    let polyline_points = coordinates
+        .iter()
+        .map(|(x, y)| format!("{},{}", x, y))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let baseline = TOP_PADDING + plot_height;
+
+    html! {
+        <svg
+            class="w-full h-auto overflow-visible"
+            viewBox={format!("0 0 {} {}", CHART_WIDTH, CHART_HEIGHT)}
+            role="img"
+            aria-label={props.title.clone()}
+        >
+            <title>{ props.title.clone() }</title>
+            <line
+                class="stroke-base-content opacity-30"
+                x1="0"
+                y1={baseline.to_string()}
+                x2={CHART_WIDTH.to_string()}
+                y2={baseline.to_string()}
+            />
+            <text class="fill-base-content" x="0" y="10" font-size="10">
+                { short_number(max) }
+            </text>
+            <text
+                class="fill-base-content"
+                x="0"
+                y={(CHART_HEIGHT - 5.0).to_string()}
+                font-size="10"
+            >
+                { "First game" }
+            </text>
+            <text
+                class="fill-base-content"
+                x={CHART_WIDTH.to_string()}
+                y={(CHART_HEIGHT - 5.0).to_string()}
+                font-size="10"
+                text-anchor="end"
+            >
+                { "Latest" }
+            </text>
+            <polyline
+                class="stroke-primary"
+                fill="none"
+                stroke-width="2"
+                stroke-linejoin="round"
+                points={polyline_points}
+            />
+            { coordinates.iter().zip(props.points.iter()).map(|((x, y), value)| html! {
+                <circle class="fill-primary" cx={x.to_string()} cy={y.to_string()} r="2.5">
+                    <title>{ value.to_string() }</title>
+                </circle>
+            }).collect::<Html>() }
+        </svg>
+    }
+}
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct HeatmapProps {
+    /// What the chart shows, read out by screen readers
+    pub title: String,
+    /// Games played on each day, keyed by the day from local_day
+    pub days: BTreeMap<i64, usize>,
+}
+
+/// A calendar of the last few months, one square a day with a column for each week
+#[function_component(Heatmap)]
+pub fn heatmap(props: &HeatmapProps) -> Html {
+    let today = local_day(js_sys::Date::now());
+    let today_weekday = js_sys::Date::new(&day_start_ms(today).into()).get_day() as i64;
+    //Starts on the Sunday so every column is a full week
+    let first_day = today - today_weekday - (HEATMAP_WEEKS - 1) * 7;
+    let max = props
+        .days
+        .range(first_day..=today)
+        .map(|(_, games)| *games)
+        .max()
+        .unwrap_or(0);
+    let step = HEATMAP_CELL + HEATMAP_GAP;
+    let width = HEATMAP_LABEL_WIDTH + HEATMAP_WEEKS as f64 * step;
+    let height = 7.0 * step;
+
+    html! {
+        <svg
+            class="w-full h-auto"
+            viewBox={format!("0 0 {} {}", width, height)}
+            role="img"
+            aria-label={props.title.clone()}
+        >
+            <title>{ props.title.clone() }</title>
+            { [(1, "Mon"), (3, "Wed"), (5, "Fri")].iter().map(|(weekday, label)| html! {
+                <text
+                    class="fill-base-content"
+                    x="0"
+                    y={(*weekday as f64 * step + HEATMAP_CELL - 2.0).to_string()}
+                    font-size="9"
+                >
+                    { *label }
+                </text>
+            }).collect::<Html>() }
+            { (first_day..=today).map(|day| {
+                let offset = day - first_day;
+                let games = props.days.get(&day).copied().unwrap_or(0);
+                let date = js_sys::Date::new(&day_start_ms(day).into())
+                    .to_date_string()
+                    .as_string()
+                    .unwrap_or_default();
+                let (class, opacity) = match games {
+                    0 => ("fill-base-300", 1.0),
+                    //Always at least a bit filled in so a single game stands out from none
+                    games => ("fill-primary", 0.3 + 0.7 * games as f64 / max.max(1) as f64),
+                };
+                html! {
+                    <rect
+                        class={class}
+                        x={(HEATMAP_LABEL_WIDTH + (offset / 7) as f64 * step).to_string()}
+                        y={((offset % 7) as f64 * step).to_string()}
+                        width={HEATMAP_CELL.to_string()}
+                        height={HEATMAP_CELL.to_string()}
+                        opacity={opacity.to_string()}
+                        rx="2"
+                    >
+                        <title>{ format!("{}: {} games", date, games) }</title>
+                    </rect>
+                }
+            }).collect::<Html>() }
+        </svg>
+    }
+}
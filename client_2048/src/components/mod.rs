@@ -1 +1,5 @@
+pub mod account_switcher;
+pub mod charts;
 pub mod theme_picker;
+pub mod post_dialog;
+pub mod session_banner;
@@ -0,0 +1,321 @@
+use crate::bsky_post::{
+    ExternalLink, MAX_POST_LENGTH, PostDraft, PostError, create_post, post_length,
+    render_board_image, replace_link, saved_game_link,
+};
+use crate::oauth_client::can_post;
+use crate::pages::login::redirect_to_auth;
+use crate::session::report_expired;
+use crate::store::UserStore;
+use twothousand_forty_eight::board::Board;
+use web_sys::{HtmlElement, HtmlInputElement, HtmlTextAreaElement};
+use yew::platform::spawn_local;
+use yew::prelude::*;
+use yewdux::use_store;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct PostToBlueskyProps {
+    /// What the post says before the player edits it
+    pub text: String,
+    pub link: ExternalLink,
+    /// Final board to attach a picture of
+    pub board: Option<Board>,
+    pub score: usize,
+    /// If the game has been saved the link is swapped for the game's own page when posting
+    #[prop_or_default]
+    pub game_hash: Option<String>,
+}
+
+/// Where the post is being made
+#[derive(Clone, PartialEq)]
+enum PostState {
+    /// Checking the login covers posting
+    Checking,
+    /// Logged in without posting to Bluesky allowed, they have to login again to grant it
+    NeedsPermission,
+    Editing,
+    Posting,
+    Posted(String),
+    Failed(String),
+}
+
+/// Posts straight to the player's Bluesky account with a preview and a chance to edit it first.
+/// Only shows when logged in
+#[function_component(PostToBluesky)]
+pub fn post_to_bluesky(props: &PostToBlueskyProps) -> Html {
+    let (user_store, _) = use_store::<UserStore>();
+    let open = use_state(|| false);
+    let text = use_state(|| props.text.clone());
+    let attach_image = use_state(|| true);
+    let image = use_state(|| None::<String>);
+    let post_state = use_state(|| PostState::Editing);
+    let opener_ref = use_node_ref();
+    let text_ref = use_node_ref();
+    let was_open = use_mut_ref(|| false);
+
+    //Focus goes into the dialog when it opens and back to the button that opened it when it closes.
+    //The text box only shows up once the login has been checked
+    {
+        let opener_ref = opener_ref.clone();
+        let text_ref = text_ref.clone();
+        let checking = *post_state == PostState::Checking;
+        use_effect_with((*open, checking), move |(open, _)| {
+            let focus_on = match (*open, was_open.replace(*open)) {
+                (true, _) => text_ref.cast::<HtmlElement>(),
+                (false, true) => opener_ref.cast::<HtmlElement>(),
+                (false, false) => None,
+            };
+            if let Some(element) = focus_on {
+                let _ = element.focus();
+            }
+        });
+    }
+
+    let Some(did) = user_store.did.clone() else {
+        return html! {};
+    };
+
+    let open_onclick = {
+        let open = open.clone();
+        let text = text.clone();
+        let image = image.clone();
+        let post_state = post_state.clone();
+        let props = props.clone();
+        let did = did.clone();
+        Callback::from(move |_: MouseEvent| {
+            text.set(props.text.clone());
+            post_state.set(PostState::Checking);
+            {
+                let post_state = post_state.clone();
+                let did = did.clone();
+                spawn_local(async move {
+                    post_state.set(match can_post(&did).await {
+                        true => PostState::Editing,
+                        false => PostState::NeedsPermission,
+                    });
+                });
+            }
+            image.set(props.board.as_ref().and_then(|board| {
+                render_board_image(board, props.score)
+                    .map_err(|err| log::error!("Error drawing the board: {}", err))
+                    .ok()
+            }));
+            open.set(true);
+        })
+    };
+
+    let close_onclick = {
+        let open = open.clone();
+        Callback::from(move |_: MouseEvent| open.set(false))
+    };
+
+    let dialog_onkeydown = {
+        let open = open.clone();
+        Callback::from(move |event: KeyboardEvent| {
+            if event.key() == "Escape" {
+                event.prevent_default();
+                open.set(false);
+            }
+        })
+    };
+
+    let allow_posting_onclick = {
+        let post_state = post_state.clone();
+        let did = did.clone();
+        Callback::from(move |_: MouseEvent| {
+            let post_state = post_state.clone();
+            let did = did.clone();
+            spawn_local(async move {
+                if let Err(err) = redirect_to_auth(did.to_string(), true).await {
+                    post_state.set(PostState::Failed(err));
+                }
+            });
+        })
+    };
+
+    let text_oninput = {
+        let text = text.clone();
+        Callback::from(move |event: InputEvent| {
+            let input: HtmlTextAreaElement = event.target_unchecked_into();
+            text.set(input.value());
+        })
+    };
+
+    let attach_onchange = {
+        let attach_image = attach_image.clone();
+        Callback::from(move |event: Event| {
+            let input: HtmlInputElement = event.target_unchecked_into();
+            attach_image.set(input.checked());
+        })
+    };
+
+    let post_onclick = {
+        let text = text.clone();
+        let image = image.clone();
+        let attach_image = attach_image.clone();
+        let post_state = post_state.clone();
+        let props = props.clone();
+        let did = did.clone();
+        Callback::from(move |_: MouseEvent| {
+            let post_state = post_state.clone();
+            let did = did.clone();
+            let mut draft = PostDraft {
+                text: (*text).clone(),
+                link: props.link.clone(),
+                image: (*attach_image).then(|| (*image).clone()).flatten(),
+            };
+            let game_hash = props.game_hash.clone();
+            post_state.set(PostState::Posting);
+            spawn_local(async move {
+                if let Some(game_hash) = game_hash {
+                    if let Some(game_link) = saved_game_link(&did, &game_hash).await {
+                        //Keeps the link in the text matching the card
+                        draft.text = replace_link(&draft.text, &draft.link.uri, &game_link);
+                        draft.link.uri = game_link;
+                    }
+                }
+                match create_post(did.clone(), draft).await {
+                    Ok(uri) => post_state.set(PostState::Posted(uri)),
+                    Err(PostError::AuthErrorNeedToReLogin) => {
+                        post_state.set(PostState::Failed(
+                            PostError::AuthErrorNeedToReLogin.to_string(),
+                        ));
+                        report_expired(&did);
+                    }
+                    Err(err) => {
+                        log::error!("Error posting to Bluesky: {}", err);
+                        post_state.set(PostState::Failed(err.to_string()));
+                    }
+                }
+            });
+        })
+    };
+
+    let length = post_length(&text);
+    let too_long = length > MAX_POST_LENGTH;
+    let link_domain = props
+        .link
+        .uri
+        .split("://")
+        .nth(1)
+        .and_then(|rest| rest.split('/').next())
+        .unwrap_or(&props.link.uri)
+        .to_string();
+
+    html! {
+        <>
+            <button class="btn btn-sm btn-primary ml-2" ref={opener_ref} onclick={open_onclick}>
+                { "Post to Bluesky" }
+            </button>
+            if *open {
+                <dialog
+                    class="modal modal-open"
+                    aria-modal="true"
+                    aria-labelledby="post-dialog-title"
+                    onkeydown={dialog_onkeydown}
+                >
+                    <div class="modal-box space-y-3">
+                        <h3 id="post-dialog-title" class="font-bold text-lg">
+                            { "Post to Bluesky" }
+                        </h3>
+                        { match &*post_state {
+                            PostState::Checking => html! {
+                                <span class="loading loading-spinner loading-md" />
+                            },
+                            PostState::NeedsPermission => html! {
+                                <div class="space-y-2">
+                                    <p>
+                                        { "When you logged in at://2048 was only allowed to save your games. To post to Bluesky you need to login again and allow posting too." }
+                                    </p>
+                                    <button class="btn btn-sm btn-primary" onclick={allow_posting_onclick}>
+                                        { "Allow posting" }
+                                    </button>
+                                </div>
+                            },
+                            PostState::Posted(uri) => html! {
+                                <div class="space-y-2">
+                                    <p>{ "Posted!" }</p>
+                                    <a
+                                        class="link link-primary"
+                                        href={bsky_post_url(uri)}
+                                        target="_blank"
+                                    >
+                                        { "See your post" }
+                                    </a>
+                                </div>
+                            },
+                            state => html! {
+                                <>
+                                    <textarea
+                                        ref={text_ref.clone()}
+                                        class="textarea w-full h-40"
+                                        aria-label="Post text"
+                                        value={(*text).clone()}
+                                        oninput={text_oninput}
+                                        disabled={*state == PostState::Posting}
+                                    />
+                                    <div
+                                        class={classes!("text-sm", "text-right", too_long.then_some("text-error"))}
+                                        aria-live="polite"
+                                    >
+                                        { format!("{}/{}", length, MAX_POST_LENGTH) }
+                                    </div>
+                                    if props.board.is_some() {
+                                        <label class="label cursor-pointer">
+                                            <input
+                                                type="checkbox"
+                                                class="checkbox checkbox-sm"
+                                                checked={*attach_image}
+                                                onchange={attach_onchange}
+                                            />
+                                            { "Attach a picture of the board" }
+                                        </label>
+                                    }
+                                    // Link card preview, looks roughly like it will on Bluesky
+                                    <div class="border border-base-300 rounded-box overflow-hidden">
+                                        if let (true, Some(image)) = (*attach_image, image.as_ref()) {
+                                            <img src={image.clone()} alt="The final board" class="w-full" />
+                                        }
+                                        <div class="p-2">
+                                            <div class="text-xs opacity-70">{ link_domain }</div>
+                                            <div class="font-semibold">{ props.link.title.clone() }</div>
+                                            <div class="text-sm">{ props.link.description.clone() }</div>
+                                        </div>
+                                    </div>
+                                    if let PostState::Failed(err) = state {
+                                        <p class="text-error">{ err }</p>
+                                    }
+                                </>
+                            },
+                        } }
+                        <div class="modal-action">
+                            <button class="btn btn-sm" onclick={close_onclick}>
+                                { "Close" }
+                            </button>
+                            if matches!(*post_state, PostState::Editing | PostState::Posting | PostState::Failed(_)) {
+                                <button
+                                    class="btn btn-sm btn-primary"
+                                    onclick={post_onclick}
+                                    disabled={too_long || *post_state == PostState::Posting}
+                                >
+                                    if *post_state == PostState::Posting {
+                                        <span class="loading loading-spinner loading-xs" />
+                                    }
+                                    { "Post" }
+                                </button>
+                            }
+                        </div>
+                    </div>
+                </dialog>
+            }
+        </>
+    }
+}
+
+/// at://did/app.bsky.feed.post/rkey to the post on bsky.app
+fn bsky_post_url(at_uri: &str) -> String {
+    let parts = at_uri.trim_start_matches("at://").split('/').collect::<Vec<_>>();
+    match parts.as_slice() {
+        [did, _, rkey] => format!("https://bsky.app/profile/{}/post/{}", did, rkey),
+        _ => String::from("https://bsky.app"),
+    }
+}
@@ -0,0 +1,51 @@
+use crate::Route;
+use crate::session::SessionState;
+use crate::store::UserStore;
+use yew::prelude::*;
+use yew_router::prelude::*;
+use yewdux::use_store;
+
+/// Lets the player know their login has expired without getting in the way of playing. Games are
+/// still saved on this device and sync once they login again
+#[function_component(SessionBanner)]
+pub fn session_banner() -> Html {
+    let (session_state, session_dispatch) = use_store::<SessionState>();
+    let (user_store, _) = use_store::<UserStore>();
+
+    //Only the account being played as matters, another one can be sorted out when switching to it
+    let Some(did) = session_state
+        .expired
+        .clone()
+        .filter(|did| user_store.did.as_ref() == Some(did))
+    else {
+        return html! {};
+    };
+    if session_state.dismissed {
+        return html! {};
+    }
+
+    let name = user_store
+        .handle
+        .as_ref()
+        .map(|handle| handle.to_string())
+        .unwrap_or_else(|| did.to_string());
+
+    let dismiss_onclick =
+        session_dispatch.reduce_mut_callback(|state: &mut SessionState| state.dismissed = true);
+
+    html! {
+        <div role="status" class="alert alert-warning rounded-none flex flex-wrap justify-between">
+            <span>
+                { format!("Your login for {} has expired. Your games are still saved on this device and will sync once you login again.", name) }
+            </span>
+            <div class="flex gap-2">
+                <Link<Route> classes="btn btn-sm" to={Route::LoginPageWithDid { did: did.to_string() }}>
+                    { "Login again" }
+                </Link<Route>>
+                <button class="btn btn-sm btn-ghost" onclick={dismiss_onclick}>
+                    { "Dismiss" }
+                </button>
+            </div>
+        </div>
+    }
+}
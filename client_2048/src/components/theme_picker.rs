@@ -6,7 +6,7 @@ use yew::{Callback, Html, function_component, html, use_state};
 
 #[function_component(ThemePicker)]
 pub fn theme_picker() -> Html {
-    let themes = vec!["light", "dark", "eink"];
+    let themes = ["light", "dark", "eink", "contrast"];
     //Detect browser preferred theme
     let browser_default = match gloo_utils::window().match_media("(prefers-color-scheme: dark)") {
         Ok(result) => match result {
@@ -42,7 +42,7 @@ pub fn theme_picker() -> Html {
         <div class="flex-none">
             <fieldset class="fieldset">
                 <legend class="md:hidden sm:block fieldset-legend">{ "Theme" }</legend>
-                <select {onchange} class="select">
+                <select {onchange} class="select" aria-label="Theme">
                     { for themes.iter().map(|theme| html! {
                     if *theme == *current_theme {
                      <option selected=true>{theme.to_string()}</option>
@@ -0,0 +1,245 @@
+use gloo::render::{AnimationFrame, request_animation_frame};
+use gloo::storage::{LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::rc::Rc;
+use twothousand_forty_eight::direction::Direction;
+use wasm_bindgen::JsCast;
+use web_sys::Gamepad;
+
+/// Where the key bindings are saved in LocalStorage
+const KEY_BINDINGS_KEY: &str = "keyBindings";
+/// How far the analog stick has to be pushed before it counts as a move
+const STICK_DEADZONE: f64 = 0.5;
+/// Standard gamepad mapping's d-pad buttons
+const DPAD_UP: u32 = 12;
+const DPAD_DOWN: u32 = 13;
+const DPAD_LEFT: u32 = 14;
+const DPAD_RIGHT: u32 = 15;
+
+/// Something the player can do from the keyboard
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Control {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Practice mode only
+    Undo,
+    /// Practice mode only
+    Redo,
+}
+
+impl Control {
+    pub fn all() -> [Control; 6] {
+        [
+            Control::Up,
+            Control::Down,
+            Control::Left,
+            Control::Right,
+            Control::Undo,
+            Control::Redo,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Control::Up => "Move up",
+            Control::Down => "Move down",
+            Control::Left => "Move left",
+            Control::Right => "Move right",
+            Control::Undo => "Undo (practice)",
+            Control::Redo => "Redo (practice)",
+        }
+    }
+
+    pub fn direction(&self) -> Option<Direction> {
+        match self {
+            Control::Up => Some(Direction::UP),
+            Control::Down => Some(Direction::DOWN),
+            Control::Left => Some(Direction::LEFT),
+            Control::Right => Some(Direction::RIGHT),
+            Control::Undo | Control::Redo => None,
+        }
+    }
+}
+
+/// Which keys do what, matched against KeyboardEvent.key. Saved in LocalStorage
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyBindings {
+    pub up: Vec<String>,
+    pub down: Vec<String>,
+    pub left: Vec<String>,
+    pub right: Vec<String>,
+    pub undo: Vec<String>,
+    pub redo: Vec<String>,
+}
+
+impl Default for KeyBindings {
+    /// Arrow keys, WASD, and vim keys
+    fn default() -> Self {
+        let keys = |keys: &[&str]| keys.iter().map(|key| key.to_string()).collect();
+        Self {
+            up: keys(&["ArrowUp", "w", "k"]),
+            down: keys(&["ArrowDown", "s", "j"]),
+            left: keys(&["ArrowLeft", "a", "h"]),
+            right: keys(&["ArrowRight", "d", "l"]),
+            undo: keys(&["u"]),
+            redo: keys(&["r"]),
+        }
+    }
+}
+
+impl KeyBindings {
+    /// The saved bindings, or the defaults if there are none
+    pub fn load() -> Self {
+        LocalStorage::get(KEY_BINDINGS_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = LocalStorage::set(KEY_BINDINGS_KEY, self) {
+            log::error!("Error saving key bindings: {:?}", err);
+        }
+    }
+
+    pub fn keys(&self, control: Control) -> &Vec<String> {
+        match control {
+            Control::Up => &self.up,
+            Control::Down => &self.down,
+            Control::Left => &self.left,
+            Control::Right => &self.right,
+            Control::Undo => &self.undo,
+            Control::Redo => &self.redo,
+        }
+    }
+
+    fn keys_mut(&mut self, control: Control) -> &mut Vec<String> {
+        match control {
+            Control::Up => &mut self.up,
+            Control::Down => &mut self.down,
+            Control::Left => &mut self.left,
+            Control::Right => &mut self.right,
+            Control::Undo => &mut self.undo,
+            Control::Redo => &mut self.redo,
+        }
+    }
+
+    /// What a key press does, if anything
+    pub fn control_for(&self, key: &str) -> Option<Control> {
+        Control::all()
+            .into_iter()
+            .find(|control| self.keys(*control).iter().any(|bound| bound == key))
+    }
+
+    /// Binds the key to the control, taking it off anything else it was bound to
+    pub fn bind(&mut self, control: Control, key: String) {
+        for other in Control::all() {
+            self.keys_mut(other).retain(|bound| *bound != key);
+        }
+        self.keys_mut(control).push(key);
+    }
+
+    pub fn unbind(&mut self, control: Control, key: &str) {
+        self.keys_mut(control).retain(|bound| bound != key);
+    }
+}
+
+/// A key as it is shown to the player
+pub fn key_label(key: &str) -> String {
+    match key {
+        "ArrowUp" => String::from("↑"),
+        "ArrowDown" => String::from("↓"),
+        "ArrowLeft" => String::from("←"),
+        "ArrowRight" => String::from("→"),
+        " " => String::from("Space"),
+        key if key.chars().count() == 1 => key.to_uppercase(),
+        key => key.to_string(),
+    }
+}
+
+/// Which way the gamepad's d-pad or left stick is being held, d-pad first
+fn gamepad_direction(gamepad: &Gamepad) -> Option<Direction> {
+    let buttons = gamepad.buttons();
+    let pressed = |index: u32| {
+        buttons
+            .get(index)
+            .dyn_into::<web_sys::GamepadButton>()
+            .map(|button| button.pressed())
+            .unwrap_or(false)
+    };
+    if pressed(DPAD_UP) {
+        return Some(Direction::UP);
+    }
+    if pressed(DPAD_DOWN) {
+        return Some(Direction::DOWN);
+    }
+    if pressed(DPAD_LEFT) {
+        return Some(Direction::LEFT);
+    }
+    if pressed(DPAD_RIGHT) {
+        return Some(Direction::RIGHT);
+    }
+
+    let axes = gamepad.axes();
+    let x = axes.get(0).as_f64().unwrap_or(0.0);
+    let y = axes.get(1).as_f64().unwrap_or(0.0);
+    if x.abs().max(y.abs()) < STICK_DEADZONE {
+        return None;
+    }
+    //Whichever way the stick is pushed furthest wins
+    match (x.abs() > y.abs(), x > 0.0, y > 0.0) {
+        (true, true, _) => Some(Direction::RIGHT),
+        (true, false, _) => Some(Direction::LEFT),
+        (false, _, true) => Some(Direction::DOWN),
+        (false, _, false) => Some(Direction::UP),
+    }
+}
+
+/// The first connected gamepad's direction, if any
+fn poll_gamepads() -> Option<Direction> {
+    let gamepads = gloo_utils::window().navigator().get_gamepads().ok()?;
+    gamepads
+        .iter()
+        .filter_map(|gamepad| gamepad.dyn_into::<Gamepad>().ok())
+        .filter(|gamepad| gamepad.connected())
+        .find_map(|gamepad| gamepad_direction(&gamepad))
+}
+
+/// Polls connected gamepads every animation frame and calls on_move once each time a direction is
+/// pressed. Holding it does not repeat, the d-pad or stick has to go back to the middle first.
+/// Polling stops when the returned handle is dropped
+pub struct GamepadPoller {
+    frame: Rc<RefCell<Option<AnimationFrame>>>,
+}
+
+impl GamepadPoller {
+    pub fn start(on_move: impl Fn(Direction) + 'static) -> Self {
+        let frame = Rc::new(RefCell::new(None));
+        poll_next_frame(Rc::clone(&frame), Rc::new(on_move), None);
+        Self { frame }
+    }
+}
+
+impl Drop for GamepadPoller {
+    fn drop(&mut self) {
+        //Dropping the pending frame cancels it, which ends the loop
+        self.frame.borrow_mut().take();
+    }
+}
+
+fn poll_next_frame(
+    frame: Rc<RefCell<Option<AnimationFrame>>>,
+    on_move: Rc<dyn Fn(Direction)>,
+    held: Option<Direction>,
+) {
+    let next_frame = Rc::clone(&frame);
+    *frame.borrow_mut() = Some(request_animation_frame(move |_| {
+        let direction = poll_gamepads();
+        if let Some(direction) = direction {
+            if held != Some(direction) {
+                on_move(direction);
+            }
+        }
+        poll_next_frame(next_frame, on_move, direction);
+    }));
+}
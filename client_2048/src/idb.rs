@@ -1,4 +1,4 @@
-use atrium_api::types::string::RecordKey;
+use atrium_api::types::string::{Did, RecordKey};
 use indexed_db_futures::cursor::CursorDirection;
 use indexed_db_futures::database::Database;
 use indexed_db_futures::error::OpenDbError;
@@ -6,6 +6,9 @@ use indexed_db_futures::prelude::*;
 use indexed_db_futures::transaction::TransactionMode;
 use indexed_db_futures::{KeyPath, SerialiseToJs};
 use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use wasm_bindgen::JsCast;
+use types_2048::blue::_2048::{game, player};
 
 //Object Store names
 /// db name
@@ -26,45 +29,258 @@ pub const DID_RESOLVER_STORE: &str = "did:resolver";
 pub const STATE_STORE: &str = "states";
 /// atrium SessionStore
 pub const SESSIONS_STORE: &str = "sessions";
+/// Store for the practice game, 1 record for the store uses self as the key. Never synced or counted in stats
+pub const PRACTICE_GAME_STORE: &str = "practice_game";
+/// Cached blue.2048.player.stats of the people in the player's social graph, keys are dids. Kept in
+/// each account's db since their social graphs differ, the one in the shared db is no longer used
+pub const FRIENDS_STATS_STORE: &str = "friends:stats";
+/// Store for unlocked achievements(blue.2048.player.achievement), keys are the achievement ids
+pub const ACHIEVEMENTS_STORE: &str = "achievements";
+/// Store for app wide flags, like which account claimed the data saved before accounts were split up
+pub const META_STORE: &str = "meta";
+
+/// Each logged in account gets its own db named this plus their did, holding the player stores.
+/// Playing without logging in uses the same stores in the DB_NAME db
+pub const PLAYER_DB_PREFIX: &str = "2048:";
+/// The stores that belong to whoever is playing, everything else is shared by every account
+pub const PLAYER_STORES: [&str; 4] = [GAME_STORE, STATS_STORE, PROFILE_STORE, ACHIEVEMENTS_STORE];
+/// Did of the account that took the anonymous data when multiple accounts were added
+const LEGACY_DATA_CLAIMED_KEY: &str = "legacyDataClaimedBy";
+/// Set once the claimed data has been copied over and cleared from the shared stores
+const LEGACY_DATA_MOVED_KEY: &str = "legacyDataMoved";
+
+thread_local! {
+    /// Remembers the anonymous data has been moved so opening a player db doesn't check every time
+    static LEGACY_DATA_MOVED: Cell<bool> = const { Cell::new(false) };
+}
 
 /// Static keys for one record stores
 pub const SELF_KEY: &str = "self";
 
 pub async fn create_database() -> Result<Database, OpenDbError> {
     let db = Database::open(DB_NAME)
-        .with_version(1u8)
+        .with_version(5u8)
         .with_on_blocked(|event| {
             log::debug!("DB upgrade blocked: {:?}", event);
             Ok(())
         })
         .with_on_upgrade_needed_fut(|event, db| async move {
-            match (event.old_version(), event.new_version()) {
-                (0.0, Some(1.0)) => {
-                    let record_key_path = KeyPath::from("rkey");
-                    let game_store = db
-                        .create_object_store(GAME_STORE)
-                        .with_key_path(record_key_path.clone())
-                        .build()?;
-                    game_store
-                        .create_index("index_hash", KeyPath::from("index_hash"))
-                        .build()?;
-                    db.create_object_store(CURRENT_GAME_STORE).build()?;
-                    db.create_object_store(STATS_STORE).build()?;
-                    db.create_object_store(PROFILE_STORE).build()?;
-                    db.create_object_store(KEY_STORE).build()?;
-                    db.create_object_store(DID_RESOLVER_STORE).build()?;
-                    db.create_object_store(STATE_STORE).build()?;
-                    db.create_object_store(SESSIONS_STORE).build()?;
-                }
-                _ => {}
+            //Runs each version's changes in order so a new or old db ends up the same
+            if event.old_version() < 1.0 {
+                let record_key_path = KeyPath::from("rkey");
+                let game_store = db
+                    .create_object_store(GAME_STORE)
+                    .with_key_path(record_key_path.clone())
+                    .build()?;
+                game_store
+                    .create_index("index_hash", KeyPath::from("index_hash"))
+                    .build()?;
+                db.create_object_store(CURRENT_GAME_STORE).build()?;
+                db.create_object_store(STATS_STORE).build()?;
+                db.create_object_store(PROFILE_STORE).build()?;
+                db.create_object_store(KEY_STORE).build()?;
+                db.create_object_store(DID_RESOLVER_STORE).build()?;
+                db.create_object_store(STATE_STORE).build()?;
+                db.create_object_store(SESSIONS_STORE).build()?;
+            }
+            if event.old_version() < 2.0 {
+                db.create_object_store(FRIENDS_STATS_STORE)
+                    .with_key_path(KeyPath::from("did"))
+                    .build()?;
+            }
+            if event.old_version() < 3.0 {
+                db.create_object_store(PRACTICE_GAME_STORE).build()?;
+            }
+            if event.old_version() < 4.0 {
+                db.create_object_store(ACHIEVEMENTS_STORE)
+                    .with_key_path(KeyPath::from("achievement"))
+                    .build()?;
             }
+            if event.old_version() < 5.0 {
+                db.create_object_store(META_STORE).build()?;
+            }
+
+            Ok(())
+        })
+        .await?;
+    Ok(db)
+}
 
+/// The db holding an account's games, stats, profile, achievements, and friends' stats
+pub async fn create_player_database(did: &Did) -> Result<Database, OpenDbError> {
+    let db = Database::open(format!("{}{}", PLAYER_DB_PREFIX, did.as_str()))
+        .with_version(2u8)
+        .with_on_blocked(|event| {
+            log::debug!("Player DB upgrade blocked: {:?}", event);
+            Ok(())
+        })
+        .with_on_upgrade_needed_fut(|event, db| async move {
+            //Same layout as the player stores in the shared db
+            if event.old_version() < 1.0 {
+                let game_store = db
+                    .create_object_store(GAME_STORE)
+                    .with_key_path(KeyPath::from("rkey"))
+                    .build()?;
+                game_store
+                    .create_index("index_hash", KeyPath::from("index_hash"))
+                    .build()?;
+                db.create_object_store(STATS_STORE).build()?;
+                db.create_object_store(PROFILE_STORE).build()?;
+                db.create_object_store(ACHIEVEMENTS_STORE)
+                    .with_key_path(KeyPath::from("achievement"))
+                    .build()?;
+            }
+            if event.old_version() < 2.0 {
+                db.create_object_store(FRIENDS_STATS_STORE)
+                    .with_key_path(KeyPath::from("did"))
+                    .build()?;
+            }
             Ok(())
         })
         .await?;
     Ok(db)
 }
 
+/// Opens the db with the player stores for the account, or the anonymous ones in the shared db.
+/// The first account to be opened takes over anything played before accounts were split up
+pub async fn open_player_db(did: Option<&Did>) -> Result<Database, StorageError> {
+    let Some(did) = did else {
+        return Database::open(DB_NAME)
+            .await
+            .map_err(|err| StorageError::OpenDbError(err.to_string()));
+    };
+    let db = create_player_database(did)
+        .await
+        .map_err(|err| StorageError::OpenDbError(err.to_string()))?;
+    if let Err(err) = claim_legacy_data(did, db.clone()).await {
+        log::error!("Error moving saved games to {}: {}", did.as_str(), err.to_string());
+    }
+    Ok(db)
+}
+
+/// Removes everything saved locally for the account. Anything synced to their PDS is left alone
+pub async fn delete_player_db(did: &Did) -> Result<(), StorageError> {
+    Database::delete_by_name(&format!("{}{}", PLAYER_DB_PREFIX, did.as_str()))
+        .map_err(|err| StorageError::OpenDbError(err.to_string()))?
+        .await
+        .map_err(|err| StorageError::Error(err.to_string()))
+}
+
+/// Moves the anonymous player stores into the account's db the first time any account is opened.
+/// Before multiple accounts everyone shared those stores, so they belong to whoever was logged in
+async fn claim_legacy_data(did: &Did, player_db: Database) -> Result<(), StorageError> {
+    if LEGACY_DATA_MOVED.get() {
+        return Ok(());
+    }
+    let shared_db = Database::open(DB_NAME)
+        .await
+        .map_err(|err| StorageError::OpenDbError(err.to_string()))?;
+    if object_get::<bool>(shared_db.clone(), META_STORE, LEGACY_DATA_MOVED_KEY)
+        .await?
+        .is_some()
+    {
+        LEGACY_DATA_MOVED.set(true);
+        return Ok(());
+    }
+    if !take_legacy_claim(shared_db.clone(), did).await? {
+        //Another account has it and finishes the copy itself, there is nothing left for this tab
+        LEGACY_DATA_MOVED.set(true);
+        return Ok(());
+    }
+
+    //Puts with the same keys, so copying again after a failed try is fine
+    let games = object_get_all::<RecordStorageWrapper<game::RecordData>>(shared_db.clone(), GAME_STORE)
+        .await?;
+    for game in games {
+        transaction_put(player_db.clone(), game, GAME_STORE, None).await?;
+    }
+    let achievements =
+        object_get_all::<player::achievement::RecordData>(shared_db.clone(), ACHIEVEMENTS_STORE)
+            .await?;
+    for achievement in achievements {
+        transaction_put(player_db.clone(), achievement, ACHIEVEMENTS_STORE, None).await?;
+    }
+    if let Some(stats) =
+        object_get::<player::stats::RecordData>(shared_db.clone(), STATS_STORE, SELF_KEY).await?
+    {
+        transaction_put(player_db.clone(), stats, STATS_STORE, Some(SELF_KEY.to_string())).await?;
+    }
+    if let Some(profile) =
+        object_get::<player::profile::RecordData>(shared_db.clone(), PROFILE_STORE, SELF_KEY)
+            .await?
+    {
+        transaction_put(player_db, profile, PROFILE_STORE, Some(SELF_KEY.to_string())).await?;
+    }
+
+    finish_legacy_claim(shared_db).await?;
+    LEGACY_DATA_MOVED.set(true);
+    Ok(())
+}
+
+/// Checks who claimed the anonymous data and claims it if nobody has, in one transaction so two
+/// tabs opening different accounts can't both take it. True if it belongs to this account
+async fn take_legacy_claim(shared_db: Database, did: &Did) -> Result<bool, StorageError> {
+    let transaction = shared_db
+        .transaction(META_STORE)
+        .with_mode(TransactionMode::Readwrite)
+        .build()
+        .map_err(|err| StorageError::Error(err.to_string()))?;
+    let store = transaction
+        .object_store(META_STORE)
+        .map_err(|err| StorageError::Error(err.to_string()))?;
+    let claimed_by: Option<String> = store
+        .get(LEGACY_DATA_CLAIMED_KEY)
+        .serde()
+        .map_err(|err| StorageError::Error(err.to_string()))?
+        .await
+        .map_err(|err| StorageError::Error(err.to_string()))?;
+    let claimed = match claimed_by {
+        Some(claimed_by) => claimed_by == did.as_str(),
+        None => {
+            store
+                .put(did.to_string())
+                .with_key(LEGACY_DATA_CLAIMED_KEY.to_string())
+                .serde()
+                .map_err(|err| StorageError::Error(err.to_string()))?
+                .await?;
+            true
+        }
+    };
+    transaction.commit().await?;
+    Ok(claimed)
+}
+
+/// Clears the shared player stores and marks the data as moved. Only done once the copy worked, so
+/// a failed one is tried again the next time the account is opened
+async fn finish_legacy_claim(shared_db: Database) -> Result<(), StorageError> {
+    let mut stores = PLAYER_STORES.to_vec();
+    stores.push(META_STORE);
+    let transaction = shared_db
+        .transaction(stores)
+        .with_mode(TransactionMode::Readwrite)
+        .build()
+        .map_err(|err| StorageError::Error(err.to_string()))?;
+    for store in PLAYER_STORES {
+        transaction
+            .object_store(store)
+            .map_err(|err| StorageError::Error(err.to_string()))?
+            .clear()
+            .map_err(|err| StorageError::Error(err.to_string()))?
+            .await
+            .map_err(|err| StorageError::Error(err.to_string()))?;
+    }
+    transaction
+        .object_store(META_STORE)
+        .map_err(|err| StorageError::Error(err.to_string()))?
+        .put(true)
+        .with_key(LEGACY_DATA_MOVED_KEY.to_string())
+        .serde()
+        .map_err(|err| StorageError::Error(err.to_string()))?
+        .await?;
+    transaction.commit().await?;
+    Ok(())
+}
+
 /// A think wrapper around a at proto record with the record key for storage
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct RecordStorageWrapper<T> {
@@ -77,6 +293,8 @@ pub struct RecordStorageWrapper<T> {
 pub enum StorageError {
     Error(String),
     OpenDbError(String),
+    /// The browser will not let the site store any more
+    QuotaExceeded,
 }
 
 impl StorageError {
@@ -84,6 +302,26 @@ impl StorageError {
         match self {
             StorageError::Error(err) => err.to_string(),
             StorageError::OpenDbError(err) => err.to_string(),
+            StorageError::QuotaExceeded => String::from("IndexedDB quota exceeded"),
+        }
+    }
+}
+
+/// Picks out running out of space, which shows up as a QuotaExceededError DOMException on the
+/// write or the commit
+impl From<indexed_db_futures::error::Error> for StorageError {
+    fn from(err: indexed_db_futures::error::Error) -> Self {
+        match &err {
+            indexed_db_futures::error::Error::DomException(exception)
+                if exception
+                    .as_ref()
+                    .unchecked_ref::<web_sys::DomException>()
+                    .name()
+                    == "QuotaExceededError" =>
+            {
+                StorageError::QuotaExceeded
+            }
+            _ => StorageError::Error(err.to_string()),
         }
     }
 }
@@ -120,7 +358,7 @@ where
             Ok(action) => match action.await {
                 Ok(_) => {}
                 Err(err) => {
-                    return Err(StorageError::Error(err.to_string()));
+                    return Err(err.into());
                 }
             },
             Err(err) => return Err(StorageError::Error(err.to_string())),
@@ -128,7 +366,7 @@ where
         Some(key) => match store.put(item).with_key(key).serde() {
             Ok(action) => match action.await {
                 Ok(_) => {}
-                Err(err) => return Err(StorageError::Error(err.to_string())),
+                Err(err) => return Err(err.into()),
             },
             Err(err) => return Err(StorageError::Error(err.to_string())),
         },
@@ -136,7 +374,7 @@ where
 
     match transaction.commit().await {
         Ok(_) => Ok(()),
-        Err(err) => Err(StorageError::Error(err.to_string())),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -276,6 +514,47 @@ where
     Ok(result_items)
 }
 
+/// Every item in the store in key order
+pub async fn object_get_all<T>(db: Database, store: &str) -> Result<Vec<T>, StorageError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let transaction = match db
+        .transaction(store)
+        .with_mode(TransactionMode::Readonly)
+        .build()
+    {
+        Ok(transaction) => transaction,
+        Err(err) => {
+            return Err(StorageError::Error(err.to_string()));
+        }
+    };
+
+    let store = match transaction.object_store(store) {
+        Ok(store) => store,
+        Err(err) => {
+            return Err(StorageError::Error(err.to_string()));
+        }
+    };
+
+    let items = store
+        .get_all::<T>()
+        .serde()
+        .map_err(|e| StorageError::Error(e.to_string()))?
+        .await
+        .map_err(|e| StorageError::Error(e.to_string()))?;
+    //Skips anything that no longer deserializes instead of failing the whole list
+    Ok(items
+        .filter_map(|item| match item {
+            Ok(item) => Some(item),
+            Err(err) => {
+                log::error!("Error getting a record: {}", err);
+                None
+            }
+        })
+        .collect())
+}
+
 pub async fn object_delete(db: Database, store: &str, key: &str) -> Result<(), StorageError> {
     let transaction = match db
         .transaction(store)
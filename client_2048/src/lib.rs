@@ -1,18 +1,23 @@
 use crate::agent::{Postcard, StorageTask};
-use crate::at_repo_sync::AtRepoSync;
+use crate::at_repo_sync::{AtRepoSync, AtRepoSyncError};
+use crate::components::account_switcher::AccountSwitcher;
+use crate::components::session_banner::SessionBanner;
 use crate::components::theme_picker::ThemePicker;
-use crate::idb::{DB_NAME, SESSIONS_STORE, object_delete};
 use crate::oauth_client::oauth_client;
 use crate::pages::callback::CallbackPage;
+use crate::pages::friends::FriendsPage;
 use crate::pages::game::GamePage;
+use crate::pages::game_view::GameViewPage;
 use crate::pages::history::HistoryPage;
+use crate::pages::leaderboard::LeaderboardPage;
 use crate::pages::login::LoginPage;
 use crate::pages::seed::SeedPage;
+use crate::pages::settings::SettingsPage;
 use crate::pages::stats::StatsPage;
+use crate::session::{is_expired, report_expired, use_session_manager};
 use crate::store::UserStore;
 use atrium_api::agent::Agent;
 use gloo_utils::document;
-use indexed_db_futures::database::Database;
 use wasm_bindgen::JsCast;
 use web_sys::{HtmlElement, HtmlInputElement};
 use yew::platform::spawn_local;
@@ -22,14 +27,21 @@ use yew_hooks::use_effect_once;
 use yew_router::prelude::*;
 use yewdux::use_store;
 
+pub mod achievements;
 pub mod agent;
+mod appview;
 pub mod at_repo_sync;
 mod atrium_stores;
+mod bsky_post;
 mod components;
+pub mod controls;
 pub mod idb;
 pub mod oauth_client;
 mod pages;
 mod resolver;
+pub mod session;
+mod solver;
+pub mod streaks;
 pub mod store;
 
 #[derive(Clone, Routable, PartialEq)]
@@ -44,12 +56,20 @@ enum Route {
     CallbackPage,
     #[at("/stats")]
     StatsPage,
+    #[at("/stats/friends")]
+    FriendsPage,
     #[at("/seed/:seed")]
     SeedPage { seed: u32 },
     #[at("/seed")]
     SeedPageNoSeed,
     #[at("/history")]
     HistoryPage,
+    #[at("/leaderboard")]
+    LeaderboardPage,
+    #[at("/game/:did/:rkey")]
+    GameViewPage { did: String, rkey: String },
+    #[at("/settings")]
+    SettingsPage,
     #[not_found]
     #[at("/404")]
     NotFound,
@@ -62,11 +82,15 @@ fn switch(routes: Route) -> Html {
         Route::LoginPageWithDid { did } => html! { <LoginPage did={Some(did)} /> },
         Route::CallbackPage => html! { <CallbackPage /> },
         Route::StatsPage => html! { <StatsPage /> },
+        Route::FriendsPage => html! { <FriendsPage /> },
         Route::SeedPage { seed } => html! { <SeedPage starting_seed={seed} /> },
         Route::SeedPageNoSeed => html! { <SeedPage starting_seed={None} /> },
         Route::HistoryPage => {
             html! { <HistoryPage /> }
         }
+        Route::LeaderboardPage => html! { <LeaderboardPage /> },
+        Route::GameViewPage { did, rkey } => html! { <GameViewPage {did} {rkey} /> },
+        Route::SettingsPage => html! { <SettingsPage /> },
         Route::NotFound => html! { <h1>{ "404" }</h1> },
     }
 }
@@ -100,66 +124,43 @@ fn Main() -> Html {
         check_drawer_open();
     });
 
-    let user_store_clone = user_store.clone();
-    let dispatch_clone = dispatch.clone();
-    //logout callback
-    let onclick = Callback::from(move |_: MouseEvent| {
-        check_drawer_open();
-
-        let user_store = user_store_clone.clone();
-        let dispatch = dispatch_clone.clone();
-        spawn_local(async move {
-            let db = match Database::open(DB_NAME).await {
-                Ok(db) => db,
-                Err(err) => {
-                    log::error!("{:?}", err);
-                    return;
-                }
-            };
-
-            if let Some(did) = user_store.did.clone() {
-                dispatch.set(UserStore::default());
-
-                object_delete(db, SESSIONS_STORE, &did)
-                    .await
-                    .unwrap_or_else(|err| {
-                        log::error!("{:?}", err);
-                    })
-            }
-        });
+    //Logins from before multiple accounts need adding to the account list
+    use_effect_once(move || {
+        dispatch.reduce_mut(|store| store.list_active_account());
+        || ()
     });
 
-    let user_store_clone = user_store.clone();
-    use_effect_once(move || {
-        if user_store_clone.did.is_some() {
-            // Effect logic here
+    use_session_manager(user_store.did.clone());
+
+    //Pulls down the profile and stats for whichever account is being played as
+    use_effect_with(user_store.did.clone(), move |did| {
+        if let Some(did) = did.clone().filter(|did| !is_expired(did)) {
             spawn_local(async move {
-                match user_store_clone.did.clone() {
-                    None => {}
-                    Some(did) => {
-                        let oauth_client = oauth_client();
-                        let session = match oauth_client.restore(&did).await {
-                            Ok(session) => session,
-                            Err(err) => {
-                                log::error!("{:?}", err);
-                                return;
-                            }
-                        };
+                let oauth_client = oauth_client();
+                let session = match oauth_client.restore(&did).await {
+                    Ok(session) => session,
+                    Err(err) => {
+                        log::error!("{:?}", err);
+                        return;
+                    }
+                };
 
-                        let agent = Agent::new(session);
-                        let at_repo_sync = AtRepoSync::new_logged_in_repo(agent, did);
-                        match at_repo_sync.sync_profiles().await {
-                            Ok(_) => {}
-                            Err(err) => {
-                                log::error!("Error syncing your profile: {:?}", err.to_string());
-                            }
-                        }
-                        match at_repo_sync.sync_stats().await {
-                            Ok(_) => {}
-                            Err(err) => {
-                                log::error!("Error syncing stats: {:?}", err.to_string());
-                            }
-                        }
+                let agent = Agent::new(session);
+                let at_repo_sync = AtRepoSync::new_logged_in_repo(agent, did.clone());
+                match at_repo_sync.sync_profiles().await {
+                    Ok(_) => {}
+                    Err(AtRepoSyncError::AuthErrorNeedToReLogin) => {
+                        report_expired(&did);
+                        return;
+                    }
+                    Err(err) => {
+                        log::error!("Error syncing your profile: {:?}", err.to_string());
+                    }
+                }
+                match at_repo_sync.sync_stats().await {
+                    Ok(_) => {}
+                    Err(err) => {
+                        log::error!("Error syncing stats: {:?}", err.to_string());
                     }
                 }
             });
@@ -172,6 +173,8 @@ fn Main() -> Html {
         html! {<li key=1 onclick={menu_entry_onclick.clone()}><Link<Route> to={Route::GamePage}>{ "Play" }</Link<Route>></li>},
         html! {<li key=2 onclick={menu_entry_onclick.clone()}><Link<Route> to={Route::StatsPage}>{ "Stats" }</Link<Route>></li>},
         html! {<li key=3 onclick={menu_entry_onclick.clone()}><Link<Route> to={Route::HistoryPage}>{ "History" }</Link<Route>></li>},
+        html! {<li key=6 onclick={menu_entry_onclick.clone()}><Link<Route> to={Route::LeaderboardPage}>{ "Leaderboard" }</Link<Route>></li>},
+        html! {<li key=7 onclick={menu_entry_onclick.clone()}><Link<Route> to={Route::SettingsPage}>{ "Settings" }</Link<Route>></li>},
         //WIll come back to this, just buggy and clicks wrong entry sometimes
         // html! {
         // <li key={2}>
@@ -197,20 +200,27 @@ fn Main() -> Html {
         </li>
     });
 
-    if user_store.did.is_some() {
+    if user_store.accounts.is_empty() {
         links.push(html! {
-            <li key=5>
-                <a class="cursor-pointer" {onclick}>{ "Logout" }</a>
+            <li key=5 onclick={menu_entry_onclick.clone()}>
+                <Link<Route> to={Route::LoginPage}>{ "Login" }</Link<Route>>
             </li>
         });
     } else {
         links.push(html! {
-            <li key=5 {onclick}>
-                <Link<Route> to={Route::LoginPage}>{ "Login" }</Link<Route>>
+            <li key=5>
+                <AccountSwitcher onselect={Callback::from(|_| check_drawer_open())} />
             </li>
         });
     }
 
+    //Pages remount when switching accounts so they load that account's data
+    let main_key = user_store
+        .did
+        .as_ref()
+        .map(|did| did.to_string())
+        .unwrap_or_else(|| String::from("guest"));
+
     html! {
         <div class="drawer">
             <input id="my-drawer-3" type="checkbox" class="drawer-toggle" />
@@ -249,7 +259,8 @@ fn Main() -> Html {
                         <ThemePicker />
                     </div>
                 </div>
-                <main>
+                <SessionBanner />
+                <main key={main_key}>
                     <Switch<Route> render={switch} />
                 </main>
             </div>
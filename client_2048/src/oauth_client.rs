@@ -1,32 +1,45 @@
 use crate::atrium_stores::{IndexDBSessionStore, IndexDBStateStore};
-use crate::resolver::ApiDNSTxtResolver;
+use crate::idb::{DB_NAME, SESSIONS_STORE, object_get};
+use crate::resolver::{CachingDidResolver, OriginHandleResolver};
 use atrium_api::types::string::Did;
 use atrium_common::resolver::Resolver;
-use atrium_identity::{
-    did::{CommonDidResolver, CommonDidResolverConfig, DEFAULT_PLC_DIRECTORY_URL},
-    handle::{AtprotoHandleResolver, AtprotoHandleResolverConfig},
-};
+use atrium_oauth::store::session::Session;
 use atrium_oauth::{
-    AtprotoClientMetadata, AtprotoLocalhostClientMetadata, AuthMethod, DefaultHttpClient,
-    GrantType, KnownScope, OAuthClient, OAuthClientConfig, OAuthResolverConfig, Scope,
+    AtprotoLocalhostClientMetadata, KnownScope, OAuthClient, OAuthClientConfig,
+    OAuthResolverConfig, Scope,
 };
+use indexed_db_futures::database::Database;
+use oauth_2048::{all_scopes, client_metadata, posting_scopes};
 use std::sync::Arc;
 
 pub type OAuthClientType = Arc<
     OAuthClient<
         IndexDBStateStore,
         IndexDBSessionStore,
-        CommonDidResolver<DefaultHttpClient>,
-        AtprotoHandleResolver<ApiDNSTxtResolver, DefaultHttpClient>,
+        CachingDidResolver,
+        OriginHandleResolver,
     >,
 >;
 
+/// If the account's session was granted posting to Bluesky. Logins from before granular scopes
+/// have transition:generic which covers it
+pub async fn can_post(did: &Did) -> bool {
+    let Ok(db) = Database::open(DB_NAME).await else {
+        return false;
+    };
+    let granted = match object_get::<Session>(db, SESSIONS_STORE, did.as_str()).await {
+        Ok(Some(session)) => session.token_set.scope.unwrap_or_default(),
+        _ => return false,
+    };
+    let granted = granted.split(' ').collect::<Vec<_>>();
+    granted.contains(&Scope::Known(KnownScope::TransitionGeneric).as_ref())
+        || posting_scopes()
+            .iter()
+            .all(|scope| granted.contains(&scope.as_ref()))
+}
+
 pub async fn handle_resolve_from_did(did: Did) -> Option<String> {
-    let http_client = Arc::new(DefaultHttpClient::default());
-    let did_resolver = CommonDidResolver::new(CommonDidResolverConfig {
-        plc_directory_url: DEFAULT_PLC_DIRECTORY_URL.to_string(),
-        http_client: http_client.clone(),
-    });
+    let did_resolver = CachingDidResolver::default();
 
     let resolved_did = did_resolver.resolve(&did).await;
     match resolved_did {
@@ -46,37 +59,32 @@ pub async fn handle_resolve_from_did(did: Did) -> Option<String> {
     }
 }
 
+/// Where the client is served from, set with APP_ORIGIN at build time
+pub fn app_origin() -> String {
+    std::option_env!("APP_ORIGIN")
+        .unwrap_or("http://127.0.0.1:8080")
+        .to_string()
+}
+
 pub fn oauth_client() -> OAuthClientType {
     // Create a new OAuth client
-    let http_client = Arc::new(DefaultHttpClient::default());
     let session_store = IndexDBSessionStore::new();
     let state_store = IndexDBStateStore::new();
     let resolver = OAuthResolverConfig {
-        did_resolver: CommonDidResolver::new(CommonDidResolverConfig {
-            plc_directory_url: DEFAULT_PLC_DIRECTORY_URL.to_string(),
-            http_client: http_client.clone(),
-        }),
-        handle_resolver: AtprotoHandleResolver::new(AtprotoHandleResolverConfig {
-            dns_txt_resolver: ApiDNSTxtResolver::default(),
-            http_client: http_client.clone(),
-        }),
+        did_resolver: CachingDidResolver::default(),
+        handle_resolver: OriginHandleResolver::default(),
         authorization_server_metadata: Default::default(),
         protected_resource_metadata: Default::default(),
     };
 
-    let origin = std::option_env!("APP_ORIGIN")
-        .unwrap_or("http://127.0.0.1:8080")
-        .to_string();
+    let origin = app_origin();
 
     match origin.contains("127.0.0.1") {
         true => {
             let config = OAuthClientConfig {
                 client_metadata: AtprotoLocalhostClientMetadata {
                     redirect_uris: Some(vec![format!("{}/oauth/callback", origin)]),
-                    scopes: Some(vec![
-                        Scope::Known(KnownScope::Atproto),
-                        Scope::Known(KnownScope::TransitionGeneric),
-                    ]),
+                    scopes: Some(all_scopes()),
                 },
                 keys: None,
                 state_store,
@@ -86,21 +94,8 @@ pub fn oauth_client() -> OAuthClientType {
             Arc::new(OAuthClient::new(config).expect("failed to create OAuth client"))
         }
         false => {
-            let client_metadata = AtprotoClientMetadata {
-                client_id: format!("{}/client_metadata.json", origin),
-                client_uri: Some(origin.clone()),
-                redirect_uris: vec![format!("{}/oauth/callback", origin)],
-                token_endpoint_auth_method: AuthMethod::None,
-                grant_types: vec![GrantType::AuthorizationCode, GrantType::RefreshToken],
-                scopes: vec![
-                    Scope::Known(KnownScope::Atproto),
-                    Scope::Known(KnownScope::TransitionGeneric),
-                ],
-                jwks_uri: None,
-                token_endpoint_auth_signing_alg: None,
-            };
             let config = OAuthClientConfig {
-                client_metadata,
+                client_metadata: client_metadata(&origin),
                 keys: None,
                 state_store,
                 session_store,
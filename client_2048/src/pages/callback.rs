@@ -49,13 +49,11 @@ pub fn callback() -> Html {
                                     )
                                     .await;
                                     //HACK
-                                    dispatch.set(UserStore {
-                                        did: Some(did.clone()),
-                                        handle: match profile {
-                                            Ok(profile) => Some(profile.handle.clone()),
-                                            Err(_) => None,
-                                        },
-                                    });
+                                    let handle = match profile {
+                                        Ok(profile) => Some(profile.handle.clone()),
+                                        Err(_) => None,
+                                    };
+                                    dispatch.reduce_mut(|store| store.add_account(did.clone(), handle));
                                     let at_repo_sync = AtRepoSync::new_logged_in_repo(agent, did);
                                     match at_repo_sync.sync_profiles().await {
                                         Ok(_) => {}
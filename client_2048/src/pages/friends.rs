@@ -0,0 +1,282 @@
+use crate::Route;
+use crate::at_repo_sync::AtRepoSync;
+use crate::at_repo_sync::friends::{FriendStats, Social};
+use crate::store::UserStore;
+use atrium_api::agent::Agent;
+use atrium_api::types::string::{Datetime, Did};
+use numfmt::{Formatter, Precision};
+use std::rc::Rc;
+use web_sys::HtmlSelectElement;
+use yew::platform::spawn_local;
+use yew::prelude::*;
+use yew_router::prelude::Link;
+use yewdux::use_store;
+
+async fn load_friends_stats(
+    did: Did,
+    handle: Option<String>,
+    social: Social,
+    refresh: bool,
+) -> Result<Vec<FriendStats>, String> {
+    let oauth_client = crate::oauth_client::oauth_client();
+    let session = oauth_client
+        .restore(&did)
+        .await
+        .map_err(|err| err.to_string())?;
+    let agent = Agent::new(session);
+    let at_repo_sync = AtRepoSync::new_logged_in_repo(agent, did.clone());
+    let mut friends_stats = at_repo_sync
+        .get_friends_stats(&social, refresh)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    //The player is always on their own friends leaderboard
+    let own_stats = at_repo_sync.get_local_player_stats().await.unwrap_or(None);
+    friends_stats.retain(|friend| friend.did != did.as_str());
+    friends_stats.push(FriendStats {
+        did: did.to_string(),
+        handle: handle.unwrap_or(did.to_string()),
+        stats: own_stats,
+        fetched_at: Datetime::now(),
+    });
+
+    //Only people who have played are ranked
+    friends_stats.retain(|friend| friend.stats.is_some());
+    friends_stats.sort_by_key(|friend| {
+        std::cmp::Reverse(
+            friend
+                .stats
+                .as_ref()
+                .map(|stats| stats.highest_score)
+                .unwrap_or(0),
+        )
+    });
+    Ok(friends_stats)
+}
+
+#[function_component(FriendsPage)]
+pub fn friends() -> Html {
+    let (user_store, _) = use_store::<UserStore>();
+    let social: UseStateHandle<Option<Social>> = use_state(|| None);
+    let friends_stats: UseStateHandle<Option<Rc<Vec<FriendStats>>>> = use_state(|| None);
+    let error: UseStateHandle<Option<String>> = use_state(|| None);
+
+    //Starts with the social saved on the player's profile
+    {
+        let social = social.clone();
+        let did = user_store.did.clone();
+        use_effect_with(did, move |did| {
+            if let Some(did) = did.clone() {
+                spawn_local(async move {
+                    let profile = match crate::oauth_client::oauth_client().restore(&did).await {
+                        Ok(session) => {
+                            AtRepoSync::new_logged_in_repo(Agent::new(session), did)
+                                .get_local_player_profile()
+                                .await
+                        }
+                        Err(err) => {
+                            log::error!("{:?}", err);
+                            return;
+                        }
+                    };
+                    social.set(Some(match profile {
+                        Ok(Some(profile)) => Social::from_profile(&profile),
+                        _ => Social::default(),
+                    }));
+                });
+            }
+        });
+    }
+
+    {
+        let friends_stats = friends_stats.clone();
+        let error = error.clone();
+        let did = user_store.did.clone();
+        let handle = user_store.handle.as_ref().map(|handle| handle.to_string());
+        use_effect_with((*social).clone(), move |social| {
+            if let (Some(did), Some(social)) = (did, social.clone()) {
+                friends_stats.set(None);
+                error.set(None);
+                spawn_local(async move {
+                    match load_friends_stats(did, handle, social, false).await {
+                        Ok(result) => friends_stats.set(Some(Rc::new(result))),
+                        Err(err) => {
+                            log::error!("Error loading friends stats: {:?}", err);
+                            error.set(Some(err));
+                            friends_stats.set(Some(Rc::new(vec![])));
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    let social_onchange = {
+        let social = social.clone();
+        let did = user_store.did.clone();
+        Callback::from(move |event: Event| {
+            let target: HtmlSelectElement = event.target_unchecked_into();
+            let new_social = match target.value().as_str() {
+                "followers" => Social::Followers,
+                "mutuals" => Social::Mutuals,
+                _ => Social::Following,
+            };
+            social.set(Some(new_social.clone()));
+
+            //Remembers the choice on the player's profile
+            if let Some(did) = did.clone() {
+                spawn_local(async move {
+                    let session = match crate::oauth_client::oauth_client().restore(&did).await {
+                        Ok(session) => session,
+                        Err(err) => {
+                            log::error!("{:?}", err);
+                            return;
+                        }
+                    };
+                    let at_repo_sync = AtRepoSync::new_logged_in_repo(Agent::new(session), did);
+                    if let Ok(Some(mut profile)) = at_repo_sync.get_local_player_profile().await {
+                        profile.default_social = Some(new_social.as_str().to_string());
+                        if let Err(err) = at_repo_sync.update_a_player_profile(profile).await {
+                            log::error!("Error saving your profile: {:?}", err.to_string());
+                        }
+                    }
+                });
+            }
+        })
+    };
+
+    //Skips the cache and asks everyone's PDS again
+    let refresh_onclick = {
+        let social = social.clone();
+        let friends_stats = friends_stats.clone();
+        let error = error.clone();
+        let did = user_store.did.clone();
+        let handle = user_store.handle.as_ref().map(|handle| handle.to_string());
+        Callback::from(move |_: MouseEvent| {
+            if let (Some(did), Some(social)) = (did.clone(), (*social).clone()) {
+                let friends_stats = friends_stats.clone();
+                let error = error.clone();
+                let handle = handle.clone();
+                friends_stats.set(None);
+                error.set(None);
+                spawn_local(async move {
+                    match load_friends_stats(did, handle, social, true).await {
+                        Ok(result) => friends_stats.set(Some(Rc::new(result))),
+                        Err(err) => {
+                            log::error!("Error refreshing friends stats: {:?}", err);
+                            error.set(Some(err));
+                            friends_stats.set(Some(Rc::new(vec![])));
+                        }
+                    }
+                });
+            }
+        })
+    };
+
+    let viewer_did = user_store.did.as_ref().map(|did| did.to_string());
+    let mut number_formatter = Formatter::new()
+        .precision(Precision::Decimals(0))
+        .separator(',')
+        .expect("Could not build the number formatter.");
+
+    let content = match (user_store.did.is_some(), friends_stats.as_ref()) {
+        (false, _) => html! {
+            <div class="text-center">
+                <p>{ "Login with your AT Protocol account to see how your friends are doing." }</p>
+                <Link<Route> classes="btn btn-primary mt-2" to={Route::LoginPage}>
+                    { "Login" }
+                </Link<Route>>
+            </div>
+        },
+        (true, None) => html! {
+            <div class="flex items-center justify-center">
+                <span class="loading loading-spinner loading-lg" />
+                <h1 class="ml-4 text-3xl font-bold">{ "Loading..." }</h1>
+            </div>
+        },
+        //The player's own stats are in the list whenever they have played, so only others count
+        (true, Some(friends_stats))
+            if friends_stats
+                .iter()
+                .all(|friend| viewer_did.as_ref() == Some(&friend.did)) =>
+        {
+            html! {
+                <div class="flex items-center justify-center">
+                    <h1 class="pt-2 ml-4 text-3xl font-bold">
+                        { "None of your friends have played yet." }
+                    </h1>
+                </div>
+            }
+        }
+        (true, Some(friends_stats)) => html! {
+            <div class="overflow-x-auto">
+                <table class="table">
+                    <thead>
+                        <tr>
+                            <th>{ "Rank" }</th>
+                            <th>{ "Player" }</th>
+                            <th>{ "Highest score" }</th>
+                            <th>{ "Games played" }</th>
+                            <th>{ "2048s found" }</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        { friends_stats.iter().enumerate().filter_map(|(position, friend)| {
+                            let stats = friend.stats.as_ref()?;
+                            let is_viewer = viewer_did.as_ref() == Some(&friend.did);
+                            Some(html! {
+                                <tr key={friend.did.clone()} class={classes!(is_viewer.then_some("bg-accent text-accent-content"))}>
+                                    <th>{ position + 1 }</th>
+                                    <td>
+                                        <a
+                                            class="link link-hover"
+                                            href={format!("https://bsky.app/profile/{}", friend.did)}
+                                            target="_blank"
+                                        >
+                                            { friend.handle.clone() }
+                                        </a>
+                                    </td>
+                                    <td>{ number_formatter.fmt2(stats.highest_score).to_string() }</td>
+                                    <td>{ number_formatter.fmt2(stats.games_played).to_string() }</td>
+                                    <td>{ number_formatter.fmt2(stats.times_twenty_forty_eight_been_found).to_string() }</td>
+                                </tr>
+                            })
+                        }).collect::<Html>() }
+                    </tbody>
+                </table>
+            </div>
+        },
+    };
+
+    html! {
+        <div class="md:p-4 p-1">
+            <div class="max-w-4xl mx-auto space-y-6 justify-center">
+                <h1 class="text-4xl font-bold text-center md:mb-6 mb-1">{ "Friends" }</h1>
+                <div class="bg-base-100 shadow-lg rounded-lg md:p-6 p-1">
+                    <div class="w-full max-w-2xl mx-auto">
+                        if let Some(current_social) = social.as_ref() {
+                            <div class="flex flex-row gap-2 pb-4">
+                                <select class="select select-bordered w-full" onchange={social_onchange}>
+                                    { [Social::Following, Social::Followers, Social::Mutuals].into_iter().map(|option| {
+                                        html! {
+                                            <option value={option.as_str()} selected={option == *current_social}>
+                                                { option.to_string() }
+                                            </option>
+                                        }
+                                    }).collect::<Html>() }
+                                </select>
+                                <button class="btn btn-outline" onclick={refresh_onclick}>
+                                    { "Refresh" }
+                                </button>
+                            </div>
+                        }
+                        if let Some(err) = error.as_ref() {
+                            <span class="text-red-500">{ err }</span>
+                        }
+                        { content }
+                    </div>
+                </div>
+            </div>
+        </div>
+    }
+}
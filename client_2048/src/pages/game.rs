@@ -1,42 +1,72 @@
-use crate::Route;
-use crate::agent::{StorageRequest, StorageResponse, StorageTask};
+use crate::agent::{CompletedGame, PlayTime, StorageRequest, StorageResponse, StorageTask};
 use crate::at_repo_sync::AtRepoSyncError;
+use crate::bsky_post::ExternalLink;
+use crate::components::post_dialog::PostToBluesky;
+use crate::controls::{Control, GamepadPoller, KeyBindings};
 use crate::idb::{
-    CURRENT_GAME_STORE, DB_NAME, SELF_KEY, STATS_STORE, object_delete, object_get, transaction_put,
+    CURRENT_GAME_STORE, DB_NAME, PRACTICE_GAME_STORE, SELF_KEY, STATS_STORE, object_delete,
+    object_get, open_player_db, transaction_put,
 };
+use crate::session::{is_expired, report_expired};
 use crate::store::UserStore;
 use atrium_api::types::string::Datetime;
 use gloo::dialogs::{alert, confirm};
 use gloo::events::EventListener;
-use gloo::timers::callback::Timeout;
+use gloo::timers::callback::{Interval, Timeout};
 use indexed_db_futures::database::Database;
 use js_sys::encode_uri_component;
-use log::info;
 use numfmt::{Formatter, Precision};
 use serde::{Deserialize, Serialize};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use twothousand_forty_eight::direction::Direction;
+use twothousand_forty_eight::unified::hash::Hashable;
 use twothousand_forty_eight::{unified::game::GameState, v2::recording::SeededRecording};
 use types_2048::blue;
 use types_2048::blue::_2048::defs::SyncStatusData;
-use web_sys::{HtmlElement, wasm_bindgen::JsCast, wasm_bindgen::closure::Closure};
+use types_2048::game_mode::GameMode;
+use web_sys::{HtmlElement, HtmlSelectElement, wasm_bindgen::JsCast, wasm_bindgen::closure::Closure};
 use yew::platform::spawn_local;
 use yew::{
-    Callback, Html, Properties, Reducible, function_component, html, use_effect_with, use_mut_ref,
-    use_node_ref, use_reducer, use_state, use_state_eq,
+    Callback, Event, Html, MouseEvent, NodeRef, Properties, Reducible, TargetCast, UseStateHandle,
+    function_component, html, use_effect_with, use_mut_ref, use_node_ref, use_reducer, use_state,
+    use_state_eq,
 };
 use yew_agent::oneshot::use_oneshot_runner;
 use yew_hooks::use_effect_once;
-use yew_router::hooks::use_navigator;
 use yewdux::use_store;
 
+/// Practice games get their own store so they never mix with the ranked game
+fn game_store(practice: bool) -> &'static str {
+    match practice {
+        true => PRACTICE_GAME_STORE,
+        false => CURRENT_GAME_STORE,
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct State {
     gamestate: GameState,
     history: SeededRecording,
     message: String,
     hiscore: usize,
+    /// Hints or auto-play have been used this game
+    assisted: bool,
+    /// Practice games can be undone and are never saved to history or stats
+    practice: bool,
+    /// Moves that have been undone in practice mode, last undone is at the end
+    redo_moves: Vec<Direction>,
+    mode: GameMode,
+    /// When the first move was made in ms since the epoch. Starts the time attack clock
+    started_at: Option<f64>,
+    /// No moves are left or the mode's limit has been hit
+    finished: bool,
+    /// Active play time banked so far
+    duration_ms: i64,
+    /// When the current stretch of active play started in ms since the epoch. None while paused
+    resumed_at: Option<f64>,
+    /// Active play time when the first 2048 block was found
+    twenty_forty_eight_ms: Option<i64>,
     // current_game: game::RecordData,
 }
 
@@ -45,11 +75,32 @@ impl PartialEq for State {
         self.history == other.history
             && self.message == other.message
             && self.hiscore == other.hiscore
+            && self.assisted == other.assisted
+            && self.practice == other.practice
+            && self.redo_moves == other.redo_moves
+            && self.mode == other.mode
+            && self.started_at == other.started_at
+            && self.finished == other.finished
+            && self.duration_ms == other.duration_ms
+            && self.resumed_at == other.resumed_at
+            && self.twenty_forty_eight_ms == other.twenty_forty_eight_ms
     }
 }
 
 pub enum Action {
     Move(Direction),
+    /// Flags the game as using hints or auto-play so it stays off leaderboards
+    MarkAssisted,
+    /// Takes back the last move. Practice mode only
+    Undo,
+    /// Plays the last undone move again. Practice mode only
+    Redo,
+    /// The time attack clock ran out
+    TimeUp,
+    /// The page was hidden so play time stops counting
+    Pause,
+    /// The page is visible again
+    Resume,
 }
 
 impl Reducible for State {
@@ -58,6 +109,13 @@ impl Reducible for State {
     fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
         match action {
             Action::Move(direction) => {
+                if self.finished || self.limit_reached() {
+                    return Rc::new(State {
+                        message: self.finished_message(),
+                        finished: true,
+                        ..(*self).clone()
+                    });
+                }
                 if !self.gamestate.allowed_moves.contains(&direction) {
                     let mut message = "".to_string();
                     if self.gamestate.over {
@@ -68,10 +126,22 @@ impl Reducible for State {
                         history: self.history.clone(),
                         message,
                         hiscore: self.hiscore,
+                        assisted: self.assisted,
+                        practice: self.practice,
+                        redo_moves: self.redo_moves.clone(),
+                        mode: self.mode,
+                        started_at: self.started_at,
+                        finished: self.gamestate.over,
+                        duration_ms: self.duration_ms,
+                        resumed_at: self.resumed_at,
+                        twenty_forty_eight_ms: self.twenty_forty_eight_ms,
                     });
                 }
                 let mut new_history = self.history.clone();
                 new_history.moves.push(direction);
+                //Banks the time since the last move and keeps the clock running
+                let now = js_sys::Date::now();
+                let duration_ms = self.active_ms(now);
                 let history_string: String = (&new_history).into();
 
                 let mut state = match history_string.parse::<SeededRecording>() {
@@ -81,6 +151,18 @@ impl Reducible for State {
                             history,
                             message: String::new(),
                             hiscore: self.hiscore.max(gamestate.score_max),
+                            assisted: self.assisted,
+                            practice: self.practice,
+                            //A new move starts a new line so there is nothing to redo
+                            redo_moves: vec![],
+                            mode: self.mode,
+                            started_at: Some(self.started_at.unwrap_or(now)),
+                            finished: false,
+                            duration_ms,
+                            resumed_at: Some(now),
+                            twenty_forty_eight_ms: self
+                                .twenty_forty_eight_ms
+                                .or(gamestate.won.then_some(duration_ms)),
                         },
                         Err(e) => {
                             log::error!("{:?}", e);
@@ -89,6 +171,15 @@ impl Reducible for State {
                                 history: self.history.clone(),
                                 message: format!("{:?}", e),
                                 hiscore: self.hiscore,
+                                assisted: self.assisted,
+                                practice: self.practice,
+                                redo_moves: self.redo_moves.clone(),
+                                mode: self.mode,
+                                started_at: self.started_at,
+                                finished: self.finished,
+                                duration_ms: self.duration_ms,
+                                resumed_at: self.resumed_at,
+                                twenty_forty_eight_ms: self.twenty_forty_eight_ms,
                             }
                         }
                     },
@@ -97,25 +188,111 @@ impl Reducible for State {
                         history: self.history.clone(),
                         message: format!("{:?}", e),
                         hiscore: self.hiscore,
+                        assisted: self.assisted,
+                        practice: self.practice,
+                        redo_moves: self.redo_moves.clone(),
+                        mode: self.mode,
+                        started_at: self.started_at,
+                        finished: self.finished,
+                        duration_ms: self.duration_ms,
+                        resumed_at: self.resumed_at,
+                        twenty_forty_eight_ms: self.twenty_forty_eight_ms,
                     },
                 };
+                state.finished = state.gamestate.over || state.limit_reached();
                 let mut state_clone = state.clone();
                 spawn_local(async move {
                     state_clone.save().await;
                 });
-                if state.gamestate.over {
-                    state.message = "Game over".to_string();
+                if state.finished {
+                    state.message = state.finished_message();
                 }
 
                 //TODO spawn off to a worker to calculate new stats and save them
                 Rc::new(state)
             }
+            Action::MarkAssisted => {
+                if self.assisted {
+                    return self;
+                }
+                let mut state = (*self).clone();
+                state.assisted = true;
+                let mut state_clone = state.clone();
+                spawn_local(async move {
+                    state_clone.save().await;
+                });
+                Rc::new(state)
+            }
+            Action::Undo => {
+                if !self.practice || self.history.moves.is_empty() {
+                    return self;
+                }
+                let mut history = self.history.clone();
+                let mut redo_moves = self.redo_moves.clone();
+                if let Some(direction) = history.moves.pop() {
+                    redo_moves.push(direction);
+                }
+                self.replay(history, redo_moves)
+            }
+            Action::Redo => {
+                if !self.practice || self.redo_moves.is_empty() {
+                    return self;
+                }
+                let mut history = self.history.clone();
+                let mut redo_moves = self.redo_moves.clone();
+                if let Some(direction) = redo_moves.pop() {
+                    history.moves.push(direction);
+                }
+                self.replay(history, redo_moves)
+            }
+            Action::TimeUp => {
+                if self.finished {
+                    return self;
+                }
+                let state = State {
+                    message: "Time's up".to_string(),
+                    finished: true,
+                    duration_ms: self.active_ms(js_sys::Date::now()),
+                    resumed_at: None,
+                    ..(*self).clone()
+                };
+                let mut state_clone = state.clone();
+                spawn_local(async move {
+                    state_clone.save().await;
+                });
+                Rc::new(state)
+            }
+            Action::Pause => {
+                if self.resumed_at.is_none() {
+                    return self;
+                }
+                let state = State {
+                    duration_ms: self.active_ms(js_sys::Date::now()),
+                    resumed_at: None,
+                    ..(*self).clone()
+                };
+                let mut state_clone = state.clone();
+                spawn_local(async move {
+                    state_clone.save().await;
+                });
+                Rc::new(state)
+            }
+            Action::Resume => {
+                //The clock only runs once the game has started and until it is over
+                if self.resumed_at.is_some() || self.history.moves.is_empty() || self.finished {
+                    return self;
+                }
+                Rc::new(State {
+                    resumed_at: Some(js_sys::Date::now()),
+                    ..(*self).clone()
+                })
+            }
         }
     }
 }
 
 impl State {
-    pub fn new() -> Self {
+    pub fn new(practice: bool, mode: GameMode) -> Self {
         let random_seed = rand::random();
         let history = SeededRecording::empty(random_seed, 4, 4);
         let gamestate = GameState::from_reconstructable_ruleset(&history).unwrap();
@@ -124,33 +301,130 @@ impl State {
             history,
             message: "".to_string(),
             hiscore: 0,
+            assisted: false,
+            practice,
+            redo_moves: vec![],
+            mode,
+            started_at: None,
+            finished: false,
+            duration_ms: 0,
+            resumed_at: None,
+            twenty_forty_eight_ms: None,
         }
     }
 
+    /// Active play time up to now, including the stretch that is still running
+    fn active_ms(&self, now: f64) -> i64 {
+        self.duration_ms
+            + self
+                .resumed_at
+                .map(|resumed_at| (now - resumed_at).max(0.0) as i64)
+                .unwrap_or(0)
+    }
+
+    fn play_time(&self) -> PlayTime {
+        PlayTime {
+            duration_ms: self.duration_ms,
+            twenty_forty_eight_ms: self.twenty_forty_eight_ms,
+        }
+    }
+
+    /// Ms left on the time attack clock. None for other modes
+    fn time_left_ms(&self) -> Option<f64> {
+        match self.mode {
+            GameMode::TimeAttack(seconds) => {
+                let elapsed = self
+                    .started_at
+                    .map(|started_at| js_sys::Date::now() - started_at)
+                    .unwrap_or(0.0);
+                Some((seconds as f64 * 1000.0 - elapsed).max(0.0))
+            }
+            _ => None,
+        }
+    }
+
+    /// The clock ran out or every move has been used
+    fn limit_reached(&self) -> bool {
+        match self.mode {
+            GameMode::Classic => false,
+            GameMode::TimeAttack(_) => self.time_left_ms() == Some(0.0),
+            GameMode::MoveLimit(moves) => self.history.moves.len() >= moves as usize,
+        }
+    }
+
+    fn finished_message(&self) -> String {
+        match self.mode {
+            _ if self.gamestate.over => "Game over".to_string(),
+            GameMode::TimeAttack(_) => "Time's up".to_string(),
+            GameMode::MoveLimit(_) => "Out of moves".to_string(),
+            GameMode::Classic => "Game over".to_string(),
+        }
+    }
+
+    /// Rebuilds the game from a changed recording, used for undo and redo
+    fn replay(&self, history: SeededRecording, redo_moves: Vec<Direction>) -> Rc<Self> {
+        let gamestate = match GameState::from_reconstructable_ruleset(&history) {
+            Ok(gamestate) => gamestate,
+            Err(e) => {
+                log::error!("{:?}", e);
+                return Rc::new(State {
+                    message: format!("{:?}", e),
+                    ..self.clone()
+                });
+            }
+        };
+        let mut state = State {
+            message: String::new(),
+            gamestate,
+            history,
+            redo_moves,
+            ..self.clone()
+        };
+        state.finished = state.gamestate.over || state.limit_reached();
+        if state.finished {
+            state.message = state.finished_message();
+        }
+        let mut state_clone = state.clone();
+        spawn_local(async move {
+            state_clone.save().await;
+        });
+        state.hiscore = state.hiscore.max(state.gamestate.score_max);
+        Rc::new(state)
+    }
+
     pub async fn save(&mut self) {
         let history_string: String = (&self.history).into();
 
         let db = match Database::open(DB_NAME).await {
             Ok(db) => db,
             Err(err) => {
-                panic!("Error opening database: {:?}", err);
+                log::error!("Error opening database: {:?}", err);
+                return;
             }
         };
 
         let mut current_game = match object_get::<blue::_2048::game::RecordData>(
             db.clone(),
-            CURRENT_GAME_STORE,
+            game_store(self.practice),
             SELF_KEY,
         )
         .await
         {
             Ok(current_game) => match current_game {
                 Some(current_game) => current_game,
+                //Not made till the first move since load takes its created_at as when the clock
+                //started. Anything before then, like being marked assisted, is in the first save
+                None if self.history.moves.is_empty() => return,
                 None => {
                     let current_game = blue::_2048::game::RecordData {
+                        assisted: self.assisted.then_some(true),
                         completed: self.gamestate.over,
                         created_at: Datetime::now(),
                         current_score: self.gamestate.score_current as i64,
+                        duration_ms: Some(self.duration_ms),
+                        limit: self.mode.record_limit(),
+                        mode: self.mode.record_mode(),
+                        move_count: Some(self.history.moves.len() as i64),
                         seeded_recording: history_string,
                         sync_status: SyncStatusData {
                             created_at: Datetime::now(),
@@ -159,12 +433,13 @@ impl State {
                             updated_at: Datetime::now(),
                         }
                         .into(),
+                        time_to_find_twenty_forty_eight_ms: self.twenty_forty_eight_ms,
                         won: self.gamestate.won,
                     };
                     let result = transaction_put(
                         db.clone(),
                         current_game.clone(),
-                        CURRENT_GAME_STORE,
+                        game_store(self.practice),
                         Some(SELF_KEY.to_string()),
                     )
                     .await;
@@ -186,11 +461,15 @@ impl State {
 
         current_game.current_score = self.gamestate.score_current as i64;
         current_game.seeded_recording = history_string;
+        current_game.assisted = self.assisted.then_some(true);
+        current_game.duration_ms = Some(self.duration_ms);
+        current_game.move_count = Some(self.history.moves.len() as i64);
+        current_game.time_to_find_twenty_forty_eight_ms = self.twenty_forty_eight_ms;
 
         let result = transaction_put(
             db.clone(),
             current_game,
-            CURRENT_GAME_STORE,
+            game_store(self.practice),
             Some(SELF_KEY.to_string()),
         )
         .await;
@@ -202,17 +481,17 @@ impl State {
         };
     }
 
-    pub async fn load() -> Option<Self> {
+    pub async fn load(practice: bool) -> Option<Self> {
         let db = match Database::open(DB_NAME).await {
             Ok(db) => db,
             Err(err) => {
-                panic!("Error opening database: {:?}", err);
-                // return Err(AtRepoSyncError::ThereWasAnError(err.to_string()));
+                log::error!("Error opening database: {:?}", err);
+                return None;
             }
         };
 
         let current_game =
-            match object_get::<blue::_2048::game::RecordData>(db, CURRENT_GAME_STORE, SELF_KEY)
+            match object_get::<blue::_2048::game::RecordData>(db, game_store(practice), SELF_KEY)
                 .await
             {
                 Ok(current_game) => match current_game {
@@ -244,12 +523,30 @@ impl State {
             }
         };
         let hiscore = gamestate.score_max;
-        Some(Self {
+        //The record is first saved on the first move so that is when the clock started
+        let started_at = (!history.moves.is_empty())
+            .then(|| current_game.created_at.as_ref().timestamp_millis() as f64);
+        let mut state = Self {
             history,
             message: "".to_string(),
             gamestate,
             hiscore,
-        })
+            assisted: current_game.assisted.unwrap_or(false),
+            practice,
+            redo_moves: vec![],
+            mode: GameMode::from_record(current_game.mode.as_deref(), current_game.limit)
+                .unwrap_or_default(),
+            started_at,
+            finished: false,
+            duration_ms: current_game.duration_ms.unwrap_or(0),
+            resumed_at: None,
+            twenty_forty_eight_ms: current_game.time_to_find_twenty_forty_eight_ms,
+        };
+        state.finished = state.gamestate.over || state.limit_reached();
+        if state.finished {
+            state.message = state.finished_message();
+        }
+        Some(state)
     }
 }
 
@@ -404,6 +701,10 @@ pub fn tile(props: &TileProps) -> Html {
 
 pub enum ScoreBoardAction {
     NewGame,
+    /// Switches between the ranked game and the practice game
+    TogglePractice,
+    /// Starts a new game with different rules
+    ChangeMode(GameMode),
 }
 
 #[derive(Properties, PartialEq, Clone)]
@@ -412,6 +713,13 @@ pub struct ScoreboardProps {
     pub hiscore: usize,
     pub message: String,
     pub action: Callback<ScoreBoardAction>,
+    pub mode: GameMode,
+    pub moves: usize,
+    /// Ms left on the time attack clock
+    pub time_left_ms: Option<f64>,
+    /// Focused when the game ends so keyboard players can start the next one
+    #[prop_or_default]
+    pub new_game_ref: NodeRef,
 }
 
 #[function_component(ScoreBoard)]
@@ -421,6 +729,10 @@ pub fn scoreboard(props: &ScoreboardProps) -> Html {
         hiscore,
         message,
         action,
+        mode,
+        moves,
+        time_left_ms,
+        new_game_ref,
     } = props.clone();
     let hiscore_to_display = if score > hiscore { score } else { hiscore };
 
@@ -449,9 +761,31 @@ pub fn scoreboard(props: &ScoreboardProps) -> Html {
                         <div class="stat-title">{ "Best" }</div>
                         <div class="stat-value">{ number_formatter.fmt2(hiscore_to_display) }</div>
                     </div>
+                    { match mode {
+                        GameMode::Classic => html! {},
+                        GameMode::TimeAttack(_) => {
+                            let seconds_left = (time_left_ms.unwrap_or(0.0) / 1000.0).ceil() as u32;
+                            html! {
+                                <div class="stat">
+                                    <div class="stat-title">{ "Time" }</div>
+                                    <div class="stat-value">
+                                        { format!("{}:{:02}", seconds_left / 60, seconds_left % 60) }
+                                    </div>
+                                </div>
+                            }
+                        }
+                        GameMode::MoveLimit(limit) => html! {
+                            <div class="stat">
+                                <div class="stat-title">{ "Moves" }</div>
+                                <div class="stat-value">{ format!("{}/{}", moves, limit) }</div>
+                            </div>
+                        },
+                    } }
                 </div>
                 <div class="flex flex-col items-center justify-center mx-5">
-                    <button {onclick} class="btn btn-outline btn-sm">{ "New game" }</button>
+                    <button {onclick} ref={new_game_ref} class="btn btn-outline btn-sm">
+                        { "New game" }
+                    </button>
                 </div>
             </div>
             <div class="text-center md:mt-4 mt-2">
@@ -461,35 +795,35 @@ pub fn scoreboard(props: &ScoreboardProps) -> Html {
     }
 }
 
-fn _emoji_board(tile_values: Vec<usize>) -> String {
-    let mut emoji_board = String::new();
-    let mut column_count = 0;
-    for tile_value in tile_values {
-        let emoji = match tile_value {
-            2 | 4 => '⬜',
-            8 => '🟧',
-            16 => '🟫',
-            32 | 64 => '🟥',
-            128 | 256 | 1024 => '🟨',
-            2048 => '⭐',
-            _ => '🤯',
-        };
-        emoji_board.push(emoji);
-        if column_count == 3 {
-            emoji_board.push_str("\n");
-            column_count = 0;
-        } else {
-            column_count += 1;
-        }
-    }
-    emoji_board
+/// The board as rows of emoji squares for sharing, works for any board size
+pub fn emoji_board(board: &twothousand_forty_eight::board::Board) -> String {
+    (0..board.height)
+        .map(|y| {
+            (0..board.width)
+                .map(|x| match board.tiles[y][x].map(|tile| tile.value) {
+                    None => '⬛',
+                    Some(2 | 4) => '⬜',
+                    Some(8 | 16) => '🟧',
+                    Some(32 | 64) => '🟥',
+                    Some(128 | 256 | 512 | 1024) => '🟨',
+                    Some(2048) => '⭐',
+                    Some(_) => '🤯',
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 #[derive(Properties, PartialEq, Clone)]
 struct ShareButtonProps {
     score: usize,
     seed: u32,
-    // emoji_board: String,
+    /// The final board from emoji_board
+    emoji_board: String,
+    board: twothousand_forty_eight::board::Board,
+    /// Used to find the saved game so the post can link to it
+    game_hash: String,
 }
 
 #[function_component(ShareGameButtons)]
@@ -500,16 +834,18 @@ fn bsky_buttons(props: &ShareButtonProps) -> Html {
         .expect("Could not build the number formatter.");
     let score = number_formatter.fmt2(props.score).to_string();
     let normal_share_display_text = format!(
-        "I just scored {} on a game of at://2048.\nThink you can do better? Join in on the fun with @2048.blue.\n\nhttps://2048.blue",
-        score.clone()
+        "I just scored {} on a game of at://2048.\n\n{}\n\nThink you can do better? Join in on the fun with @2048.blue.\n\nhttps://2048.blue",
+        score.clone(),
+        props.emoji_board
     );
 
     let seed_redirect_url = format!("https://2048.blue/seed/{}", props.seed.to_string());
 
     let seeded_share = format!(
-        "I just scored {} on a game of at://2048 with a starting seed of {}.\nThink you can do better with this exact same seed? Try it out here {} \n @2048.blue",
+        "I just scored {} on a game of at://2048 with a starting seed of {}.\n\n{}\n\nThink you can do better with this exact same seed? Try it out here {} \n @2048.blue",
         score.clone(),
         props.seed.to_string(),
+        props.emoji_board,
         seed_redirect_url.clone()
     );
 
@@ -551,10 +887,79 @@ fn bsky_buttons(props: &ShareButtonProps) -> Html {
                 { "Share" }
                 { bsky_logo }
             </a>
+            <PostToBluesky
+                text={normal_share_display_text.clone()}
+                link={ExternalLink {
+                    uri: String::from("https://2048.blue"),
+                    title: String::from("at://2048"),
+                    description: format!("I just scored {} on a game of at://2048. Think you can do better?", score),
+                }}
+                board={Some(props.board)}
+                score={props.score}
+                game_hash={Some(props.game_hash.clone())}
+            />
         </div>
     )
 }
 
+/// Delay between auto-play moves so you can follow along
+const AUTO_PLAY_DELAY_MS: u32 = 250;
+/// How often the time attack clock updates
+const CLOCK_TICK_MS: u32 = 250;
+
+fn direction_label(direction: Direction) -> &'static str {
+    match direction {
+        Direction::UP => "⬆ Up",
+        Direction::DOWN => "⬇ Down",
+        Direction::LEFT => "⬅ Left",
+        Direction::RIGHT => "➡ Right",
+        _ => "",
+    }
+}
+
+/// What a screen reader says after a move, like "Moved left. Merged two 64s into 128. Score 1,204"
+fn move_announcement(state: &State) -> String {
+    let Some(direction) = state.history.moves.last() else {
+        return String::from("New game started");
+    };
+    let mut number_formatter = Formatter::new()
+        .precision(Precision::Decimals(0))
+        .separator(',')
+        .expect("Could not build the number formatter.");
+    let mut merges = state
+        .gamestate
+        .board
+        .tiles
+        .iter()
+        .flatten()
+        .filter_map(|tile| *tile)
+        .filter(|tile| tile.merged_from.is_some())
+        .map(|tile| tile.value)
+        .collect::<Vec<_>>();
+    merges.sort_unstable_by(|a, b| b.cmp(a));
+    let direction = match *direction {
+        Direction::UP => "up",
+        Direction::DOWN => "down",
+        Direction::LEFT => "left",
+        Direction::RIGHT => "right",
+        _ => "",
+    };
+    let mut announcement = vec![format!("Moved {}", direction)];
+    announcement.extend(
+        merges
+            .into_iter()
+            .map(|value| format!("Merged two {}s into {}", value / 2, value)),
+    );
+    announcement.push(format!(
+        "Score {}",
+        number_formatter.fmt2(state.gamestate.score_current)
+    ));
+    if state.finished {
+        announcement.push(state.message.clone());
+    }
+    announcement.join(". ")
+}
+
 #[derive(Properties, PartialEq, Clone)]
 pub struct GameProps {
     state: State,
@@ -564,23 +969,138 @@ pub struct GameProps {
 #[function_component(Board)]
 pub fn board(game_props: &GameProps) -> Html {
     let state = use_reducer(|| game_props.state.clone());
-    let (user_store, dispatch) = use_store::<UserStore>();
+    let (user_store, _) = use_store::<UserStore>();
     let move_delay: Rc<RefCell<Option<Timeout>>> = use_mut_ref(|| None);
     let storage_action_not_running = use_state(|| true);
-    let navigator = use_navigator().unwrap();
+    let save_error: UseStateHandle<Option<String>> = use_state(|| None);
 
     let storage_task = use_oneshot_runner::<StorageTask>();
     let storage_agent = storage_task.clone();
     let hiscore = use_state_eq(|| 0);
+    let suggestion: UseStateHandle<Option<Direction>> = use_state(|| None);
+    let auto_play = use_state(|| false);
+    let time_left_ms = use_state(|| game_props.state.time_left_ms());
+
+    //Play time only counts while the page can be seen
+    {
+        let state = state.clone();
+        use_effect_with((), move |_| {
+            let listener =
+                EventListener::new(&gloo::utils::document(), "visibilitychange", move |_| {
+                    match gloo::utils::document().hidden() {
+                        true => state.dispatch(Action::Pause),
+                        false => state.dispatch(Action::Resume),
+                    }
+                });
+            move || drop(listener)
+        });
+    }
+
+    //Keeps the time attack clock ticking once the first move is made and ends the game when it runs out
+    {
+        let state = state.clone();
+        let time_left_ms = time_left_ms.clone();
+        use_effect_with(
+            (state.mode, state.started_at, state.finished),
+            move |(mode, started_at, finished)| {
+                let clock = (matches!(mode, GameMode::TimeAttack(_))
+                    && started_at.is_some()
+                    && !*finished)
+                    .then(|| {
+                        Interval::new(CLOCK_TICK_MS, move || {
+                            let left = state.time_left_ms();
+                            time_left_ms.set(left);
+                            if left == Some(0.0) {
+                                state.dispatch(Action::TimeUp);
+                            }
+                        })
+                    });
+                move || drop(clock)
+            },
+        );
+    }
+
+    //A suggestion is only good for the board it was made for
+    {
+        let suggestion = suggestion.clone();
+        use_effect_with(state.history.moves.len(), move |_| suggestion.set(None));
+    }
+
+    let hint_onclick = {
+        let state = state.clone();
+        let suggestion = suggestion.clone();
+        let storage_task = storage_task.clone();
+        Callback::from(move |_: MouseEvent| {
+            state.dispatch(Action::MarkAssisted);
+            let history_string: String = (&state.history).into();
+            let suggestion = suggestion.clone();
+            let storage_task = storage_task.clone();
+            spawn_local(async move {
+                match storage_task
+                    .run(StorageRequest::SuggestMove(history_string))
+                    .await
+                {
+                    StorageResponse::SuggestedMove(direction) => suggestion.set(direction),
+                    StorageResponse::Error(err) => {
+                        log::error!("Error finding a hint: {:?}", err.to_string());
+                    }
+                    _ => {}
+                }
+            });
+        })
+    };
+
+    let auto_play_onchange = {
+        let state = state.clone();
+        let auto_play = auto_play.clone();
+        Callback::from(move |_: Event| {
+            if !*auto_play {
+                state.dispatch(Action::MarkAssisted);
+            }
+            auto_play.set(!*auto_play);
+        })
+    };
+
+    //Auto-play asks the worker for the best move after every move until the game is over
+    {
+        let state = state.clone();
+        let storage_task = storage_task.clone();
+        use_effect_with(
+            (*auto_play, state.history.moves.len(), state.finished),
+            move |(auto_play, _, over)| {
+                let still_playing = Rc::new(Cell::new(*auto_play && !*over));
+                if still_playing.get() {
+                    let history_string: String = (&state.history).into();
+                    let still_playing = still_playing.clone();
+                    spawn_local(async move {
+                        let response = storage_task
+                            .run(StorageRequest::SuggestMove(history_string))
+                            .await;
+                        if let StorageResponse::SuggestedMove(Some(direction)) = response {
+                            Timeout::new(AUTO_PLAY_DELAY_MS, move || {
+                                if still_playing.get() {
+                                    state.dispatch(Action::Move(direction));
+                                }
+                            })
+                            .forget();
+                        }
+                    });
+                }
+                move || still_playing.set(false)
+            },
+        );
+    }
 
     //Gets the current hiscore and compares
-    use_effect_with(hiscore.clone(), move |hiscore| {
+    use_effect_with((hiscore.clone(), user_store.did.clone()), move |(hiscore, did)| {
         let hiscore = hiscore.clone();
+        let did = did.clone();
         spawn_local(async move {
-            let db = match Database::open(DB_NAME).await {
+            let db = match open_player_db(did.as_ref()).await {
                 Ok(db) => db,
                 Err(err) => {
-                    panic!("Error opening database: {:?}", err);
+                    log::error!("Error opening database: {:?}", err);
+                    return;
                 }
             };
             match object_get::<blue::_2048::player::stats::RecordData>(db, STATS_STORE, SELF_KEY)
@@ -588,7 +1108,7 @@ pub fn board(game_props: &GameProps) -> Html {
             {
                 Ok(stats) => match stats {
                     Some(stats) => {
-                        hiscore.set(stats.highest_score.clone());
+                        hiscore.set(stats.highest_score);
                     }
                     None => {}
                 },
@@ -600,14 +1120,29 @@ pub fn board(game_props: &GameProps) -> Html {
     });
 
     let game_over_state = state.clone();
-    use_effect_with(state.gamestate.over, move |gameover| {
-        if *gameover {
+    let game_over_save_error = save_error.clone();
+    use_effect_with(state.finished, move |gameover| {
+        //Practice games never count
+        if *gameover && !game_over_state.practice {
             storage_action_not_running.set(false);
+            game_over_save_error.set(None);
             let history_string: String = (&game_over_state.history.clone()).into();
+            let assisted = game_over_state.assisted;
+            let mode = game_over_state.mode;
+            let play_time = game_over_state.play_time();
             let did = user_store.did.clone();
+            let paused = did.as_ref().is_some_and(is_expired);
             let storage_action_not_running_clone = storage_action_not_running.clone();
+            let save_error = game_over_save_error.clone();
             spawn_local(async move {
-                let request = StorageRequest::GameCompleted(history_string, did.clone());
+                let request = StorageRequest::GameCompleted(CompletedGame {
+                    game_history: history_string,
+                    did: did.clone(),
+                    assisted,
+                    mode,
+                    play_time,
+                    paused,
+                });
                 let result = storage_agent.run(request).await;
                 match result {
                     StorageResponse::Error(err) => {
@@ -618,25 +1153,17 @@ pub fn board(game_props: &GameProps) -> Html {
                     }
                     StorageResponse::RepoError(error) => {
                         log::error!("Error saving game: {:?}", error.to_string());
-                        match error {
-                            AtRepoSyncError::AuthErrorNeedToReLogin => {
-                                match confirm(
-                                    "Your AT Protocol session has expired. You need to relogin to save your game to your profile. Press confirm to be redirected to login page.",
-                                ) {
-                                    true => {
-                                        if let Some(did) = did.as_ref() {
-                                            info!("Here");
-                                            navigator.push(&Route::LoginPageWithDid {
-                                                did: did.to_string(),
-                                            })
-                                        }
-                                    }
-                                    false => {
-                                        dispatch.set(UserStore::default());
-                                    }
-                                }
+                        match (error, did.as_ref()) {
+                            (AtRepoSyncError::AuthErrorNeedToReLogin, Some(did)) => {
+                                report_expired(did)
                             }
-                            _ => {}
+                            (error, _) => save_error.set(Some(error.message())),
+                        }
+                    }
+                    StorageResponse::SyncPaused => {
+                        storage_action_not_running_clone.set(true);
+                        if let Some(did) = did.as_ref() {
+                            report_expired(did);
                         }
                     }
                     _ => {
@@ -648,20 +1175,50 @@ pub fn board(game_props: &GameProps) -> Html {
     });
     //Syncs the completed game with your pds and saves it locally
 
+    //Moves with a gamepad's d-pad or left stick
+    {
+        let state = state.clone();
+        use_effect_with((), move |_| {
+            let poller = GamepadPoller::start(move |direction| state.dispatch(Action::Move(direction)));
+            move || drop(poller)
+        });
+    }
+
     // Setup keyboard event listener
+    let key_bindings = use_state(KeyBindings::load);
     use_effect_with(state.clone(), {
         let move_delay = move_delay.clone(); // Clone the Rc pointer for this closure
+        let key_bindings = (*key_bindings).clone();
         move |state| {
             let state = state.clone();
             let listener = EventListener::new(&gloo::utils::document(), "keydown", move |event| {
                 if let Some(event) = event.dyn_ref::<web_sys::KeyboardEvent>() {
-                    let direction = match event.key().as_str() {
-                        "k" | "w" | "ArrowUp" => Direction::UP,
-                        "j" | "s" | "ArrowDown" => Direction::DOWN,
-                        "h" | "a" | "ArrowLeft" => Direction::LEFT,
-                        "l" | "d" | "ArrowRight" => Direction::RIGHT,
-                        _ => return,
+                    //Typing in a text box or picking from a select should not move the board
+                    let in_form_field = event
+                        .target()
+                        .and_then(|target| target.dyn_into::<HtmlElement>().ok())
+                        .map(|element| {
+                            matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+                                || element.is_content_editable()
+                        })
+                        .unwrap_or(false);
+                    if in_form_field || event.ctrl_key() || event.meta_key() || event.alt_key() {
+                        return;
+                    }
+                    let Some(control) = key_bindings.control_for(&event.key()) else {
+                        return;
                     };
+                    //Undo and redo only do anything in practice mode
+                    let direction = match control {
+                        Control::Undo => return state.dispatch(Action::Undo),
+                        Control::Redo => return state.dispatch(Action::Redo),
+                        control => match control.direction() {
+                            Some(direction) => direction,
+                            None => return,
+                        },
+                    };
+                    //Stops the arrow keys from scrolling the page while playing
+                    event.prevent_default();
                     let cloned_state = state.clone();
                     *move_delay.borrow_mut() = Some(Timeout::new(150, {
                         let move_delay_timer = move_delay.clone();
@@ -681,6 +1238,23 @@ pub fn board(game_props: &GameProps) -> Html {
 
     //Touches
     let board_ref = use_node_ref();
+    let new_game_ref = use_node_ref();
+
+    //A new game puts focus on the board so the arrow keys work straight away, and the end of a
+    //game moves it to the new game button
+    {
+        let board_ref = board_ref.clone();
+        let new_game_ref = new_game_ref.clone();
+        use_effect_with(state.finished, move |finished| {
+            let focus_on = match finished {
+                true => new_game_ref,
+                false => board_ref,
+            };
+            if let Some(element) = focus_on.cast::<HtmlElement>() {
+                let _ = element.focus();
+            }
+        });
+    }
     let touch_start = Rc::new(RefCell::new((0, 0))); // Use RefCell for mutable state
 
     {
@@ -795,7 +1369,47 @@ pub fn board(game_props: &GameProps) -> Html {
                 action.emit(ScoreBoardAction::NewGame);
                 // cloned_state_for_callback.dispatch(Action::Move(Direction::RIGHT));
             }
+            ScoreBoardAction::TogglePractice => action.emit(ScoreBoardAction::TogglePractice),
+            ScoreBoardAction::ChangeMode(mode) => action.emit(ScoreBoardAction::ChangeMode(mode)),
         });
+
+    let mode_onchange = {
+        let score_board_callback = score_board_callback.clone();
+        let in_progress = !state.history.moves.is_empty() && !state.finished;
+        let current_mode = state.mode;
+        Callback::from(move |event: Event| {
+            let target: HtmlSelectElement = event.target_unchecked_into();
+            let Some(mode) = target
+                .value()
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| GameMode::options().get(index).copied())
+            else {
+                return;
+            };
+            if in_progress && !confirm("Changing the mode starts a new game. Keep going?") {
+                //Puts the select back on the mode being played
+                if let Some(index) = GameMode::options().iter().position(|option| *option == current_mode) {
+                    target.set_value(&index.to_string());
+                }
+                return;
+            }
+            score_board_callback.emit(ScoreBoardAction::ChangeMode(mode));
+        })
+    };
+
+    let practice_onchange = {
+        let score_board_callback = score_board_callback.clone();
+        Callback::from(move |_: Event| score_board_callback.emit(ScoreBoardAction::TogglePractice))
+    };
+    let undo_onclick = {
+        let state = state.clone();
+        Callback::from(move |_: MouseEvent| state.dispatch(Action::Undo))
+    };
+    let redo_onclick = {
+        let state = state.clone();
+        Callback::from(move |_: MouseEvent| state.dispatch(Action::Redo))
+    };
     html! {
         <div class="flex flex-col ">
             <ScoreBoard
@@ -803,18 +1417,126 @@ pub fn board(game_props: &GameProps) -> Html {
                 hiscore={*hiscore as usize}
                 message={state.message.clone()}
                 action={score_board_callback.clone()}
+                mode={state.mode}
+                moves={state.history.moves.len()}
+                time_left_ms={*time_left_ms}
+                new_game_ref={new_game_ref}
             />
-            if state.gamestate.over {
-                // <ShareGameButtons score={state.hiscore} seed={state.history.seed} emoji_board={emoji_board(flatten_tiles.iter().map(|tile| tile.value).collect::<Vec<_>>())}/>
-                <ShareGameButtons score={state.hiscore} seed={state.history.seed} />
+            <div class="flex flex-row items-center justify-center gap-4">
+                <select class="select select-bordered select-sm" onchange={mode_onchange}>
+                    { GameMode::options().into_iter().enumerate().map(|(index, option)| {
+                        html! {
+                            <option value={index.to_string()} selected={option == state.mode}>
+                                { option.to_string() }
+                            </option>
+                        }
+                    }).collect::<Html>() }
+                </select>
+            </div>
+            <div class="flex flex-row items-center justify-center gap-4">
+                <button
+                    class="btn btn-outline btn-sm"
+                    onclick={hint_onclick}
+                    disabled={state.finished || *auto_play}
+                >
+                    { "Hint" }
+                </button>
+                <label class="label cursor-pointer">
+                    <span class="label-text mr-2">{ "Auto-play" }</span>
+                    <input
+                        type="checkbox"
+                        class="toggle toggle-sm"
+                        checked={*auto_play}
+                        onchange={auto_play_onchange}
+                    />
+                </label>
+                if let Some(direction) = *suggestion {
+                    <span class="badge badge-info">
+                        { format!("Try {}", direction_label(direction)) }
+                    </span>
+                }
+            </div>
+            <div class="flex flex-row items-center justify-center gap-4">
+                <label class="label cursor-pointer">
+                    <span class="label-text mr-2">{ "Practice" }</span>
+                    <input
+                        type="checkbox"
+                        class="toggle toggle-sm"
+                        checked={state.practice}
+                        onchange={practice_onchange}
+                    />
+                </label>
+                if state.practice {
+                    <div class="join">
+                        <button
+                            class="btn btn-outline btn-sm join-item"
+                            onclick={undo_onclick}
+                            disabled={state.history.moves.is_empty()}
+                        >
+                            { "Undo" }
+                        </button>
+                        <button
+                            class="btn btn-outline btn-sm join-item"
+                            onclick={redo_onclick}
+                            disabled={state.redo_moves.is_empty()}
+                        >
+                            { "Redo" }
+                        </button>
+                    </div>
+                }
+            </div>
+            if state.practice {
+                <div class="text-center text-sm opacity-70">
+                    { "Practice games are not saved to your history or stats" }
+                </div>
+            } else if state.assisted {
+                <div class="text-center text-sm opacity-70">
+                    { "Hints were used so this game will not show up on leaderboards" }
+                </div>
+            }
+            if let Some(save_error) = save_error.as_ref() {
+                <div class="text-center text-sm text-error" role="alert">{ save_error }</div>
             }
+            if state.finished && !state.practice {
+                <ShareGameButtons
+                    score={state.hiscore}
+                    seed={state.history.seed}
+                    emoji_board={emoji_board(&state.gamestate.board)}
+                    board={state.gamestate.board}
+                    game_hash={state.history.game_hash()}
+                />
+            }
+            // Read out after every move
+            <div class="sr-only" role="status" aria-live="polite" aria-atomic="true">
+                { move_announcement(&state) }
+            </div>
+            // The positioned tiles mean nothing to a screen reader so the board is also a table
+            <table class="sr-only">
+                <caption>{ "Game board" }</caption>
+                <tbody>
+                    { (0..height).map(|y| html! {
+                        <tr key={y}>
+                            { (0..width).map(|x| html! {
+                                <td key={x}>
+                                    { state.gamestate.board.tiles[y][x]
+                                        .map(|tile| tile.value.to_string())
+                                        .unwrap_or_else(|| String::from("Empty")) }
+                                </td>
+                            }).collect::<Html>() }
+                        </tr>
+                    }).collect::<Html>() }
+                </tbody>
+            </table>
             // Game board
             <div
                 ref={board_ref}
                 id="game-board"
-                class="flex-1 mx-auto md:p-4 p-4 w-90 md:w-3/4 lg:w-1/2 xl:w-140 bg-light-board-background shadow-2xl rounded-md md:mt-4 xs:mt-1 mt-2"
+                tabindex="0"
+                role="application"
+                aria-label="Game board. Use your movement keys or a gamepad to move the tiles"
+                class="flex-1 mx-auto md:p-4 p-4 w-90 md:w-3/4 lg:w-1/2 xl:w-140 bg-light-board-background shadow-2xl rounded-md md:mt-4 xs:mt-1 mt-2 focus:outline-none focus-visible:ring-4 focus-visible:ring-primary"
             >
-                <div class="aspect-square p-2 flex flex-col rounded-md w-full  relative ">
+                <div class="aspect-square p-2 flex flex-col rounded-md w-full  relative " aria-hidden="true">
                     <div class="flex flex-col p-2 relative w-full h-full">
                         //Place holder grids
                         { (0..total_tiles).map(|i| {
@@ -834,24 +1556,53 @@ pub fn board(game_props: &GameProps) -> Html {
 #[function_component(GamePage)]
 pub fn game() -> Html {
     let current_game_state = use_state(|| None);
+    let practice = use_state(|| false);
+    let mode = use_state(GameMode::default);
     let current_game_state_clone = current_game_state.clone();
     let cloned_state_for_callback = current_game_state_clone.clone();
 
     let score_board_callback = {
         let cloned_state = cloned_state_for_callback.clone();
-        Callback::from(move |action: ScoreBoardAction| match action {
-            ScoreBoardAction::NewGame => {
+        let practice = practice.clone();
+        let mode = mode.clone();
+        //Clears the saved game and starts a fresh one
+        let start_new_game = {
+            let cloned_state = cloned_state.clone();
+            move |mode: GameMode, practice: bool| {
                 let cloned_state = cloned_state.clone();
                 cloned_state.set(None);
                 spawn_local(async move {
-                    let db = match Database::open(DB_NAME).await {
-                        Ok(db) => db,
-                        Err(err) => {
-                            panic!("Error opening database: {:?}", err);
+                    //Not being able to clear the old game should not stop a new one from starting
+                    match Database::open(DB_NAME).await {
+                        Ok(db) => {
+                            let _ = object_delete(db, game_store(practice), SELF_KEY).await;
                         }
+                        Err(err) => log::error!("Error opening database: {:?}", err),
                     };
-                    let _ = object_delete(db, CURRENT_GAME_STORE, SELF_KEY).await;
-                    cloned_state.set(Some(State::new()));
+                    cloned_state.set(Some(State::new(practice, mode)));
+                })
+            }
+        };
+        Callback::from(move |action: ScoreBoardAction| match action {
+            ScoreBoardAction::NewGame => start_new_game(*mode, *practice),
+            ScoreBoardAction::ChangeMode(new_mode) => {
+                mode.set(new_mode);
+                start_new_game(new_mode, *practice);
+            }
+            ScoreBoardAction::TogglePractice => {
+                let cloned_state = cloned_state.clone();
+                let mode = mode.clone();
+                let new_practice = !*practice;
+                practice.set(new_practice);
+                cloned_state.set(None);
+                spawn_local(async move {
+                    match State::load(new_practice).await {
+                        None => cloned_state.set(Some(State::new(new_practice, *mode))),
+                        Some(game) => {
+                            mode.set(game.mode);
+                            cloned_state.set(Some(game));
+                        }
+                    }
                 })
             }
         })
@@ -859,11 +1610,12 @@ pub fn game() -> Html {
 
     use_effect_once(move || {
         spawn_local(async move {
-            match State::load().await {
+            match State::load(false).await {
                 None => {
-                    current_game_state_clone.set(Some(State::new()));
+                    current_game_state_clone.set(Some(State::new(false, GameMode::default())));
                 }
                 Some(current_game) => {
+                    mode.set(current_game.mode);
                     current_game_state_clone.set(Some(current_game));
                 }
             }
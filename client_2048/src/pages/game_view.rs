@@ -0,0 +1,211 @@
+use crate::Route;
+use crate::appview::{AppViewError, GameView, get_game};
+use crate::bsky_post::ExternalLink;
+use crate::components::post_dialog::PostToBluesky;
+use crate::pages::game::emoji_board;
+use crate::pages::history::MiniGameboard;
+use js_sys::encode_uri_component;
+use twothousand_forty_eight::unified::game::GameState;
+use twothousand_forty_eight::v2::recording::SeededRecording;
+use web_sys::{HtmlInputElement, InputEvent};
+use yew::platform::spawn_local;
+use yew::{
+    Callback, Html, MouseEvent, Properties, TargetCast, function_component, html, use_effect_with,
+    use_state,
+};
+use yew_router::prelude::Link;
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct ReplayViewerProps {
+    pub recording: SeededRecording,
+}
+
+/// Steps through a game one move at a time by replaying the recording up to the chosen move
+#[function_component(ReplayViewer)]
+pub fn replay_viewer(props: &ReplayViewerProps) -> Html {
+    let total_moves = props.recording.moves.len();
+    let step = use_state(|| total_moves);
+
+    {
+        let step = step.clone();
+        use_effect_with(total_moves, move |total_moves| step.set(*total_moves));
+    }
+
+    let move_to = |new_step: usize| {
+        let step = step.clone();
+        Callback::from(move |_: MouseEvent| step.set(new_step.min(total_moves)))
+    };
+
+    let slider_oninput = {
+        let step = step.clone();
+        Callback::from(move |input_event: InputEvent| {
+            let target: HtmlInputElement = input_event.target_unchecked_into();
+            if let Ok(new_step) = target.value().parse::<usize>() {
+                step.set(new_step.min(total_moves));
+            }
+        })
+    };
+
+    let mut partial_recording = props.recording.clone();
+    partial_recording.moves.truncate(*step);
+
+    html! {
+        <div class="flex flex-col gap-2">
+            <MiniGameboard recording={partial_recording} />
+            <input
+                type="range"
+                class="range range-sm"
+                min="0"
+                max={total_moves.to_string()}
+                value={step.to_string()}
+                oninput={slider_oninput}
+            />
+            <div class="join justify-center">
+                <button class="btn btn-sm join-item" onclick={move_to(0)}>{ "<<" }</button>
+                <button class="btn btn-sm join-item" onclick={move_to(step.saturating_sub(1))}>
+                    { "<" }
+                </button>
+                <button class="btn btn-sm join-item" onclick={move_to(*step + 1)}>{ ">" }</button>
+                <button class="btn btn-sm join-item" onclick={move_to(total_moves)}>{ ">>" }</button>
+            </div>
+            <span class="text-center text-sm">
+                { format!("Move {} of {}", *step, total_moves) }
+            </span>
+        </div>
+    }
+}
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct GameViewProps {
+    pub did: String,
+    pub rkey: String,
+}
+
+/// Shows a game someone else played that the appview has indexed
+#[function_component(GameViewPage)]
+pub fn game_view(props: &GameViewProps) -> Html {
+    let game: yew::UseStateHandle<Option<GameView>> = use_state(|| None);
+    let error: yew::UseStateHandle<Option<String>> = use_state(|| None);
+
+    {
+        let game = game.clone();
+        let error = error.clone();
+        use_effect_with(props.clone(), move |props| {
+            let props = props.clone();
+            spawn_local(async move {
+                match get_game(&props.did, &props.rkey).await {
+                    Ok(result) => game.set(Some(result)),
+                    Err(AppViewError::NotFound) => {
+                        error.set(Some(String::from(
+                            "We have not found this game yet. New games can take a few minutes to show up.",
+                        )))
+                    }
+                    Err(err) => {
+                        log::error!("Error loading the game: {:?}", err.to_string());
+                        error.set(Some(err.to_string()));
+                    }
+                }
+            });
+        });
+    }
+
+    let content = match (game.as_ref(), error.as_ref()) {
+        (_, Some(err)) => html! { <span class="text-red-500">{ err }</span> },
+        (None, None) => html! {
+            <div class="flex items-center justify-center">
+                <span class="loading loading-spinner loading-lg" />
+                <h1 class="ml-4 text-3xl font-bold">{ "Loading..." }</h1>
+            </div>
+        },
+        (Some(game), None) => {
+            let display_name = game.handle.clone().unwrap_or(game.did.clone());
+            let final_board = game
+                .seeded_recording
+                .parse::<SeededRecording>()
+                .ok()
+                .and_then(|recording| GameState::from_reconstructable_ruleset(&recording).ok())
+                .map(|gamestate| gamestate.board);
+            let game_url = format!("https://2048.blue/game/{}/{}", game.did, game.rkey);
+            //The link unfurls into a card of the final board on Bluesky
+            let share_text = format!(
+                "{} scored {} on at://2048\n\n{}\n\n{}",
+                display_name,
+                game.score,
+                final_board.as_ref().map(emoji_board).unwrap_or_default(),
+                game_url
+            );
+            let post_text = format!(
+                "{} scored {} on at://2048 with @2048.blue\n\n{}",
+                display_name, game.score, game_url
+            );
+            let post_link = ExternalLink {
+                uri: game_url.clone(),
+                title: format!("{} scored {} on at://2048", display_name, game.score),
+                description: format!(
+                    "Best tile {} in {} moves. Think you can do better?",
+                    game.highest_tile, game.moves
+                ),
+            };
+            html! {
+                <div class="flex flex-row">
+                    <div class="flex flex-col">
+                        if let Ok(recording) = game.seeded_recording.parse::<SeededRecording>() {
+                            <ReplayViewer {recording} />
+                        }
+                    </div>
+                    <div class="pl-2 md:w-3/4 w-1/2 mx-auto">
+                        <a
+                            class="link link-hover text-xl"
+                            href={format!("https://bsky.app/profile/{}", game.did)}
+                            target="_blank"
+                        >
+                            { display_name }
+                        </a>
+                        <p>{ format!("Score: {}", game.score) }</p>
+                        <p>{ format!("Best tile: {}", game.highest_tile) }</p>
+                        <p>{ format!("Moves: {}", game.moves) }</p>
+                        <p>{ format!("Mode: {}", game.game_mode()) }</p>
+                        if game.won {
+                            <div class="badge badge-success">{ "Found 2048" }</div>
+                        }
+                        <p>
+                            <Link<Route>
+                                classes="cursor-pointer underline text-blue-600 visited:text-purple-600"
+                                to={Route::SeedPage { seed: game.seed }}
+                            >
+                                { format!("Play seed: {}", game.seed) }
+                            </Link<Route>>
+                        </p>
+                        <a
+                            class="btn btn-sm btn-accent mt-2"
+                            href={format!(
+                                "https://bsky.app/intent/compose?text={}",
+                                encode_uri_component(&share_text)
+                            )}
+                            target="_blank"
+                        >
+                            { "Share on Bluesky" }
+                        </a>
+                        <PostToBluesky
+                            text={post_text}
+                            link={post_link}
+                            board={final_board}
+                            score={game.score}
+                        />
+                    </div>
+                </div>
+            }
+        }
+    };
+
+    html! {
+        <div class="md:p-4 p-1">
+            <div class="max-w-4xl mx-auto space-y-6 justify-center">
+                <h1 class="text-4xl font-bold text-center md:mb-6 mb-1">{ "Game" }</h1>
+                <div class="bg-base-100 shadow-lg rounded-lg md:p-6 p-1">
+                    <div class="w-full max-w-2xl mx-auto">{ content }</div>
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -1,15 +1,15 @@
 use crate::Route;
 use crate::agent::{StorageRequest, StorageResponse, StorageTask};
 use crate::at_repo_sync::{AtRepoSync, AtRepoSyncError};
-use crate::idb::{DB_NAME, GAME_STORE, RecordStorageWrapper, paginated_cursor};
+use crate::idb::{GAME_STORE, RecordStorageWrapper, open_player_db, paginated_cursor};
 use crate::oauth_client::oauth_client;
 use crate::pages::game::TileProps;
+use crate::session::{is_expired, report_expired};
 use crate::store::UserStore;
 use StorageResponse::RepoError;
 use atrium_api::agent::Agent;
 use atrium_api::types::string::Did;
-use gloo::dialogs::{alert, confirm};
-use indexed_db_futures::database::Database;
+use gloo::dialogs::alert;
 use std::fmt::Display;
 use std::rc::Rc;
 use twothousand_forty_eight::unified::game::GameState;
@@ -22,7 +22,6 @@ use yew::platform::spawn_local;
 use yew::prelude::*;
 use yew_agent::oneshot::use_oneshot_runner;
 use yew_hooks::use_effect_once;
-use yew_router::hooks::use_navigator;
 use yew_router::prelude::Link;
 use yewdux::use_store;
 
@@ -147,13 +146,26 @@ fn mini_tile(props: &TileProps) -> Html {
 }
 
 #[derive(Properties, Clone, PartialEq)]
-struct MiniGameboardProps {
-    recording: SeededRecording,
+pub(crate) struct MiniGameboardProps {
+    pub recording: SeededRecording,
 }
 
 #[function_component(MiniGameboard)]
-fn mini_gameboard(props: &MiniGameboardProps) -> Html {
-    let gamestate = GameState::from_reconstructable_ruleset(&props.recording).unwrap();
+pub(crate) fn mini_gameboard(props: &MiniGameboardProps) -> Html {
+    //Recordings from someone's repo can parse fine and still not replay
+    let gamestate = match GameState::from_reconstructable_ruleset(&props.recording) {
+        Ok(gamestate) => gamestate,
+        Err(err) => {
+            log::error!("Could not replay the game: {:?}", err);
+            return html! {
+                <div
+                    class="w-1/4 flex-1 mx-auto w-full bg-light-board-background shadow-2xl rounded-md p-1 flex items-center justify-center"
+                >
+                    <span class="text-sm text-red-500">{ "This game could not be replayed." }</span>
+                </div>
+            };
+        }
+    };
     let flatten_tiles = gamestate
         .board
         .tiles
@@ -188,32 +200,28 @@ fn game_tile(props: &GameTileProps) -> Html {
     let validation_result: UseStateHandle<Option<ValidationResult>> = use_state(|| None);
     let resync_loading = use_state(|| false);
     let sync_error = use_state(|| None);
-    let navigator = use_navigator().unwrap();
 
     let storage_task = use_oneshot_runner::<StorageTask>();
     let storage_agent = storage_task.clone();
 
+    //The board is only drawn for recordings that validated, a remote one can parse and not replay
     let use_effect_seeded_clone = seeded_recording.clone();
+    let validation_clone = validation_result.clone();
     use_effect_with(props.game.clone(), move |game| {
         match game.record.seeded_recording.parse::<SeededRecording>() {
-            Ok(results) => use_effect_seeded_clone.set(Some(results)),
+            Ok(recording) => match recording.validate() {
+                Ok(result) => {
+                    validation_clone.set(Some(result));
+                    use_effect_seeded_clone.set(Some(recording));
+                }
+                Err(err) => log::error!("{:?}", err),
+            },
             Err(err) => {
                 log::error!("{:?}", err);
             }
         }
     });
 
-    let validation_clone = validation_result.clone();
-    use_effect_with(props.game.clone(), move |game| {
-        match &game.record.seeded_recording.parse::<SeededRecording>() {
-            Ok(seeded_recording) => match seeded_recording.validate() {
-                Ok(result) => validation_clone.set(Some(result)),
-                Err(_) => {}
-            },
-            Err(_) => {}
-        }
-    });
-
     let storage_agent_for_click = storage_agent.clone(); // Clone it before use
     let did = props.did.clone();
     let resync_loading_clone = resync_loading.clone();
@@ -222,9 +230,14 @@ fn game_tile(props: &GameTileProps) -> Html {
     let sync_onclick = Callback::from(move |_: MouseEvent| {
         let did = did.clone();
         let sync_error_clone = sync_error_clone.clone();
+        //Syncing is paused till they login again, the banner has the way to do that
+        if let Some(did) = did.as_ref().filter(|did| is_expired(did)) {
+            report_expired(did);
+            sync_error_clone.set(Some(String::from("Login again to sync this game")));
+            return;
+        }
         let request = StorageRequest::TryToSyncRemotely(record_key.clone(), did.clone());
         let storage_agent_for_click = storage_agent_for_click.clone(); // Clone it before use
-        let navigator = navigator.clone();
         let cloned_reload_action = cloned_reload_action.clone();
 
         resync_loading_clone.set(true);
@@ -241,25 +254,14 @@ fn game_tile(props: &GameTileProps) -> Html {
                     log::error!("Error saving game: {:?}", error.to_string());
                     match error {
                         AtRepoSyncError::AuthErrorNeedToReLogin => {
-                            match confirm(
-                                "Your AT Protocol session has expired. You need to relogin to save your game to your profile. Press confirm to be redirected to login page.",
-                            ) {
-                                true => {
-                                    if let Some(did) = did.as_ref() {
-                                        navigator.push(&Route::LoginPageWithDid {
-                                            did: did.to_string(),
-                                        })
-                                    }
-                                }
-                                false => {
-                                    // dispatch.set(UserStore::default());
-                                }
+                            if let Some(did) = did.as_ref() {
+                                report_expired(did);
                             }
+                            sync_error_clone.set(Some(String::from("Login again to sync this game")));
                         }
-                        AtRepoSyncError::Error(err) => {
-                            sync_error_clone.set(Some(err));
+                        error => {
+                            sync_error_clone.set(Some(error.message()));
                         }
-                        _ => {}
                     }
                 }
                 StorageResponse::Success => {
@@ -404,15 +406,14 @@ impl Default for PaginationOptions {
 
 async fn get_local_games(
     options: PaginationOptions,
+    did: Option<&Did>,
 ) -> Result<Rc<Vec<Rc<RecordStorageWrapper<game::RecordData>>>>, AtRepoSyncError> {
-    let db = Database::open(DB_NAME)
-        .await
-        .map_err(|e| AtRepoSyncError::Error(e.to_string()))?;
+    let db = open_player_db(did).await.map_err(AtRepoSyncError::from)?;
 
     let local_games: Vec<RecordStorageWrapper<game::RecordData>> =
         paginated_cursor(db, GAME_STORE, options.count, options.skip)
             .await
-            .map_err(|e| AtRepoSyncError::Error(e.to_string()))?;
+            .map_err(AtRepoSyncError::from)?;
     Ok(Rc::new(
         local_games
             .iter()
@@ -435,21 +436,18 @@ async fn get_remote_games(
     let session = match oauth_client.restore(&did).await {
         Ok(session) => session,
         Err(err) => {
-            return Err(AtRepoSyncError::Error(err.to_string()));
+            log::error!("{:?}", err);
+            return Err(AtRepoSyncError::AuthErrorNeedToReLogin);
         }
     };
     let agent = Agent::new(session);
     let at_repo_sync = AtRepoSync::new_logged_in_repo(agent, did);
-    match at_repo_sync
+    at_repo_sync
         .get_remote_games(
             pagination_options.at_proto_cursor,
             Some(pagination_options.count as u8),
         )
         .await
-    {
-        Ok(results) => Ok(results),
-        Err(err) => Err(AtRepoSyncError::Error(err.to_string())),
-    }
 }
 
 async fn get_games(
@@ -465,7 +463,7 @@ async fn get_games(
 > {
     match tab_state {
         TabState::Local => {
-            let result = get_local_games(options).await?;
+            let result = get_local_games(options, did.as_ref()).await?;
             Ok((result, None))
         }
         TabState::Remote => {
@@ -494,10 +492,11 @@ pub fn history() -> Html {
     let display_games_effect = display_games.clone();
     let pagination_clone = pagination.clone();
     let use_effect_pagination = pagination.clone();
+    let mount_did = user_store.did.clone();
     use_effect_once(move || {
         spawn_local(async move {
             //Can default pagination since this is on load
-            match get_local_games(PaginationOptions::default()).await {
+            match get_local_games(PaginationOptions::default(), mount_did.as_ref()).await {
                 Ok(games) => {
                     if games.len() < use_effect_pagination.count as usize {
                         use_effect_pagination.set(PaginationOptions {
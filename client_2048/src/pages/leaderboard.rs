@@ -0,0 +1,368 @@
+use crate::Route;
+use crate::appview::{Board, LeaderboardEntry, LeaderboardResponse, get_leaderboard};
+use crate::store::UserStore;
+use numfmt::{Formatter, Precision};
+use std::rc::Rc;
+use types_2048::game_mode::GameMode;
+use web_sys::{HtmlInputElement, HtmlSelectElement, InputEvent, SubmitEvent};
+use yew::platform::spawn_local;
+use yew::prelude::*;
+use yew_router::prelude::Link;
+use yewdux::use_store;
+
+#[derive(Clone, PartialEq, Default, Debug)]
+enum LeaderboardTab {
+    #[default]
+    Global,
+    Daily,
+    Seed,
+    Friends,
+}
+
+impl LeaderboardTab {
+    fn label(&self) -> &'static str {
+        match self {
+            LeaderboardTab::Global => "Global",
+            LeaderboardTab::Daily => "Daily",
+            LeaderboardTab::Seed => "Seed",
+            LeaderboardTab::Friends => "Friends",
+        }
+    }
+}
+
+#[derive(Properties, Clone, PartialEq)]
+struct LeaderboardRowProps {
+    entry: LeaderboardEntry,
+    is_viewer: bool,
+}
+
+#[function_component(LeaderboardRow)]
+fn leaderboard_row(props: &LeaderboardRowProps) -> Html {
+    let mut number_formatter = Formatter::new()
+        .precision(Precision::Decimals(0))
+        .separator(',')
+        .expect("Could not build the number formatter.");
+    let score_formatted = number_formatter.fmt2(props.entry.score).to_string();
+    let entry = &props.entry;
+    let display_name = entry.handle.clone().unwrap_or(entry.did.clone());
+    html! {
+        <tr class={classes!(props.is_viewer.then_some("bg-accent text-accent-content"))}>
+            <th>{ entry.rank }</th>
+            <td>
+                <a
+                    class="link link-hover"
+                    href={format!("https://bsky.app/profile/{}", entry.did)}
+                    target="_blank"
+                >
+                    { display_name }
+                </a>
+            </td>
+            <td>
+                <Link<Route>
+                    classes="link link-hover"
+                    to={Route::GameViewPage { did: entry.did.clone(), rkey: entry.rkey.clone() }}
+                >
+                    { score_formatted }
+                </Link<Route>>
+            </td>
+            <td class="hidden md:table-cell">{ entry.highest_tile }</td>
+            <td class="hidden md:table-cell">
+                <Link<Route> classes="link link-hover" to={Route::SeedPage { seed: entry.seed }}>
+                    { entry.seed }
+                </Link<Route>>
+            </td>
+        </tr>
+    }
+}
+
+#[function_component(LeaderboardPage)]
+pub fn leaderboard() -> Html {
+    let (user_store, _) = use_store::<UserStore>();
+    let current_tab = use_state(LeaderboardTab::default);
+    let seed_input: UseStateHandle<Option<u32>> = use_state(|| None);
+    //None while the Seed tab is waiting for a seed to be entered
+    let board: UseStateHandle<Option<Board>> = use_state(|| Some(Board::default()));
+    let mode = use_state(GameMode::default);
+    let entries: UseStateHandle<Option<Rc<Vec<LeaderboardEntry>>>> = use_state(|| None);
+    let cursor: UseStateHandle<Option<String>> = use_state(|| None);
+    let viewer: UseStateHandle<Option<LeaderboardEntry>> = use_state(|| None);
+    let error: UseStateHandle<Option<String>> = use_state(|| None);
+    let loading_more = use_state(|| false);
+    let viewer_did = user_store.did.as_ref().map(|did| did.to_string());
+
+    //Loads the first page any time the board or mode changes. Also when the player's did loads so
+    //their rank is shown
+    {
+        let entries = entries.clone();
+        let cursor = cursor.clone();
+        let viewer = viewer.clone();
+        let error = error.clone();
+        let loading_more = loading_more.clone();
+        use_effect_with(
+            ((*board).clone(), *mode, viewer_did.clone()),
+            move |(board, mode, viewer_did)| {
+                let board = board.clone();
+                let mode = *mode;
+                let viewer_did = viewer_did.clone();
+                cursor.set(None);
+                viewer.set(None);
+                loading_more.set(false);
+                error.set(None);
+                let Some(board) = board else {
+                    entries.set(Some(Rc::new(vec![])));
+                    return;
+                };
+                entries.set(None);
+                spawn_local(async move {
+                    match get_leaderboard(&board, mode, None, viewer_did).await {
+                        Ok(LeaderboardResponse {
+                            entries: page,
+                            cursor: next_cursor,
+                            viewer: viewer_entry,
+                        }) => {
+                            cursor.set(next_cursor);
+                            viewer.set(viewer_entry);
+                            entries.set(Some(Rc::new(page)));
+                        }
+                        Err(err) => {
+                            log::error!("Error loading the leaderboard: {:?}", err.to_string());
+                            error.set(Some(err.to_string()));
+                            entries.set(Some(Rc::new(vec![])));
+                        }
+                    }
+                });
+            },
+        );
+    }
+
+    let tab_onclick = {
+        let current_tab = current_tab.clone();
+        let board = board.clone();
+        let seed_input = seed_input.clone();
+        let viewer_did = viewer_did.clone();
+        move |tab: LeaderboardTab| {
+            let current_tab = current_tab.clone();
+            let board = board.clone();
+            let seed_input = seed_input.clone();
+            let viewer_did = viewer_did.clone();
+            Callback::from(move |_: MouseEvent| {
+                let new_board = match tab {
+                    LeaderboardTab::Global => Board::Global,
+                    LeaderboardTab::Daily => Board::Daily,
+                    LeaderboardTab::Seed => match *seed_input {
+                        Some(seed) => Board::Seed(seed),
+                        None => {
+                            current_tab.set(tab.clone());
+                            board.set(None);
+                            return;
+                        }
+                    },
+                    LeaderboardTab::Friends => match viewer_did.clone() {
+                        Some(did) => Board::Friends(did),
+                        None => return,
+                    },
+                };
+                current_tab.set(tab.clone());
+                board.set(Some(new_board));
+            })
+        }
+    };
+
+    let seed_oninput = {
+        let seed_input = seed_input.clone();
+        Callback::from(move |input_event: InputEvent| {
+            let target: HtmlInputElement = input_event.target_unchecked_into();
+            seed_input.set(target.value().parse::<u32>().ok());
+        })
+    };
+
+    let seed_onsubmit = {
+        let seed_input = seed_input.clone();
+        let board = board.clone();
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+            if let Some(seed) = *seed_input {
+                board.set(Some(Board::Seed(seed)));
+            }
+        })
+    };
+
+    let mode_onchange = {
+        let mode = mode.clone();
+        Callback::from(move |event: Event| {
+            let target: HtmlSelectElement = event.target_unchecked_into();
+            if let Some(new_mode) = target
+                .value()
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| GameMode::options().get(index).copied())
+            {
+                mode.set(new_mode);
+            }
+        })
+    };
+
+    let load_more_callback = {
+        let entries = entries.clone();
+        let cursor = cursor.clone();
+        let board = board.clone();
+        let mode = mode.clone();
+        let viewer_did = viewer_did.clone();
+        let loading_more = loading_more.clone();
+        Callback::from(move |_: MouseEvent| {
+            //A second click while a page is loading would add the same page twice
+            if *loading_more {
+                return;
+            }
+            let Some(board) = (*board).clone() else {
+                return;
+            };
+            let entries = entries.clone();
+            let cursor = cursor.clone();
+            let mode = *mode;
+            let viewer_did = viewer_did.clone();
+            let current_cursor = (*cursor).clone();
+            let loading_more = loading_more.clone();
+            loading_more.set(true);
+            spawn_local(async move {
+                match get_leaderboard(&board, mode, current_cursor, viewer_did).await {
+                    Ok(response) => {
+                        let mut combined = match &*entries {
+                            Some(entries) => entries.as_ref().to_vec(),
+                            None => vec![],
+                        };
+                        combined.extend(response.entries);
+                        entries.set(Some(Rc::new(combined)));
+                        cursor.set(response.cursor);
+                    }
+                    Err(err) => {
+                        log::error!("Error loading more of the leaderboard: {:?}", err.to_string());
+                    }
+                }
+                loading_more.set(false);
+            });
+        })
+    };
+
+    let tabs = vec![
+        LeaderboardTab::Global,
+        LeaderboardTab::Daily,
+        LeaderboardTab::Seed,
+        LeaderboardTab::Friends,
+    ];
+
+    html! {
+        <div class="md:p-4 p-1">
+            <div class="max-w-4xl mx-auto space-y-6 justify-center">
+                <h1 class="text-4xl font-bold text-center md:mb-6 mb-1">{ "Leaderboard" }</h1>
+                <div class="bg-base-100 shadow-lg rounded-lg md:p-6 p-1">
+                    <div class="w-full max-w-2xl mx-auto">
+                        <div role="tablist" class="tabs tabs-lift tabs-lg">
+                            { tabs.into_iter().filter(|tab| *tab != LeaderboardTab::Friends || user_store.did.is_some()).map(|tab| {
+                                let active = *current_tab == tab;
+                                html! {
+                                    <a
+                                        onclick={tab_onclick(tab.clone())}
+                                        role="tab"
+                                        class={classes!("tab", active.then_some("tab-active"))}
+                                    >
+                                        { tab.label() }
+                                    </a>
+                                }
+                            }).collect::<Html>() }
+                        </div>
+                        <select class="select select-bordered w-full mt-2" onchange={mode_onchange}>
+                            { GameMode::options().into_iter().enumerate().map(|(index, option)| {
+                                html! {
+                                    <option value={index.to_string()} selected={option == *mode}>
+                                        { option.to_string() }
+                                    </option>
+                                }
+                            }).collect::<Html>() }
+                        </select>
+                        if *current_tab == LeaderboardTab::Seed {
+                            <form class="flex flex-row gap-2 py-2" onsubmit={seed_onsubmit}>
+                                <input
+                                    type="number"
+                                    class="input input-bordered w-full"
+                                    placeholder="Seed"
+                                    value={seed_input.map(|seed| seed.to_string()).unwrap_or_default()}
+                                    oninput={seed_oninput}
+                                />
+                                <button type="submit" class="btn btn-primary">{ "Go" }</button>
+                            </form>
+                        }
+                        if user_store.did.is_some() && board.is_some() {
+                            <div class="py-2 text-center">
+                                { match viewer.as_ref() {
+                                    Some(viewer) => format!("You are ranked #{} with a score of {}", viewer.rank, viewer.score),
+                                    None => String::from("You are not on this leaderboard yet"),
+                                } }
+                            </div>
+                        }
+                        if let Some(err) = error.as_ref() {
+                            <span class="text-red-500">{ err }</span>
+                        }
+                        { match entries.as_ref() {
+                            _ if board.is_none() => html! {
+                                <div class="flex items-center justify-center">
+                                    <h1 class="pt-2 ml-4 text-3xl font-bold">
+                                        { "Enter a seed to see its leaderboard." }
+                                    </h1>
+                                </div>
+                            },
+                            None => html! {
+                                <div class="flex items-center justify-center">
+                                    <span class="loading loading-spinner loading-lg" />
+                                    <h1 class="ml-4 text-3xl font-bold">{ "Loading..." }</h1>
+                                </div>
+                            },
+                            Some(entries) if entries.is_empty() => html! {
+                                <div class="flex items-center justify-center">
+                                    <h1 class="pt-2 ml-4 text-3xl font-bold">
+                                        { "No games on this leaderboard yet." }
+                                    </h1>
+                                </div>
+                            },
+                            Some(entries) => html! {
+                                <div class="overflow-x-auto">
+                                    <table class="table">
+                                        <thead>
+                                            <tr>
+                                                <th>{ "Rank" }</th>
+                                                <th>{ "Player" }</th>
+                                                <th>{ "Score" }</th>
+                                                <th class="hidden md:table-cell">{ "Best tile" }</th>
+                                                <th class="hidden md:table-cell">{ "Seed" }</th>
+                                            </tr>
+                                        </thead>
+                                        <tbody>
+                                            { entries.iter().map(|entry| {
+                                                let is_viewer = viewer_did.as_ref() == Some(&entry.did);
+                                                html! { <LeaderboardRow key={entry.uri.clone()} entry={entry.clone()} {is_viewer} /> }
+                                            }).collect::<Html>() }
+                                        </tbody>
+                                    </table>
+                                </div>
+                            },
+                        } }
+                        if cursor.is_some() {
+                            <div class="flex w-full justify-center pt-2">
+                                <button
+                                    onclick={load_more_callback}
+                                    class="btn btn-outline btn-wide"
+                                    disabled={*loading_more}
+                                >
+                                    if *loading_more {
+                                        <span class="loading loading-spinner" />
+                                    }
+                                    { "Load more" }
+                                </button>
+                            </div>
+                        }
+                    </div>
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -1,7 +1,8 @@
 use crate::oauth_client::{handle_resolve_from_did, oauth_client};
 use crate::store::UserStore;
 use atrium_api::types::string::Did;
-use atrium_oauth::{AuthorizeOptions, KnownScope, Scope};
+use atrium_oauth::AuthorizeOptions;
+use oauth_2048::{game_scopes, posting_scopes};
 use std::str::FromStr;
 use web_sys::{HtmlInputElement, InputEvent, SubmitEvent};
 use yew::platform::spawn_local;
@@ -11,18 +12,21 @@ use yew::{
 use yew_hooks::use_effect_once;
 use yewdux::use_store;
 
-pub async fn redirect_to_auth(handle: String) -> Result<(), String> {
+/// Sends the player off to their PDS to login. Posting to Bluesky is only asked for when they
+/// want to post, otherwise the login only covers the game's own records
+pub async fn redirect_to_auth(handle: String, with_posting: bool) -> Result<(), String> {
     let client = oauth_client();
     let oauth_client = client.clone();
+    let mut scopes = game_scopes();
+    if with_posting {
+        scopes.extend(posting_scopes());
+    }
 
     let url = oauth_client
         .authorize(
             handle.to_string().to_lowercase(),
             AuthorizeOptions {
-                scopes: vec![
-                    Scope::Known(KnownScope::Atproto),
-                    Scope::Known(KnownScope::TransitionGeneric),
-                ],
+                scopes,
                 ..Default::default()
             },
         )
@@ -75,10 +79,7 @@ pub fn login(props: &LoginProps) -> Html {
             spawn_local(async move {
                 let error = match Did::from_str(&users_did) {
                     Ok(did) => match handle_resolve_from_did(did).await {
-                        Some(handle) => match redirect_to_auth(handle).await {
-                            Ok(_) => None,
-                            Err(err) => Some(err),
-                        },
+                        Some(handle) => redirect_to_auth(handle, false).await.err(),
                         None => None,
                     },
                     Err(err) => Some(err.to_string()),
@@ -105,7 +106,7 @@ pub fn login(props: &LoginProps) -> Html {
             event.prevent_default();
             let handle = handle.clone();
             spawn_local(async move {
-                match redirect_to_auth((*handle).clone()).await {
+                match redirect_to_auth((*handle).clone(), false).await {
                     Ok(_) => return,
                     Err(err) => {
                         error_callback_clone.set(Some(err));
@@ -132,7 +133,7 @@ pub fn login(props: &LoginProps) -> Html {
                 <ul class="list-disc list-inside space-y-2 mb-4">
                     <li>{ "Save your progress across multiple devices" }</li>
                     <li>{ "Track your statistics across multiple devices" }</li>
-                    <li>{ "Compete on global leaderboards (future)" }</li>
+                    <li>{ "Compete on global leaderboards" }</li>
                     <li>{ "See friends scores (future)" }</li>
                     <li>{ "The data is 100% yours stored in your PDS" }</li>
                 </ul>
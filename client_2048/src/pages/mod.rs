@@ -1,6 +1,10 @@
 pub mod callback;
+pub mod friends;
 pub mod game;
+pub mod game_view;
 pub mod history;
+pub mod leaderboard;
 pub mod login;
 pub mod seed;
+pub mod settings;
 pub mod stats;
@@ -1,16 +1,96 @@
 use crate::Route;
+use crate::appview::{Board, LeaderboardEntry, get_leaderboard};
 use crate::idb::{CURRENT_GAME_STORE, DB_NAME, SELF_KEY, transaction_put};
 use atrium_api::types::string::Datetime;
+use gloo::timers::callback::Timeout;
 use indexed_db_futures::database::Database;
 use twothousand_forty_eight::v2::recording::SeededRecording;
 use types_2048::blue;
 use types_2048::blue::_2048::defs::SyncStatusData;
-use web_sys::{HtmlInputElement, InputEvent, SubmitEvent};
+use types_2048::game_mode::GameMode;
+use web_sys::{Event, HtmlInputElement, HtmlSelectElement, InputEvent, SubmitEvent};
 use yew::platform::spawn_local;
 use yew::{
-    Callback, Html, Properties, TargetCast, classes, function_component, html, use_state_eq,
+    Callback, Html, Properties, TargetCast, UseStateHandle, classes, function_component, html,
+    use_effect_with, use_state, use_state_eq,
 };
 use yew_router::hooks::use_navigator;
+use yew_router::prelude::Link;
+
+/// Waits for the player to stop typing before asking the appview for the seed's scores
+const SEED_SEARCH_DEBOUNCE_MS: u32 = 500;
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct SeedTopScoresProps {
+    pub seed: u32,
+    pub mode: GameMode,
+}
+
+/// The best scores anyone has gotten on a seed in a mode with links to replay their games
+#[function_component(SeedTopScores)]
+pub fn seed_top_scores(props: &SeedTopScoresProps) -> Html {
+    let entries: UseStateHandle<Option<Vec<LeaderboardEntry>>> = use_state(|| None);
+
+    let entries_effect = entries.clone();
+    use_effect_with((props.seed, props.mode), move |(seed, mode)| {
+        let seed = *seed;
+        let mode = *mode;
+        entries_effect.set(None);
+        let timeout = Timeout::new(SEED_SEARCH_DEBOUNCE_MS, move || {
+            spawn_local(async move {
+                match get_leaderboard(&Board::Seed(seed), mode, None, None).await {
+                    Ok(response) => entries_effect.set(Some(response.entries)),
+                    Err(err) => {
+                        log::error!("Error loading the seed's scores: {:?}", err.to_string());
+                        entries_effect.set(Some(vec![]));
+                    }
+                }
+            });
+        });
+        move || drop(timeout)
+    });
+
+    html! {
+        <div class="backdrop-blur-md bg-base-200/50 p-6 rounded-lg shadow-lg mb-8 max-w-md w-full">
+            <h2 class="text-2xl font-bold mb-2">
+                { format!("Best {} scores for seed {}", props.mode, props.seed) }
+            </h2>
+            { match entries.as_ref() {
+                None => html! { <span class="loading loading-spinner loading-md" /> },
+                Some(entries) if entries.is_empty() => html! {
+                    <p>{ "No one has played this seed yet. Be the first!" }</p>
+                },
+                Some(entries) => html! {
+                    <ul class="list">
+                        { entries.iter().map(|entry| {
+                            html! {
+                                <li key={entry.uri.clone()} class="list-row">
+                                    <div class="font-bold">{ format!("#{}", entry.rank) }</div>
+                                    <div>
+                                        <a
+                                            class="link link-hover"
+                                            href={format!("https://bsky.app/profile/{}", entry.did)}
+                                            target="_blank"
+                                        >
+                                            { entry.handle.clone().unwrap_or(entry.did.clone()) }
+                                        </a>
+                                        <div class="text-xs opacity-60">{ format!("Score: {}", entry.score) }</div>
+                                    </div>
+                                    <Link<Route>
+                                        classes="btn btn-sm btn-outline"
+                                        to={Route::GameViewPage { did: entry.did.clone(), rkey: entry.rkey.clone() }}
+                                    >
+                                        { "Replay" }
+                                    </Link<Route>>
+                                </li>
+                            }
+                        }).collect::<Html>() }
+                    </ul>
+                },
+            } }
+        </div>
+    }
+}
 
 #[derive(Properties, Clone, PartialEq)]
 pub struct SeedProps {
@@ -20,6 +100,7 @@ pub struct SeedProps {
 #[function_component(SeedPage)]
 pub fn seed(props: &SeedProps) -> Html {
     let seed_input = use_state_eq(|| props.starting_seed.unwrap_or(0));
+    let mode = use_state_eq(GameMode::default);
     let error = use_state_eq(|| None);
     let navigator = use_navigator().unwrap();
     let on_input_handle = seed_input.clone();
@@ -36,9 +117,24 @@ pub fn seed(props: &SeedProps) -> Html {
             }
         }
     });
+    let mode_onchange = {
+        let mode = mode.clone();
+        Callback::from(move |event: Event| {
+            let target: HtmlSelectElement = event.target_unchecked_into();
+            if let Some(new_mode) = target
+                .value()
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| GameMode::options().get(index).copied())
+            {
+                mode.set(new_mode);
+            }
+        })
+    };
     let error_view_clone = error.clone();
     let onsubmit = {
         let seed_input = seed_input.clone();
+        let mode = mode.clone();
         let error_input = error.clone();
         let navigator = navigator.clone();
         Callback::from(move |event: SubmitEvent| {
@@ -46,6 +142,7 @@ pub fn seed(props: &SeedProps) -> Html {
             error_callback_clone.set(None);
             event.prevent_default();
             let seed_value = *seed_input;
+            let mode = *mode;
             let error_spawn = error_input.clone();
             let nav = navigator.clone();
             spawn_local(async move {
@@ -55,13 +152,20 @@ pub fn seed(props: &SeedProps) -> Html {
                 let db = match Database::open(DB_NAME).await {
                     Ok(db) => db,
                     Err(err) => {
-                        panic!("Error opening database: {:?}", err);
+                        log::error!("Error opening database: {:?}", err);
+                        error_spawn.set(Some("Error creating a new game from that seed"));
+                        return;
                     }
                 };
                 let current_game = blue::_2048::game::RecordData {
+                    assisted: None,
                     completed: false,
                     created_at: Datetime::now(),
                     current_score: 0,
+                    duration_ms: None,
+                    limit: mode.record_limit(),
+                    mode: mode.record_mode(),
+                    move_count: None,
                     seeded_recording: history_string,
                     sync_status: SyncStatusData {
                         created_at: Datetime::now(),
@@ -70,6 +174,7 @@ pub fn seed(props: &SeedProps) -> Html {
                         updated_at: Datetime::now(),
                     }
                     .into(),
+                    time_to_find_twenty_forty_eight_ms: None,
                     won: false,
                 };
                 let result = transaction_put(
@@ -129,8 +234,18 @@ pub fn seed(props: &SeedProps) -> Html {
                             { "New Game" }
                         </button>
                     </div>
+                    <select class="select select-bordered w-full mt-2" onchange={mode_onchange}>
+                        { GameMode::options().into_iter().enumerate().map(|(index, option)| {
+                            html! {
+                                <option value={index.to_string()} selected={option == *mode}>
+                                    { option.to_string() }
+                                </option>
+                            }
+                        }).collect::<Html>() }
+                    </select>
                 </form>
             </div>
+            <SeedTopScores seed={*seed_input} mode={*mode} />
             <div class="container mx-auto p-4" />
         </div>
     }
@@ -0,0 +1,153 @@
+use crate::controls::{Control, KeyBindings, key_label};
+use gloo::events::EventListener;
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+
+/// Remaps the keys used to play. Bindings are saved to this browser
+#[function_component(SettingsPage)]
+pub fn settings_page() -> Html {
+    let key_bindings = use_state(KeyBindings::load);
+    //The control waiting on the next key press to bind
+    let listening_for = use_state(|| None::<Control>);
+
+    //Captures the next key press while listening. Escape cancels
+    {
+        let key_bindings = key_bindings.clone();
+        let listening_for = listening_for.clone();
+        use_effect_with(*listening_for, move |listening| {
+            let listener = listening.map(|control| {
+                EventListener::new(&gloo::utils::document(), "keydown", move |event| {
+                    let Some(event) = event.dyn_ref::<web_sys::KeyboardEvent>() else {
+                        return;
+                    };
+                    event.prevent_default();
+                    let key = event.key();
+                    if key != "Escape" {
+                        let mut bindings = (*key_bindings).clone();
+                        bindings.bind(control, key);
+                        bindings.save();
+                        key_bindings.set(bindings);
+                    }
+                    listening_for.set(None);
+                })
+            });
+            move || drop(listener)
+        });
+    }
+
+    let reset_onclick = {
+        let key_bindings = key_bindings.clone();
+        let listening_for = listening_for.clone();
+        Callback::from(move |_: MouseEvent| {
+            let bindings = KeyBindings::default();
+            bindings.save();
+            key_bindings.set(bindings);
+            listening_for.set(None);
+        })
+    };
+
+    let rows = Control::all()
+        .into_iter()
+        .map(|control| {
+            let listening = *listening_for == Some(control);
+            let add_onclick = {
+                let listening_for = listening_for.clone();
+                Callback::from(move |_: MouseEvent| listening_for.set(Some(control)))
+            };
+            let keys = key_bindings
+                .keys(control)
+                .iter()
+                .map(|key| {
+                    let remove_onclick = {
+                        let key_bindings = key_bindings.clone();
+                        let key = key.clone();
+                        Callback::from(move |_: MouseEvent| {
+                            let mut bindings = (*key_bindings).clone();
+                            bindings.unbind(control, &key);
+                            bindings.save();
+                            key_bindings.set(bindings);
+                        })
+                    };
+                    html! {
+                        <button
+                            key={key.clone()}
+                            class="badge badge-outline gap-1 cursor-pointer"
+                            onclick={remove_onclick}
+                            aria-label={format!("Remove {} from {}", key_label(key), control.label())}
+                        >
+                            { key_label(key) }
+                            <span aria-hidden="true">{ "✕" }</span>
+                        </button>
+                    }
+                })
+                .collect::<Html>();
+            html! {
+                <tr key={control.label()}>
+                    <td>{ control.label() }</td>
+                    <td>
+                        <div class="flex flex-wrap gap-2">{ keys }</div>
+                    </td>
+                    <td class="text-right">
+                        <button
+                            class="btn btn-sm btn-outline"
+                            onclick={add_onclick}
+                            disabled={listening_for.is_some() && !listening}
+                        >
+                            if listening {
+                                { "Press a key..." }
+                            } else {
+                                { "Add key" }
+                            }
+                        </button>
+                    </td>
+                </tr>
+            }
+        })
+        .collect::<Html>();
+
+    html! {
+        <div class="md:p-4 p-1">
+            <div class="max-w-4xl mx-auto space-y-4">
+                <h1 class="text-4xl font-bold text-center md:mb-6 mb-1">{ "Settings" }</h1>
+                <div class="card bg-base-100 shadow-xl">
+                    <div class="card-body">
+                        <h3 class="card-title">{ "Controls" }</h3>
+                        <p class="text-sm opacity-70">
+                            { "Click a key to remove it, or add a new one. A key can only do one thing, so binding it somewhere new takes it off the old control. Press Escape to cancel." }
+                        </p>
+                        <div class="overflow-x-auto">
+                            <table class="table">
+                                <thead>
+                                    <tr>
+                                        <th>{ "Control" }</th>
+                                        <th>{ "Keys" }</th>
+                                        <th />
+                                    </tr>
+                                </thead>
+                                <tbody>{ rows }</tbody>
+                            </table>
+                        </div>
+                        <p class="text-sm opacity-70" aria-live="polite">
+                            if listening_for.is_some() {
+                                { "Waiting for a key press" }
+                            }
+                        </p>
+                        <div class="card-actions justify-end">
+                            <button class="btn btn-sm" onclick={reset_onclick}>
+                                { "Reset to defaults" }
+                            </button>
+                        </div>
+                    </div>
+                </div>
+                <div class="card bg-base-100 shadow-xl">
+                    <div class="card-body">
+                        <h3 class="card-title">{ "Gamepad" }</h3>
+                        <p>
+                            { "Connect a gamepad and press any button on it to wake it up. Move with the d-pad or the left stick." }
+                        </p>
+                    </div>
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -1,10 +1,27 @@
-use crate::at_repo_sync::AtRepoSync;
+use crate::Route;
+use crate::achievements::Achievement;
+use crate::at_repo_sync::{AtRepoSync, AtRepoSyncError};
+use crate::components::charts::{BarChart, Heatmap, LineChart, short_number};
+use crate::idb::{GAME_STORE, RecordStorageWrapper, StorageError, object_get_all, open_player_db};
+use crate::session::{is_expired, report_expired};
 use crate::store::UserStore;
+use crate::streaks::{games_per_day, local_day};
 use atrium_api::agent::Agent;
+use atrium_api::types::string::Did;
 use js_sys::encode_uri_component;
 use numfmt::{Formatter, Precision};
+use std::collections::BTreeMap;
+use twothousand_forty_eight::unified::game::GameState;
+use twothousand_forty_eight::v2::recording::SeededRecording;
+use types_2048::blue::_2048::game;
+use types_2048::blue::_2048::player::achievement;
+use types_2048::game_mode::GameMode;
+use types_2048::streaks::PlayStreaks;
 use yew::platform::spawn_local;
-use yew::{Html, Properties, function_component, html, use_effect_with, use_state};
+use yew::{
+    Html, Properties, UseStateHandle, function_component, html, use_effect_with, use_state,
+};
+use yew_router::prelude::Link;
 use yewdux::prelude::*;
 
 #[derive(Properties, PartialEq)]
@@ -41,6 +58,363 @@ pub fn bsky_button(props: &BSkyButtonProps) -> Html {
     )
 }
 
+/// How many bars the score histogram is split into
+const SCORE_BUCKETS: i64 = 8;
+/// Score over time only shows the most recent games so the line stays readable
+const SCORE_HISTORY_GAMES: usize = 50;
+
+/// Numbers and chart data worked out from the classic games saved on this device
+#[derive(Clone, PartialEq)]
+struct GameBreakdown {
+    games: usize,
+    median_score: i64,
+    win_rate: f64,
+    score_histogram: Vec<(String, f64)>,
+    highest_tiles: Vec<(String, f64)>,
+    score_history: Vec<f64>,
+}
+
+async fn load_game_breakdown(did: Option<Did>) -> Result<Option<GameBreakdown>, StorageError> {
+    let db = open_player_db(did.as_ref()).await?;
+    let mut games =
+        object_get_all::<RecordStorageWrapper<game::RecordData>>(db, GAME_STORE).await?;
+    //Timed and move limited scores are not comparable with classic ones
+    games.retain(|game| {
+        GameMode::from_record(game.record.mode.as_deref(), game.record.limit)
+            == Some(GameMode::Classic)
+    });
+    if games.is_empty() {
+        return Ok(None);
+    }
+    games.sort_by(|a, b| a.record.created_at.as_ref().cmp(b.record.created_at.as_ref()));
+
+    let mut scores = games
+        .iter()
+        .map(|game| game.record.current_score)
+        .collect::<Vec<_>>();
+    let score_history = scores
+        .iter()
+        .skip(scores.len().saturating_sub(SCORE_HISTORY_GAMES))
+        .map(|score| *score as f64)
+        .collect::<Vec<_>>();
+    scores.sort_unstable();
+    let middle = scores.len() / 2;
+    let median_score = match scores.len() % 2 {
+        0 => (scores[middle - 1] + scores[middle]) / 2,
+        _ => scores[middle],
+    };
+
+    //Rounds the bucket size up to a nice number so the labels are readable
+    let max_score = scores.last().copied().unwrap_or(0).max(1);
+    let bucket_size = ((max_score / SCORE_BUCKETS) / 500 + 1) * 500;
+    let mut buckets = vec![0; (max_score / bucket_size + 1) as usize];
+    for score in &scores {
+        buckets[(score / bucket_size) as usize] += 1;
+    }
+    let score_histogram = buckets
+        .into_iter()
+        .enumerate()
+        .map(|(index, count)| {
+            let start = index as f64 * bucket_size as f64;
+            (format!("{}+", short_number(start)), count as f64)
+        })
+        .collect();
+
+    let mut tile_counts: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut wins = 0;
+    for game in &games {
+        if game.record.won {
+            wins += 1;
+        }
+        let highest_tile = game
+            .record
+            .seeded_recording
+            .parse::<SeededRecording>()
+            .ok()
+            .and_then(|recording| GameState::from_reconstructable_ruleset(&recording).ok())
+            .and_then(|gamestate| {
+                gamestate
+                    .board
+                    .tiles
+                    .iter()
+                    .flatten()
+                    .filter_map(|tile| *tile)
+                    .map(|tile| tile.value)
+                    .max()
+            });
+        if let Some(highest_tile) = highest_tile {
+            *tile_counts.entry(highest_tile).or_default() += 1;
+        }
+    }
+    let highest_tiles = tile_counts
+        .into_iter()
+        .map(|(tile, count)| (tile.to_string(), count as f64))
+        .collect();
+
+    Ok(Some(GameBreakdown {
+        games: games.len(),
+        median_score,
+        win_rate: wins as f64 / games.len() as f64 * 100.0,
+        score_histogram,
+        highest_tiles,
+        score_history,
+    }))
+}
+
+/// Median score, win rate and charts from the games saved on this device. Works offline
+#[function_component(GameCharts)]
+fn game_charts() -> Html {
+    let (user_store, _) = use_store::<UserStore>();
+    let breakdown: UseStateHandle<Option<Option<GameBreakdown>>> = use_state(|| None);
+    {
+        let breakdown = breakdown.clone();
+        use_effect_with(user_store.did.clone(), move |did| {
+            let did = did.clone();
+            spawn_local(async move {
+                match load_game_breakdown(did).await {
+                    Ok(result) => breakdown.set(Some(result)),
+                    Err(err) => {
+                        log::error!("Error loading your games: {:?}", err.to_string());
+                        breakdown.set(Some(None));
+                    }
+                }
+            });
+        });
+    }
+
+    let mut number_formatter = Formatter::new()
+        .precision(Precision::Decimals(0))
+        .separator(',')
+        .expect("Could not build the number formatter.");
+
+    match breakdown.as_ref() {
+        None => html! {
+            <div class="flex items-center justify-center">
+                <span class="loading loading-spinner loading-lg" />
+            </div>
+        },
+        Some(None) => html! {
+            <p class="text-base-content/70">
+                { "Finish a classic game on this device to see charts of your scores." }
+            </p>
+        },
+        Some(Some(breakdown)) => html! {
+            <div class="space-y-4">
+                <div class="stats stats-vertical md:stats-horizontal shadow w-full">
+                    <div class="stat">
+                        <div class="stat-title">{ "Median Score" }</div>
+                        <div class="stat-value">
+                            { number_formatter.fmt2(breakdown.median_score).to_string() }
+                        </div>
+                    </div>
+                    <div class="stat">
+                        <div class="stat-title">{ "Win Rate" }</div>
+                        <div class="stat-value">{ format!("{}%", breakdown.win_rate.round()) }</div>
+                        <div class="stat-desc">
+                            { format!("from {} games", number_formatter.fmt2(breakdown.games)) }
+                        </div>
+                    </div>
+                </div>
+                <div class="grid grid-cols-1 md:grid-cols-2 gap-4">
+                    <div>
+                        <h4 class="font-semibold">{ "Final Scores" }</h4>
+                        <BarChart title="How many games ended in each score range" bars={breakdown.score_histogram.clone()} />
+                    </div>
+                    <div>
+                        <h4 class="font-semibold">{ "Best Tile" }</h4>
+                        <BarChart title="How many games had each tile as their best" bars={breakdown.highest_tiles.clone()} />
+                    </div>
+                </div>
+                <div>
+                    <h4 class="font-semibold">{ "Score Over Time" }</h4>
+                    <LineChart title="Final score of each of your recent games" points={breakdown.score_history.clone()} />
+                </div>
+            </div>
+        },
+    }
+}
+
+/// Every game saved on this device counts, no matter the mode
+async fn load_games_per_day(did: Option<Did>) -> Result<BTreeMap<i64, usize>, StorageError> {
+    let db = open_player_db(did.as_ref()).await?;
+    let games = object_get_all::<RecordStorageWrapper<game::RecordData>>(db, GAME_STORE).await?;
+    Ok(games_per_day(
+        games
+            .iter()
+            .map(|game| js_sys::Date::parse(game.record.created_at.as_str())),
+    ))
+}
+
+#[derive(Properties, PartialEq)]
+struct PlayActivityProps {
+    /// Longest streak from the stats record, it may have been set from games on another device
+    longest_streak: i64,
+}
+
+/// Streaks and a heatmap of how many games were played each day, from the games saved on this device
+#[function_component(PlayActivity)]
+fn play_activity(props: &PlayActivityProps) -> Html {
+    let (user_store, _) = use_store::<UserStore>();
+    let days: UseStateHandle<Option<BTreeMap<i64, usize>>> = use_state(|| None);
+    {
+        let days = days.clone();
+        use_effect_with(user_store.did.clone(), move |did| {
+            let did = did.clone();
+            spawn_local(async move {
+                match load_games_per_day(did).await {
+                    Ok(games_per_day) => days.set(Some(games_per_day)),
+                    Err(err) => {
+                        log::error!("Error loading your games: {:?}", err.to_string());
+                        days.set(Some(BTreeMap::new()));
+                    }
+                }
+            });
+        });
+    }
+
+    let Some(days) = days.as_ref() else {
+        return html! {
+            <div class="flex items-center justify-center">
+                <span class="loading loading-spinner loading-lg" />
+            </div>
+        };
+    };
+    let streaks = PlayStreaks::from_days(days.keys().copied(), local_day(js_sys::Date::now()));
+    let longest_streak = props.longest_streak.max(streaks.longest as i64);
+    let days_label = |days: i64| match days {
+        1 => String::from("1 day"),
+        days => format!("{} days", days),
+    };
+
+    html! {
+        <div class="space-y-4">
+            <div class="stats stats-vertical md:stats-horizontal shadow w-full">
+                <div class="stat">
+                    <div class="stat-title">{ "Current Streak" }</div>
+                    <div class="stat-value">{ days_label(streaks.current as i64) }</div>
+                    <div class="stat-desc">{ "Days in a row with a game played" }</div>
+                </div>
+                <div class="stat">
+                    <div class="stat-title">{ "Longest Streak" }</div>
+                    <div class="stat-value">{ days_label(longest_streak) }</div>
+                </div>
+            </div>
+            <Heatmap title="Games played each day over the last six months" days={days.clone()} />
+        </div>
+    }
+}
+
+/// Syncs with the AT repo first if logged in so achievements from other devices show up
+async fn load_achievements(
+    did: Option<Did>,
+) -> Result<Vec<achievement::RecordData>, AtRepoSyncError> {
+    let at_repo_sync = match did {
+        None => AtRepoSync::new_local_repo(),
+        Some(did) if is_expired(&did) => AtRepoSync::new_paused_repo(did),
+        Some(did) => {
+            let oauth_client = crate::oauth_client::oauth_client();
+            let session = oauth_client
+                .restore(&did)
+                .await
+                .map_err(|err| {
+                    log::error!("{:?}", err);
+                    AtRepoSyncError::AuthErrorNeedToReLogin
+                })?;
+            AtRepoSync::new_logged_in_repo(Agent::new(session), did)
+        }
+    };
+    if let Err(err) = at_repo_sync.sync_achievements().await {
+        log::error!("Error syncing achievements: {:?}", err.to_string());
+        if let (AtRepoSyncError::AuthErrorNeedToReLogin, Some(did)) =
+            (err, at_repo_sync.users_did())
+        {
+            report_expired(did);
+        }
+    }
+    at_repo_sync.get_local_achievements().await
+}
+
+/// Every achievement, with the unlocked ones highlighted
+#[function_component(AchievementList)]
+fn achievement_list() -> Html {
+    let (user_store, _) = use_store::<UserStore>();
+    let unlocked: UseStateHandle<Option<Vec<achievement::RecordData>>> = use_state(|| None);
+    {
+        let unlocked = unlocked.clone();
+        let did = user_store.did.clone();
+        use_effect_with(did, move |did| {
+            let did = did.clone();
+            spawn_local(async move {
+                match load_achievements(did).await {
+                    Ok(achievements) => unlocked.set(Some(achievements)),
+                    Err(err) => {
+                        log::error!("Error loading achievements: {:?}", err.to_string());
+                        unlocked.set(Some(vec![]));
+                    }
+                }
+            });
+        });
+    }
+
+    let Some(unlocked) = unlocked.as_ref() else {
+        return html! {
+            <div class="flex items-center justify-center">
+                <span class="loading loading-spinner loading-lg" />
+            </div>
+        };
+    };
+    let unlocked_at = |achievement: &Achievement| {
+        unlocked
+            .iter()
+            .find(|record| record.achievement == achievement.id())
+            .map(|record| record.created_at.as_ref().format("%B %-d, %Y").to_string())
+    };
+    let unlocked_count = Achievement::all()
+        .iter()
+        .filter(|achievement| unlocked_at(achievement).is_some())
+        .count();
+
+    html! {
+        <div class="space-y-4">
+            <p class="text-base-content/70">
+                { format!("{} of {} unlocked", unlocked_count, Achievement::all().len()) }
+            </p>
+            <div class="grid grid-cols-1 md:grid-cols-2 lg:grid-cols-3 gap-2">
+                { Achievement::all().iter().map(|achievement| {
+                    match unlocked_at(achievement) {
+                        Some(date) => html! {
+                            <div class="p-3 rounded-box border-2 border-primary">
+                                <div class="font-semibold">{ achievement.title() }</div>
+                                <div class="text-sm">{ achievement.description() }</div>
+                                <div class="text-xs text-base-content/70">{ format!("Unlocked {}", date) }</div>
+                            </div>
+                        },
+                        None => html! {
+                            <div class="p-3 rounded-box border-2 border-base-300 opacity-60">
+                                <div class="font-semibold">{ achievement.title() }</div>
+                                <div class="text-sm">{ achievement.description() }</div>
+                                <div class="text-xs text-base-content/70">{ "Locked" }</div>
+                            </div>
+                        },
+                    }
+                }).collect::<Html>() }
+            </div>
+        </div>
+    }
+}
+
+/// Play time as hours and minutes, or minutes and seconds if it has been under an hour
+fn format_play_time(ms: i64) -> String {
+    let total_seconds = ms / 1000;
+    let hours = total_seconds / 3600;
+    let minutes = (total_seconds % 3600) / 60;
+    let seconds = total_seconds % 60;
+    match hours {
+        0 => format!("{}m {:02}s", minutes, seconds),
+        _ => format!("{}h {:02}m", hours, minutes),
+    }
+}
+
 #[function_component(StatsPage)]
 pub fn stats() -> Html {
     let (user_store, _) = use_store::<UserStore>();
@@ -74,6 +448,15 @@ pub fn stats() -> Html {
                         }
                     }
                 }
+                //Syncing is paused till they login again, so it shows what is on this device
+                Some(did) if is_expired(&did) => {
+                    match AtRepoSync::new_paused_repo(did).get_local_player_stats().await {
+                        Ok(stats) => stats_state.set(stats),
+                        Err(err) => {
+                            log::error!("Error getting local stats: {:?}", err.to_string());
+                        }
+                    }
+                }
                 Some(did) => {
                     let oauth_client = crate::oauth_client::oauth_client();
                     let session = match oauth_client.restore(&did).await {
@@ -84,7 +467,7 @@ pub fn stats() -> Html {
                         }
                     };
                     let agent = Agent::new(session);
-                    let at_repo_sync = AtRepoSync::new_logged_in_repo(agent, did);
+                    let at_repo_sync = AtRepoSync::new_logged_in_repo(agent, did.clone());
                     match at_repo_sync.sync_stats().await {
                         Ok(_) => match at_repo_sync.get_local_player_stats().await {
                             Ok(stats) => stats_state.set(stats),
@@ -97,6 +480,9 @@ pub fn stats() -> Html {
                         },
                         Err(err) => {
                             log::error!("Error syncing stats: {:?}", err.to_string());
+                            if let AtRepoSyncError::AuthErrorNeedToReLogin = err {
+                                report_expired(&did);
+                            }
                         }
                     }
                 }
@@ -109,10 +495,10 @@ pub fn stats() -> Html {
     if let Some(stats_state) = (*stats_state).clone() {
         //HACK I am very sorry to who ever finds this. I don't have an explanation other than I gave up. Will comeback later...
         let mut formatter = number_formatter.clone();
-        let high_score_formatted = formatter.fmt2(stats_state.highest_score.clone());
+        let high_score_formatted = formatter.fmt2(stats_state.highest_score);
 
         let mut formatter = number_formatter.clone();
-        let average_score_formatted = formatter.fmt2(stats_state.average_score.clone());
+        let average_score_formatted = formatter.fmt2(stats_state.average_score);
 
         let mut formatter = number_formatter.clone();
         let total_score_formatted = formatter.fmt2(stats_state.total_score);
@@ -131,6 +517,17 @@ pub fn stats() -> Html {
         let mut formatter = number_formatter.clone();
         let total_games_formatted = formatter.fmt2(stats_state.games_played);
 
+        let mut formatter = number_formatter.clone();
+        let total_moves_formatted = formatter
+            .fmt2(stats_state.total_moves.unwrap_or(0))
+            .to_string();
+
+        let play_time_formatted = format_play_time(stats_state.total_play_time_ms.unwrap_or(0));
+        let fastest_twenty_forty_eight_formatted = stats_state
+            .fastest_twenty_forty_eight_ms
+            .map(format_play_time)
+            .unwrap_or(String::from("-"));
+
         html! {
             <div class="p-4">
                 <div class="max-w-4xl mx-auto space-y-4">
@@ -143,6 +540,11 @@ pub fn stats() -> Html {
                             <p class="text-base-content/70">
                                 { "Track your progress and achievements" }
                             </p>
+                            <div class="card-actions justify-end">
+                                <Link<Route> classes="btn btn-sm btn-primary" to={Route::FriendsPage}>
+                                    { "Compare with friends" }
+                                </Link<Route>>
+                            </div>
                         </div>
                     </div>
                     // Main Stats Grid
@@ -254,6 +656,12 @@ pub fn stats() -> Html {
                                         </div>
                                         <div class="stat-desc">{ "moves" }</div>
                                     </div>
+                                    <div class="stat">
+                                        <div class="stat-title">{ "Fastest 2048" }</div>
+                                        <div class="stat-value">
+                                            { fastest_twenty_forty_eight_formatted }
+                                        </div>
+                                    </div>
                                 </div>
                             </div>
                             <BSkyButton
@@ -294,6 +702,25 @@ pub fn stats() -> Html {
                                                     ) }
                                         </div>
                                     </div>
+                                    <div class="stat">
+                                        <div class="stat-title">{ "Time Played" }</div>
+                                        <div class="stat-value">{ play_time_formatted.clone() }</div>
+                                    </div>
+                                    <div class="stat">
+                                        <div class="stat-title">{ "Total Moves" }</div>
+                                        <div class="stat-value">{ total_moves_formatted }</div>
+                                        if let Some(direction_counts) = stats_state.direction_counts.as_ref() {
+                                            <div class="stat-desc">
+                                                { format!(
+                                                    "⬆ {} ⬇ {} ⬅ {} ➡ {}",
+                                                    direction_counts.up,
+                                                    direction_counts.down,
+                                                    direction_counts.left,
+                                                    direction_counts.right
+                                                ) }
+                                            </div>
+                                        }
+                                    </div>
                                     // <div class="stat">
                                     //     <div class="stat-title">{ "First Game" }</div>
                                     //     <div class="stat-desc">
@@ -303,10 +730,64 @@ pub fn stats() -> Html {
                                 </div>
                             </div>
                             <BSkyButton
-                                text={format!("I've played {} games of at://2048", total_games_formatted);}
+                                text={format!("I've played {} games of at://2048 over {}", total_games_formatted, play_time_formatted);}
                             />
                         </div>
                     </div>
+                    <div class="card shadow-xl">
+                        <div class="card-body">
+                            <h3 class="card-title">{ "Activity" }</h3>
+                            <PlayActivity longest_streak={stats_state.longest_streak.unwrap_or(0)} />
+                        </div>
+                    </div>
+                    <div class="card shadow-xl">
+                        <div class="card-body">
+                            <h3 class="card-title">{ "Milestones" }</h3>
+                            <AchievementList />
+                        </div>
+                    </div>
+                    <div class="card shadow-xl">
+                        <div class="card-body">
+                            <h3 class="card-title">{ "Your Games" }</h3>
+                            <p class="text-base-content/70">
+                                { "Classic games saved on this device" }
+                            </p>
+                            <GameCharts />
+                        </div>
+                    </div>
+                    if let Some(all_mode_stats) = stats_state.mode_stats.as_ref().filter(|mode_stats| !mode_stats.is_empty()) {
+                        <div class="card shadow-xl">
+                            <div class="card-body">
+                                <h3 class="card-title">{ "Game Modes" }</h3>
+                                <div class="overflow-x-auto">
+                                    <table class="table">
+                                        <thead>
+                                            <tr>
+                                                <th>{ "Mode" }</th>
+                                                <th>{ "Highest Score" }</th>
+                                                <th>{ "Average Score" }</th>
+                                                <th>{ "Games" }</th>
+                                            </tr>
+                                        </thead>
+                                        <tbody>
+                                            { all_mode_stats.iter().map(|mode_stats| {
+                                                let mut formatter = number_formatter.clone();
+                                                let mode = GameMode::from_record(Some(&mode_stats.mode), Some(mode_stats.limit)).unwrap_or_default();
+                                                html! {
+                                                    <tr>
+                                                        <td>{ mode.to_string() }</td>
+                                                        <td>{ formatter.fmt2(mode_stats.highest_score).to_string() }</td>
+                                                        <td>{ formatter.fmt2(mode_stats.average_score).to_string() }</td>
+                                                        <td>{ formatter.fmt2(mode_stats.games_played).to_string() }</td>
+                                                    </tr>
+                                                }
+                                            }).collect::<Html>() }
+                                        </tbody>
+                                    </table>
+                                </div>
+                            </div>
+                        </div>
+                    }
                 </div>
             </div>
         }
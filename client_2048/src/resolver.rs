@@ -1,14 +1,92 @@
-#[allow(dead_code)]
-use atrium_identity::handle::DnsTxtResolver;
+use crate::appview::{self, AppViewError};
+use crate::idb::{DB_NAME, DID_RESOLVER_STORE, object_get, transaction_put};
+use atrium_api::did_doc::DidDocument;
+use atrium_api::types::string::{Datetime, Did, Handle};
+use atrium_common::resolver::Resolver;
+use atrium_identity::Error as IdentityError;
+use atrium_identity::did::{
+    CommonDidResolver, CommonDidResolverConfig, DEFAULT_PLC_DIRECTORY_URL, DidResolver,
+};
+use atrium_identity::handle::{
+    AtprotoHandleResolver, AtprotoHandleResolverConfig, DnsTxtResolver, HandleResolver,
+};
+use atrium_oauth::DefaultHttpClient;
+use futures::future::{Either, select};
 use gloo::net::http::Request;
+use gloo::timers::future::TimeoutFuture;
+use indexed_db_futures::database::Database;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use yew::platform::spawn_local;
 
-/// Setup for dns resolver for the handle resolver
-pub struct ApiDNSTxtResolver;
+/// DNS over HTTPS endpoints that speak the JSON api, tried in order
+pub const DEFAULT_DOH_ENDPOINTS: [&str; 3] = [
+    "https://one.one.one.one/dns-query",
+    "https://dns.google/resolve",
+    "https://dns.quad9.net:5053/dns-query",
+];
+/// How long to wait on an endpoint before moving on to the next one
+pub const DEFAULT_DOH_TIMEOUT_MS: u32 = 3_000;
+/// DNS record type for TXT
+const TXT_RECORD_TYPE: i32 = 16;
+/// A cached did document is used as is for this long
+const DID_CACHE_FRESH_MINUTES: i64 = 60;
+/// After being fresh it is still used for this long, but refreshed in the background
+const DID_CACHE_STALE_MINUTES: i64 = 60 * 24 * 7;
+/// Response codes that are a real answer, NOERROR and NXDOMAIN. Anything else is the resolver
+/// having trouble
+const ANSWERED_STATUSES: [i32; 2] = [0, 3];
+
+/// Setup for dns resolver for the handle resolver. Browsers can't do DNS lookups themselves so this
+/// goes through DNS over HTTPS. If every endpoint fails the handle resolver falls back to
+/// https://{handle}/.well-known/atproto-did
+pub struct ApiDNSTxtResolver {
+    pub endpoints: Vec<String>,
+    pub timeout_ms: u32,
+}
 
 impl Default for ApiDNSTxtResolver {
+    /// Uses the comma separated DOH_ENDPOINTS from build time if set, otherwise the public resolvers
     fn default() -> Self {
-        Self {}
+        let endpoints = match std::option_env!("DOH_ENDPOINTS") {
+            Some(endpoints) => endpoints
+                .split(',')
+                .map(|endpoint| endpoint.trim().to_string())
+                .filter(|endpoint| !endpoint.is_empty())
+                .collect(),
+            None => DEFAULT_DOH_ENDPOINTS
+                .iter()
+                .map(|endpoint| endpoint.to_string())
+                .collect(),
+        };
+        Self {
+            endpoints,
+            timeout_ms: DEFAULT_DOH_TIMEOUT_MS,
+        }
+    }
+}
+
+impl ApiDNSTxtResolver {
+    /// Asks one endpoint, giving up after the timeout
+    async fn query_endpoint(&self, endpoint: &str, query: &str) -> Result<DnsResponse, String> {
+        let request_url = format!("{}?name={}&type=TXT", endpoint, query.to_lowercase());
+        let request = Box::pin(async move {
+            Request::get(request_url.as_str())
+                .header("accept", "application/dns-json")
+                .send()
+                .await
+                .map_err(|err| err.to_string())?
+                .json::<DnsResponse>()
+                .await
+                .map_err(|err| err.to_string())
+        });
+        match select(request, TimeoutFuture::new(self.timeout_ms)).await {
+            Either::Left((Ok(response), _)) if !ANSWERED_STATUSES.contains(&response.Status) => {
+                Err(format!("DNS status {}", response.Status))
+            }
+            Either::Left((response, _)) => response,
+            Either::Right(_) => Err(format!("timed out after {}ms", self.timeout_ms)),
+        }
     }
 }
 
@@ -18,36 +96,85 @@ impl DnsTxtResolver for ApiDNSTxtResolver {
         &self,
         query: &str,
     ) -> core::result::Result<Vec<String>, Box<dyn std::error::Error + Send + Sync + 'static>> {
-        let request_url = format!(
-            "https://one.one.one.one/dns-query?name={}&type=TXT",
-            query.to_lowercase()
-        );
-        let resp = Request::get(request_url.as_str())
-            .header("accept", "application/dns-json")
-            .send()
-            .await;
-
-        let resp = resp?.json::<DnsResponse>().await?;
-
-        let response_data = resp
-            .Answer
-            .iter()
-            .map(|a| a.data.clone().replace("\"", ""))
-            .collect::<Vec<String>>();
-        Ok(response_data)
+        let mut errors = Vec::new();
+        for endpoint in &self.endpoints {
+            match self.query_endpoint(endpoint, query).await {
+                //Any answer, even no records, is the real answer so there is no need to ask again
+                Ok(resp) => {
+                    let response_data = resp
+                        .Answer
+                        .iter()
+                        .filter(|a| a.type_ == TXT_RECORD_TYPE)
+                        .map(|a| a.data.clone().replace("\"", ""))
+                        .collect::<Vec<String>>();
+                    return Ok(response_data);
+                }
+                Err(err) => {
+                    log::warn!("DNS over HTTPS lookup with {} failed: {}", endpoint, err);
+                    errors.push(format!("{}: {}", endpoint, err));
+                }
+            }
+        }
+        Err(format!("Every DNS over HTTPS endpoint failed. {}", errors.join(", ")).into())
+    }
+}
+
+/// Resolves handles through the appview, which has real DNS and caches what it finds. If the
+/// appview can't be reached, like when running the client on its own, it falls back to DNS over
+/// HTTPS and https://{handle}/.well-known/atproto-did from the browser
+pub struct OriginHandleResolver {
+    fallback: AtprotoHandleResolver<ApiDNSTxtResolver, DefaultHttpClient>,
+}
+
+impl Default for OriginHandleResolver {
+    fn default() -> Self {
+        Self {
+            fallback: AtprotoHandleResolver::new(AtprotoHandleResolverConfig {
+                dns_txt_resolver: ApiDNSTxtResolver::default(),
+                http_client: Arc::new(DefaultHttpClient::default()),
+            }),
+        }
+    }
+}
+
+impl Resolver for OriginHandleResolver {
+    type Input = Handle;
+    type Output = Did;
+    type Error = IdentityError;
+
+    async fn resolve(&self, handle: &Self::Input) -> Result<Self::Output, Self::Error> {
+        match appview::resolve_handle(handle).await {
+            Ok(did) => Ok(did),
+            Err(AppViewError::NotFound) => Err(IdentityError::NotFound),
+            Err(AppViewError::Error(err)) => {
+                log::warn!("Resolving {} in the browser: {}", handle.as_str(), err);
+                self.fallback.resolve(handle).await
+            }
+        }
     }
 }
 
+impl HandleResolver for OriginHandleResolver {}
+
+/// Fields other than Status are left out by some resolvers when there is nothing to say,
+/// like Answer when the name has no records
 #[derive(Debug, Serialize, Deserialize)]
 #[allow(non_snake_case)]
 pub struct DnsResponse {
     pub Status: i32,
+    #[serde(default)]
     pub TC: bool,
+    #[serde(default)]
     pub RD: bool,
+    #[serde(default)]
     pub RA: bool,
+    #[serde(default)]
     pub AD: bool,
+    #[serde(default)]
     pub CD: bool,
+    #[serde(default)]
     pub Question: Vec<Question>,
+    #[serde(default)]
     pub Answer: Vec<Answer>,
 }
 
@@ -67,3 +194,118 @@ pub struct Answer {
     pub TTL: i32,
     pub data: String,
 }
+
+/// A did document saved in DID_RESOLVER_STORE, keyed by the did
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CachedDidDocument {
+    pub document: DidDocument,
+    pub fetched_at: Datetime,
+}
+
+impl CachedDidDocument {
+    fn age_minutes(&self) -> i64 {
+        Datetime::now()
+            .as_ref()
+            .signed_duration_since(*self.fetched_at.as_ref())
+            .num_minutes()
+    }
+}
+
+/// Resolves dids through the appview, or plc.directory and did:web if it can't be reached,
+/// keeping the documents in IndexedDB so logins and profiles don't wait on the network every time.
+/// Fresh documents are used straight away, stale ones are used while a new copy is fetched in the
+/// background, and anything older waits on the fetch. If the fetch fails any cached copy is better
+/// than nothing
+pub struct CachingDidResolver {
+    inner: CommonDidResolver<DefaultHttpClient>,
+}
+
+impl Default for CachingDidResolver {
+    fn default() -> Self {
+        Self {
+            inner: plc_did_resolver(),
+        }
+    }
+}
+
+fn plc_did_resolver() -> CommonDidResolver<DefaultHttpClient> {
+    CommonDidResolver::new(CommonDidResolverConfig {
+        plc_directory_url: DEFAULT_PLC_DIRECTORY_URL.to_string(),
+        http_client: Arc::new(DefaultHttpClient::default()),
+    })
+}
+
+async fn cached_did_document(did: &Did) -> Option<CachedDidDocument> {
+    let db = Database::open(DB_NAME).await.ok()?;
+    match object_get::<CachedDidDocument>(db, DID_RESOLVER_STORE, did.as_str()).await {
+        Ok(cached) => cached,
+        Err(err) => {
+            log::error!("Error reading the did cache: {}", err.to_string());
+            None
+        }
+    }
+}
+
+/// Resolves the did and saves the document for next time
+async fn fetch_did_document(
+    resolver: &CommonDidResolver<DefaultHttpClient>,
+    did: &Did,
+) -> Result<DidDocument, IdentityError> {
+    let document = match appview::resolve_did(did).await {
+        Ok(document) => document,
+        Err(AppViewError::NotFound) => return Err(IdentityError::NotFound),
+        Err(AppViewError::Error(err)) => {
+            log::warn!("Resolving {} in the browser: {}", did.as_str(), err);
+            resolver.resolve(did).await?
+        }
+    };
+    let cached = CachedDidDocument {
+        document: document.clone(),
+        fetched_at: Datetime::now(),
+    };
+    match Database::open(DB_NAME).await {
+        Ok(db) => {
+            if let Err(err) =
+                transaction_put(db, &cached, DID_RESOLVER_STORE, Some(did.to_string())).await
+            {
+                log::error!("Error saving to the did cache: {}", err.to_string());
+            }
+        }
+        Err(err) => log::error!("Error opening database: {:?}", err),
+    }
+    Ok(document)
+}
+
+impl Resolver for CachingDidResolver {
+    type Input = Did;
+    type Output = DidDocument;
+    type Error = IdentityError;
+
+    async fn resolve(&self, did: &Self::Input) -> Result<Self::Output, Self::Error> {
+        let cached = cached_did_document(did).await;
+        match cached {
+            Some(cached) if cached.age_minutes() < DID_CACHE_FRESH_MINUTES => Ok(cached.document),
+            Some(cached) if cached.age_minutes() < DID_CACHE_STALE_MINUTES => {
+                let did = did.clone();
+                spawn_local(async move {
+                    if let Err(err) = fetch_did_document(&plc_did_resolver(), &did).await {
+                        log::error!("Error refreshing the did document for {}: {}", did.as_str(), err);
+                    }
+                });
+                Ok(cached.document)
+            }
+            cached => match fetch_did_document(&self.inner, did).await {
+                Ok(document) => Ok(document),
+                Err(err) => match cached {
+                    Some(cached) => {
+                        log::warn!("Using an old did document for {}: {}", did.as_str(), err);
+                        Ok(cached.document)
+                    }
+                    None => Err(err),
+                },
+            },
+        }
+    }
+}
+
+impl DidResolver for CachingDidResolver {}
@@ -0,0 +1,159 @@
+use crate::agent::{StorageRequest, StorageResponse, StorageTask};
+use crate::at_repo_sync::AtRepoSyncError;
+use crate::idb::{DB_NAME, SESSIONS_STORE, object_get};
+use crate::oauth_client::oauth_client;
+use atrium_api::agent::Agent;
+use atrium_api::types::string::Did;
+use atrium_oauth::store::session::Session;
+use atrium_xrpc::Error::Authentication;
+use gloo::timers::callback::Interval;
+use indexed_db_futures::database::Database;
+use std::cell::Cell;
+use std::rc::Rc;
+use yew::platform::spawn_local;
+use yew::prelude::*;
+use yew_agent::oneshot::{UseOneshotRunnerHandle, use_oneshot_runner};
+use yewdux::prelude::*;
+
+/// How often the active account's login is checked while the app is open. Access tokens only last
+/// a short while, so this keeps them refreshed before a game needs them
+const CHECK_INTERVAL_MS: u32 = 5 * 60 * 1000;
+
+/// The login state for the account being played as. Not saved, it is checked again on every load
+#[derive(Clone, Default, PartialEq, Store)]
+pub struct SessionState {
+    /// Account whose login has expired. Remote syncing for it is paused till they login again
+    pub expired: Option<Did>,
+    /// The player has hidden the banner for this expiry
+    pub dismissed: bool,
+}
+
+/// What a check of the login found
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SessionStatus {
+    Active,
+    /// The tokens could not be refreshed, they need to login again
+    Expired,
+    /// The PDS could not be reached, so it is not known either way
+    Unknown,
+}
+
+/// Pauses remote syncing for the account and shows the banner asking them to login again
+pub fn report_expired(did: &Did) {
+    Dispatch::<SessionState>::global().reduce_mut(|state| {
+        if state.expired.as_ref() != Some(did) {
+            state.expired = Some(did.clone());
+            state.dismissed = false;
+        }
+    });
+}
+
+/// If remote syncing is paused for the account till they login again
+pub fn is_expired(did: &Did) -> bool {
+    Dispatch::<SessionState>::global().get().expired.as_ref() == Some(did)
+}
+
+/// If there are tokens saved for the account at all. Logging out or revoking removes them
+pub async fn has_saved_session(did: &Did) -> bool {
+    let Ok(db) = Database::open(DB_NAME).await else {
+        return false;
+    };
+    matches!(
+        object_get::<Session>(db, SESSIONS_STORE, did.as_str()).await,
+        Ok(Some(_))
+    )
+}
+
+/// Makes a cheap authenticated call so the tokens are refreshed if they need to be. A failed
+/// refresh comes back as an authentication error
+pub async fn check_session(did: &Did) -> SessionStatus {
+    if !has_saved_session(did).await {
+        return SessionStatus::Expired;
+    }
+    let session = match oauth_client().restore(did).await {
+        Ok(session) => session,
+        Err(err) => {
+            log::warn!("Could not restore the session to check it: {}", err);
+            return SessionStatus::Unknown;
+        }
+    };
+    match Agent::new(session)
+        .api
+        .com
+        .atproto
+        .server
+        .get_session()
+        .await
+    {
+        Ok(_) => SessionStatus::Active,
+        Err(Authentication(_)) => SessionStatus::Expired,
+        Err(err) => {
+            log::warn!("Could not check the session: {}", err);
+            SessionStatus::Unknown
+        }
+    }
+}
+
+/// Keeps the active account's login fresh while the app is open. Checks right away and then every
+/// few minutes. Anything saved during an expiry or while offline is synced when the login becomes
+/// good again, not on every check
+#[hook]
+pub fn use_session_manager(did: Option<Did>) {
+    let storage_task = use_oneshot_runner::<StorageTask>();
+    use_effect_with(did, move |did| {
+        let interval = did.clone().map(|did| {
+            //Nothing is known on load, so the first good check counts as the login becoming good
+            let last_status = Rc::new(Cell::new(None));
+            spawn_local(refresh_session(
+                did.clone(),
+                storage_task.clone(),
+                last_status.clone(),
+            ));
+            Interval::new(CHECK_INTERVAL_MS, move || {
+                spawn_local(refresh_session(
+                    did.clone(),
+                    storage_task.clone(),
+                    last_status.clone(),
+                ));
+            })
+        });
+        move || drop(interval)
+    });
+}
+
+async fn refresh_session(
+    did: Did,
+    storage_task: UseOneshotRunnerHandle<StorageTask>,
+    last_status: Rc<Cell<Option<SessionStatus>>>,
+) {
+    let status = check_session(&did).await;
+    let was_active = last_status.replace(Some(status)) == Some(SessionStatus::Active);
+    match status {
+        SessionStatus::Active if was_active => {}
+        SessionStatus::Active => {
+            Dispatch::<SessionState>::global().reduce_mut(|state| {
+                if state.expired.as_ref() == Some(&did) {
+                    state.expired = None;
+                }
+            });
+            match storage_task
+                .run(StorageRequest::SyncPending(did.clone()))
+                .await
+            {
+                StorageResponse::Success => log::info!("Synced what was saved while offline or logged out"),
+                StorageResponse::RepoError(AtRepoSyncError::AuthErrorNeedToReLogin) => {
+                    report_expired(&did)
+                }
+                StorageResponse::RepoError(err) => {
+                    log::error!("Error syncing what was saved while offline or logged out: {:?}", err)
+                }
+                StorageResponse::Error(err) => {
+                    log::error!("Error syncing what was saved while offline or logged out: {:?}", err)
+                }
+                _ => {}
+            }
+        }
+        SessionStatus::Expired => report_expired(&did),
+        SessionStatus::Unknown => {}
+    }
+}
@@ -0,0 +1,239 @@
+use twothousand_forty_eight::direction::{Direction, MOVE_DIRECTIONS};
+use twothousand_forty_eight::unified::game::GameState;
+
+/// How many player moves ahead the search looks
+const SEARCH_DEPTH: usize = 3;
+/// Caps how many empty cells a chance node looks at so big open boards do not blow up the search
+const MAX_CHANCE_CELLS: usize = 6;
+/// Chance a new tile is a 2 instead of a 4
+const TWO_TILE_CHANCE: f64 = 0.9;
+
+const EMPTY_WEIGHT: f64 = 270.0;
+const MONOTONICITY_WEIGHT: f64 = 47.0;
+const SMOOTHNESS_WEIGHT: f64 = 11.0;
+const CORNER_WEIGHT: f64 = 100.0;
+const GAME_OVER_PENALTY: f64 = -100_000.0;
+
+/// A board of tile exponents (2 is 1, 4 is 2, etc), 0 is an empty cell. Row major
+#[derive(Clone, PartialEq)]
+struct Grid {
+    width: usize,
+    height: usize,
+    cells: Vec<u32>,
+}
+
+impl Grid {
+    fn from_gamestate(gamestate: &GameState) -> Self {
+        let width = gamestate.board.width;
+        let height = gamestate.board.height;
+        let mut cells = vec![0; width * height];
+        for tile in gamestate.board.tiles.iter().flatten().filter_map(|tile| *tile) {
+            if tile.value > 0 {
+                cells[tile.y * width + tile.x] = tile.value.trailing_zeros();
+            }
+        }
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> u32 {
+        self.cells[y * self.width + x]
+    }
+
+    /// The cells of every row or column in the order tiles slide towards for the direction
+    fn lines(&self, direction: Direction) -> Vec<Vec<usize>> {
+        match direction {
+            Direction::LEFT => (0..self.height)
+                .map(|y| (0..self.width).map(|x| y * self.width + x).collect())
+                .collect(),
+            Direction::RIGHT => (0..self.height)
+                .map(|y| (0..self.width).rev().map(|x| y * self.width + x).collect())
+                .collect(),
+            Direction::UP => (0..self.width)
+                .map(|x| (0..self.height).map(|y| y * self.width + x).collect())
+                .collect(),
+            Direction::DOWN => (0..self.width)
+                .map(|x| (0..self.height).rev().map(|y| y * self.width + x).collect())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Slides and merges the tiles. None if nothing moved
+    fn slide(&self, direction: Direction) -> Option<Grid> {
+        let mut moved = self.clone();
+        for line in self.lines(direction) {
+            let tiles = line
+                .iter()
+                .map(|index| self.cells[*index])
+                .filter(|value| *value > 0)
+                .collect::<Vec<_>>();
+            let mut merged = Vec::with_capacity(line.len());
+            let mut i = 0;
+            while i < tiles.len() {
+                if i + 1 < tiles.len() && tiles[i] == tiles[i + 1] {
+                    merged.push(tiles[i] + 1);
+                    i += 2;
+                } else {
+                    merged.push(tiles[i]);
+                    i += 1;
+                }
+            }
+            merged.resize(line.len(), 0);
+            for (index, value) in line.iter().zip(merged) {
+                moved.cells[*index] = value;
+            }
+        }
+        (moved != *self).then_some(moved)
+    }
+
+    fn empty_cells(&self) -> Vec<usize> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| **value == 0)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// How good a board looks. Likes open space, rows and columns that go one way, neighbours
+    /// that can merge, and the biggest tile in a corner
+    fn evaluate(&self) -> f64 {
+        let empty = self.empty_cells().len() as f64;
+
+        let mut monotonicity = 0.0;
+        let mut smoothness = 0.0;
+        for direction in [Direction::LEFT, Direction::UP] {
+            for line in self.lines(direction) {
+                let values = line
+                    .iter()
+                    .map(|index| self.cells[*index] as f64)
+                    .collect::<Vec<_>>();
+                let mut increasing = 0.0;
+                let mut decreasing = 0.0;
+                for pair in values.windows(2) {
+                    if pair[0] > pair[1] {
+                        decreasing += pair[0] - pair[1];
+                    } else {
+                        increasing += pair[1] - pair[0];
+                    }
+                    if pair[0] > 0.0 && pair[1] > 0.0 {
+                        smoothness -= (pair[0] - pair[1]).abs();
+                    }
+                }
+                monotonicity -= f64::min(increasing, decreasing);
+            }
+        }
+
+        let max = self.cells.iter().copied().max().unwrap_or(0);
+        let corners = [
+            self.get(0, 0),
+            self.get(self.width - 1, 0),
+            self.get(0, self.height - 1),
+            self.get(self.width - 1, self.height - 1),
+        ];
+        let corner = if corners.contains(&max) {
+            max as f64
+        } else {
+            0.0
+        };
+
+        empty * EMPTY_WEIGHT
+            + monotonicity * MONOTONICITY_WEIGHT
+            + smoothness * SMOOTHNESS_WEIGHT
+            + corner * CORNER_WEIGHT
+    }
+}
+
+fn max_node(grid: &Grid, depth: usize) -> f64 {
+    MOVE_DIRECTIONS
+        .iter()
+        .filter_map(|direction| grid.slide(*direction))
+        .map(|moved| chance_node(&moved, depth))
+        .fold(None, |best: Option<f64>, score| {
+            Some(best.map_or(score, |best| best.max(score)))
+        })
+        .unwrap_or(GAME_OVER_PENALTY)
+}
+
+fn chance_node(grid: &Grid, depth: usize) -> f64 {
+    let empty_cells = grid.empty_cells();
+    if depth == 0 || empty_cells.is_empty() {
+        return grid.evaluate();
+    }
+    //Spread the samples out across the board instead of just the first few cells
+    let step = empty_cells.len().div_ceil(MAX_CHANCE_CELLS);
+    let sampled = empty_cells.iter().step_by(step).collect::<Vec<_>>();
+
+    let mut total = 0.0;
+    for index in &sampled {
+        for (value, chance) in [(1, TWO_TILE_CHANCE), (2, 1.0 - TWO_TILE_CHANCE)] {
+            let mut spawned = grid.clone();
+            spawned.cells[**index] = value;
+            total += chance * max_node(&spawned, depth - 1);
+        }
+    }
+    total / sampled.len() as f64
+}
+
+/// Finds the move with the best expected outcome using an expectimax search. None if the game
+/// is over
+pub fn suggest_move(gamestate: &GameState) -> Option<Direction> {
+    if gamestate.over {
+        return None;
+    }
+    best_move(&Grid::from_gamestate(gamestate), &gamestate.allowed_moves)
+}
+
+fn best_move(grid: &Grid, allowed_moves: &[Direction]) -> Option<Direction> {
+    MOVE_DIRECTIONS
+        .iter()
+        .filter(|direction| allowed_moves.contains(direction))
+        .filter_map(|direction| {
+            grid.slide(*direction)
+                .map(|moved| (*direction, chance_node(&moved, SEARCH_DEPTH)))
+        })
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(direction, _)| direction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: [[u32; 4]; 4]) -> Grid {
+        Grid {
+            width: 4,
+            height: 4,
+            cells: rows.concat(),
+        }
+    }
+
+    #[test]
+    fn only_legal_move_is_suggested() {
+        //Only sliding left into the empty column changes the board
+        let grid = grid([[0, 1, 2, 3], [0, 2, 3, 1], [0, 3, 1, 2], [0, 1, 2, 3]]);
+        assert_eq!(best_move(&grid, &MOVE_DIRECTIONS), Some(Direction::LEFT));
+    }
+
+    #[test]
+    fn merge_is_preferred() {
+        //Left and right merge the two 64s, up and down only slide them
+        let grid = grid([[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [6, 6, 0, 0]]);
+        let suggested = best_move(&grid, &MOVE_DIRECTIONS);
+        assert!(
+            matches!(suggested, Some(Direction::LEFT) | Some(Direction::RIGHT)),
+            "{:?}",
+            suggested
+        );
+    }
+
+    #[test]
+    fn no_move_when_board_is_stuck() {
+        let grid = grid([[1, 2, 1, 2], [2, 1, 2, 1], [1, 2, 1, 2], [2, 1, 2, 1]]);
+        assert_eq!(best_move(&grid, &MOVE_DIRECTIONS), None);
+    }
+}
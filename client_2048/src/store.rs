@@ -6,17 +6,57 @@ use yewdux::prelude::*;
 #[store(storage = "local")]
 #[derive(Clone)]
 pub struct UserStore {
+    /// The account being played as, None plays as a guest
     pub did: Option<Did>,
     pub handle: Option<Handle>,
+    /// Every account logged in on this browser, including the active one
+    #[serde(default)]
+    pub accounts: Vec<Account>,
 }
 
-//Incase I need a debug listener later
-// #[store(storage = "local", listener(LogListener))]
-// struct LogListener;
-// impl Listener for LogListener {
-//     type Store = UserStore;
-//
-//     fn on_change(&self, _cx: &Context, state: Rc<Self::Store>) {
-//         log::info!("Theres a did {}", state.did.is_some());
-//     }
-// }
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Account {
+    pub did: Did,
+    pub handle: Option<Handle>,
+}
+
+impl UserStore {
+    /// Adds the account or updates its handle, and switches to it
+    pub fn add_account(&mut self, did: Did, handle: Option<Handle>) {
+        match self.accounts.iter_mut().find(|account| account.did == did) {
+            Some(account) => account.handle = handle.clone(),
+            None => self.accounts.push(Account {
+                did: did.clone(),
+                handle: handle.clone(),
+            }),
+        }
+        self.did = Some(did);
+        self.handle = handle;
+    }
+
+    /// Switches to one of the logged in accounts, or to playing as a guest with None
+    pub fn switch_to(&mut self, did: Option<&Did>) {
+        let account = did.and_then(|did| self.accounts.iter().find(|account| &account.did == did));
+        self.did = account.map(|account| account.did.clone());
+        self.handle = account.and_then(|account| account.handle.clone());
+    }
+
+    /// Forgets the account, moving on to the next one logged in if it was active
+    pub fn remove_account(&mut self, did: &Did) {
+        self.accounts.retain(|account| &account.did != did);
+        if self.did.as_ref() == Some(did) {
+            let next = self.accounts.first().map(|account| account.did.clone());
+            self.switch_to(next.as_ref());
+        }
+    }
+
+    /// Logins from before multiple accounts were supported only set the active did
+    pub fn list_active_account(&mut self) {
+        if let Some(did) = self.did.clone() {
+            if !self.accounts.iter().any(|account| account.did == did) {
+                let handle = self.handle.clone();
+                self.add_account(did, handle);
+            }
+        }
+    }
+}
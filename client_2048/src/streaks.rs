@@ -0,0 +1,26 @@
+use std::collections::BTreeMap;
+
+const DAY_MS: f64 = 86_400_000.0;
+
+/// The day number in the browser's time zone of a timestamp in milliseconds
+pub fn local_day(timestamp_ms: f64) -> i64 {
+    let date = js_sys::Date::new(&timestamp_ms.into());
+    let offset_ms = date.get_timezone_offset() * 60_000.0;
+    ((timestamp_ms - offset_ms) / DAY_MS).floor() as i64
+}
+
+/// Midnight in the browser's time zone of a day from local_day, in milliseconds
+pub fn day_start_ms(day: i64) -> f64 {
+    let utc_midnight = day as f64 * DAY_MS;
+    let offset_ms = js_sys::Date::new(&utc_midnight.into()).get_timezone_offset() * 60_000.0;
+    utc_midnight + offset_ms
+}
+
+/// How many games were played on each local day. Timestamps that could not be parsed are skipped
+pub fn games_per_day(played_at_ms: impl IntoIterator<Item = f64>) -> BTreeMap<i64, usize> {
+    let mut days = BTreeMap::new();
+    for timestamp in played_at_ms.into_iter().filter(|timestamp| !timestamp.is_nan()) {
+        *days.entry(local_day(timestamp)).or_default() += 1;
+    }
+    days
+}
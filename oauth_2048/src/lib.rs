@@ -0,0 +1,103 @@
+//! The OAuth client's definition, shared by the client that logs in with it and the appview that
+//! serves its client_metadata.json, so the two can't drift apart
+use atrium_api::types::Collection;
+use atrium_oauth::{
+    AtprotoClientMetadata, AuthMethod, GrantType, KnownScope, OAuthClientMetadata, Scope,
+};
+use types_2048::blue::_2048;
+
+/// Name shown to players on their PDS's login page
+pub const CLIENT_NAME: &str = "at://2048";
+/// The only records the game writes to the player's repo
+const GAME_COLLECTIONS: [&str; 4] = [
+    _2048::Game::NSID,
+    _2048::player::Stats::NSID,
+    _2048::player::Profile::NSID,
+    _2048::player::Achievement::NSID,
+];
+/// Bluesky reads made through the player's PDS, for their handle and friends list
+const BLUESKY_READS: [&str; 3] = [
+    "app.bsky.actor.getProfile",
+    "app.bsky.graph.getFollows",
+    "app.bsky.graph.getFollowers",
+];
+
+/// What every login asks for, writing the game's own records and reading a little from Bluesky
+pub fn game_scopes() -> Vec<Scope> {
+    let mut scopes = vec![Scope::Known(KnownScope::Atproto)];
+    scopes.extend(
+        GAME_COLLECTIONS
+            .iter()
+            .map(|collection| Scope::Unknown(format!("repo:{}", collection))),
+    );
+    scopes.extend(
+        BLUESKY_READS
+            .iter()
+            .map(|method| Scope::Unknown(format!("rpc:{}?aud=*", method))),
+    );
+    scopes
+}
+
+/// Only asked for once the player wants to post their game to Bluesky
+pub fn posting_scopes() -> Vec<Scope> {
+    vec![
+        Scope::Unknown(String::from("repo:app.bsky.feed.post?action=create")),
+        Scope::Unknown(String::from("blob:image/*")),
+    ]
+}
+
+/// Everything the client could ask for, which is what the client metadata has to list
+pub fn all_scopes() -> Vec<Scope> {
+    let mut scopes = game_scopes();
+    scopes.extend(posting_scopes());
+    scopes
+}
+
+/// The client metadata for the client served from the origin, like https://2048.blue
+pub fn client_metadata(origin: &str) -> AtprotoClientMetadata {
+    AtprotoClientMetadata {
+        client_id: format!("{}/client_metadata.json", origin),
+        client_uri: Some(origin.to_string()),
+        redirect_uris: vec![format!("{}/oauth/callback", origin)],
+        token_endpoint_auth_method: AuthMethod::None,
+        grant_types: vec![GrantType::AuthorizationCode, GrantType::RefreshToken],
+        scopes: all_scopes(),
+        jwks_uri: None,
+        token_endpoint_auth_signing_alg: None,
+    }
+}
+
+/// client_metadata.json for the origin, with the display fields the OAuth client doesn't need.
+/// Filled in the same way atrium does when it checks the metadata, which it doesn't expose
+pub fn client_metadata_document(origin: &str) -> Result<serde_json::Value, serde_json::Error> {
+    let metadata = client_metadata(origin);
+    let metadata = OAuthClientMetadata {
+        client_id: metadata.client_id,
+        client_uri: metadata.client_uri,
+        redirect_uris: metadata.redirect_uris,
+        scope: Some(
+            metadata
+                .scopes
+                .iter()
+                .map(AsRef::as_ref)
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        grant_types: Some(metadata.grant_types.into_iter().map(String::from).collect()),
+        token_endpoint_auth_method: Some(metadata.token_endpoint_auth_method.into()),
+        dpop_bound_access_tokens: Some(true),
+        jwks_uri: metadata.jwks_uri,
+        jwks: None,
+        token_endpoint_auth_signing_alg: metadata.token_endpoint_auth_signing_alg,
+    };
+    let mut document = serde_json::to_value(metadata)?;
+    if let Some(document) = document.as_object_mut() {
+        document.insert(String::from("client_name"), CLIENT_NAME.into());
+        document.insert(
+            String::from("logo_uri"),
+            format!("{}/icon.png", origin).into(),
+        );
+        document.insert(String::from("response_types"), vec!["code"].into());
+    }
+    Ok(document)
+}
@@ -0,0 +1,118 @@
+//! Colors and layout of the picture of a game's final board. The appview draws it as the link
+//! preview and the client draws it on a canvas to attach to Bluesky posts, so both look the same
+
+/// The usual OpenGraph image size
+pub const CARD_WIDTH: f64 = 1200.0;
+pub const CARD_HEIGHT: f64 = 630.0;
+pub const CARD_PADDING: f64 = 40.0;
+pub const TILE_GAP: f64 = 12.0;
+pub const TILE_RADIUS: f64 = 6.0;
+pub const BOARD_RADIUS: f64 = 10.0;
+pub const FONT_FAMILY: &str = "Helvetica, Arial, sans-serif";
+
+pub const BACKGROUND_COLOR: &str = "#faf8ef";
+pub const BOARD_COLOR: &str = "#bbada0";
+pub const TEXT_COLOR: &str = "#776e65";
+pub const FOOTER_COLOR: &str = "#8f7a66";
+
+/// A line of text to the right of the board
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CardText {
+    /// Baseline of the text
+    pub y: f64,
+    pub font_size: f64,
+    pub bold: bool,
+}
+
+pub const TITLE_TEXT: CardText = CardText {
+    y: 110.0,
+    font_size: 64.0,
+    bold: true,
+};
+pub const NAME_TEXT: CardText = CardText {
+    y: 190.0,
+    font_size: 34.0,
+    bold: false,
+};
+pub const SCORE_LABEL_TEXT: CardText = CardText {
+    y: 290.0,
+    font_size: 30.0,
+    bold: false,
+};
+pub const SCORE_TEXT: CardText = CardText {
+    y: 370.0,
+    font_size: 80.0,
+    bold: true,
+};
+pub const DETAILS_TEXT: CardText = CardText {
+    y: 450.0,
+    font_size: 30.0,
+    bold: false,
+};
+pub const MODE_TEXT: CardText = CardText {
+    y: 500.0,
+    font_size: 26.0,
+    bold: false,
+};
+pub const FOOTER_TEXT: CardText = CardText {
+    y: CARD_HEIGHT - CARD_PADDING - 10.0,
+    font_size: 26.0,
+    bold: false,
+};
+
+/// Same colors as the light theme board in the client, background then text
+pub fn tile_colors(value: usize) -> (&'static str, &'static str) {
+    match value {
+        0 => ("#cdc1b4", "#776e65"),
+        2 => ("#eee4da", "#776e65"),
+        4 => ("#eee1c9", "#776e65"),
+        8 => ("#f3b27a", "#f9f6f2"),
+        16 => ("#f69664", "#f9f6f2"),
+        32 => ("#f77c5f", "#f9f6f2"),
+        64 => ("#f75f3b", "#f9f6f2"),
+        128 => ("#edd073", "#f9f6f2"),
+        256 => ("#edcc62", "#f9f6f2"),
+        512 => ("#edc950", "#f9f6f2"),
+        1024 => ("#edc53f", "#f9f6f2"),
+        _ => ("#edc22e", "#f9f6f2"),
+    }
+}
+
+/// The board is a square on the left of the card
+pub fn board_size() -> f64 {
+    CARD_HEIGHT - CARD_PADDING * 2.0
+}
+
+/// Where the text starts, just right of the board
+pub fn text_x() -> f64 {
+    CARD_PADDING * 2.0 + board_size()
+}
+
+/// Where the tiles go on a board with this many cells on its longest side
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TileLayout {
+    pub size: f64,
+}
+
+impl TileLayout {
+    pub fn new(width: usize, height: usize) -> Self {
+        let cells = width.max(height).max(1) as f64;
+        TileLayout {
+            size: (board_size() - TILE_GAP * (cells + 1.0)) / cells,
+        }
+    }
+
+    /// Top left corner of the tile in column x and row y
+    pub fn position(&self, x: usize, y: usize) -> (f64, f64) {
+        (
+            CARD_PADDING + TILE_GAP + x as f64 * (self.size + TILE_GAP),
+            CARD_PADDING + TILE_GAP + y as f64 * (self.size + TILE_GAP),
+        )
+    }
+
+    /// Shrinks the text as numbers get longer so they stay in the tile
+    pub fn font_size(&self, value: usize) -> f64 {
+        let digits = value.to_string().len() as f64;
+        self.size * 11.0 / (20.0 + digits * 5.0)
+    }
+}
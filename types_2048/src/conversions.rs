@@ -0,0 +1,33 @@
+//! Turns the untyped records that come back from a repo into the generated record types.
+//!
+//! esquema-codegen gives every record a `From<Unknown>` that unwraps, so one malformed record
+//! would panic whoever called `.into()`. Those impls are deleted after regenerating and these
+//! `TryFrom` impls are used instead. If a regeneration brings them back the build fails with
+//! conflicting implementations until they are deleted again.
+use crate::blue::_2048::{game, key, player, verification};
+use atrium_api::types::{TryFromUnknown, Unknown};
+
+macro_rules! try_from_unknown {
+    ($($record:ty),* $(,)?) => {
+        $(
+            impl TryFrom<Unknown> for $record {
+                type Error = atrium_api::error::Error;
+
+                fn try_from(value: Unknown) -> Result<Self, Self::Error> {
+                    Self::try_from_unknown(value)
+                }
+            }
+        )*
+    };
+}
+
+try_from_unknown!(
+    game::RecordData,
+    key::game::RecordData,
+    key::player::stats::RecordData,
+    player::achievement::RecordData,
+    player::profile::RecordData,
+    player::stats::RecordData,
+    verification::game::RecordData,
+    verification::stats::RecordData,
+);
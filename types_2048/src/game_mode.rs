@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// How long a time attack game lasts
+pub const TIME_ATTACK_SECONDS: u32 = 180;
+/// How many moves a move limited game gets
+pub const MOVE_LIMIT_MOVES: u32 = 200;
+
+/// The rules a game is played under. Saved on the game record as mode and limit
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum GameMode {
+    /// Play until there are no moves left
+    #[default]
+    Classic,
+    /// Highest score before the clock runs out, in seconds
+    TimeAttack(u32),
+    /// Highest score within a number of moves
+    MoveLimit(u32),
+}
+
+impl GameMode {
+    /// Every mode the player can pick from
+    pub fn options() -> [GameMode; 3] {
+        [
+            GameMode::Classic,
+            GameMode::TimeAttack(TIME_ATTACK_SECONDS),
+            GameMode::MoveLimit(MOVE_LIMIT_MOVES),
+        ]
+    }
+
+    /// Reads the mode back off a record. None if the mode is not one we know or its limit is
+    /// missing, the appview leaves those games off the boards and the client plays them as classic
+    pub fn from_record(mode: Option<&str>, limit: Option<i64>) -> Option<Self> {
+        let limit = limit
+            .and_then(|limit| u32::try_from(limit).ok())
+            .filter(|limit| *limit > 0);
+        match (mode, limit) {
+            (None | Some("classic"), _) => Some(GameMode::Classic),
+            (Some("timeAttack"), Some(seconds)) => Some(GameMode::TimeAttack(seconds)),
+            (Some("moveLimit"), Some(moves)) => Some(GameMode::MoveLimit(moves)),
+            _ => None,
+        }
+    }
+
+    /// The mode as it is saved in the record's mode field
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "classic",
+            GameMode::TimeAttack(_) => "timeAttack",
+            GameMode::MoveLimit(_) => "moveLimit",
+        }
+    }
+
+    /// Seconds for time attack and moves for move limit. Classic games do not have one
+    pub fn limit(&self) -> Option<u32> {
+        match self {
+            GameMode::Classic => None,
+            GameMode::TimeAttack(seconds) => Some(*seconds),
+            GameMode::MoveLimit(moves) => Some(*moves),
+        }
+    }
+
+    /// The record's mode field. Classic is left off so older games and new ones look the same
+    pub fn record_mode(&self) -> Option<String> {
+        match self {
+            GameMode::Classic => None,
+            _ => Some(self.as_str().to_string()),
+        }
+    }
+
+    /// The record's limit field
+    pub fn record_limit(&self) -> Option<i64> {
+        self.limit().map(i64::from)
+    }
+}
+
+impl Display for GameMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameMode::Classic => write!(f, "Classic"),
+            GameMode::TimeAttack(seconds) => {
+                write!(f, "Time attack ({}:{:02})", seconds / 60, seconds % 60)
+            }
+            GameMode::MoveLimit(moves) => write!(f, "{} moves", moves),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+// @generated - This file is generated by esquema-codegen (forked from atrium-codegen). DO NOT EDIT.
+//!Definitions for the `blue.2048.game` namespace.
+use atrium_api::types::TryFromUnknown;
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordData {
+    ///Hints or auto-play were used during the game. These games are kept off leaderboards
+    #[cfg_attr(
+        feature = "skip_serializing",
+        serde(skip_serializing_if = "core::option::Option::is_none")
+    )]
+    pub assisted: core::option::Option<bool>,
+    ///The player no longer has any moves left
+    pub completed: bool,
+    pub created_at: atrium_api::types::string::Datetime,
+    ///The game's current score
+    pub current_score: i64,
+    ///Active time spent playing in milliseconds. Time with the page hidden is not counted
+    #[cfg_attr(
+        feature = "skip_serializing",
+        serde(skip_serializing_if = "core::option::Option::is_none")
+    )]
+    pub duration_ms: core::option::Option<i64>,
+    ///Seconds for timeAttack games, moves for moveLimit games
+    #[cfg_attr(
+        feature = "skip_serializing",
+        serde(skip_serializing_if = "core::option::Option::is_none")
+    )]
+    pub limit: core::option::Option<i64>,
+    ///The rules the game was played under. Missing means classic
+    #[cfg_attr(
+        feature = "skip_serializing",
+        serde(skip_serializing_if = "core::option::Option::is_none")
+    )]
+    pub mode: core::option::Option<String>,
+    ///How many moves (swipes) were made in the game
+    #[cfg_attr(
+        feature = "skip_serializing",
+        serde(skip_serializing_if = "core::option::Option::is_none")
+    )]
+    pub move_count: core::option::Option<i64>,
+    ///This is the recording of the game. Like chess notation, but for 2048
+    pub seeded_recording: String,
+    ///The sync status of this record with the users AT Protocol repo.
+    pub sync_status: crate::blue::_2048::defs::SyncStatus,
+    ///Active time in milliseconds it took to find the first 2048 block
+    #[cfg_attr(
+        feature = "skip_serializing",
+        serde(skip_serializing_if = "core::option::Option::is_none")
+    )]
+    pub time_to_find_twenty_forty_eight_ms: core::option::Option<i64>,
+    ///The player has found a 2048 tile (they have won)
+    pub won: bool,
+}
+pub type Record = atrium_api::types::Object<RecordData>;
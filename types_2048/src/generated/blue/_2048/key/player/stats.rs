@@ -9,8 +9,3 @@ pub struct RecordData {
     pub key: crate::blue::_2048::key::defs::Key,
 }
 pub type Record = atrium_api::types::Object<RecordData>;
-impl From<atrium_api::types::Unknown> for RecordData {
-    fn from(value: atrium_api::types::Unknown) -> Self {
-        Self::try_from_unknown(value).unwrap()
-    }
-}
@@ -1,8 +1,15 @@
 // @generated - This file is generated by esquema-codegen (forked from atrium-codegen). DO NOT EDIT.
 //!Definitions for the `blue.2048.player` namespace.
+pub mod achievement;
 pub mod profile;
 pub mod stats;
 #[derive(Debug)]
+pub struct Achievement;
+impl atrium_api::types::Collection for Achievement {
+    const NSID: &'static str = "blue.2048.player.achievement";
+    type Record = achievement::Record;
+}
+#[derive(Debug)]
 pub struct Profile;
 impl atrium_api::types::Collection for Profile {
     const NSID: &'static str = "blue.2048.player.profile";
@@ -5,14 +5,15 @@ use atrium_api::types::TryFromUnknown;
 #[serde(rename_all = "camelCase")]
 pub struct RecordData {
     pub created_at: atrium_api::types::string::Datetime,
+    ///Which part of the player's social graph to show on their friends leaderboard.
+    #[cfg_attr(
+        feature = "skip_serializing",
+        serde(skip_serializing_if = "core::option::Option::is_none")
+    )]
+    pub default_social: core::option::Option<String>,
     ///Does not want to show up anywhere. Keep stats to your PDS.
     pub solo_play: bool,
     ///The sync status of this record with the users AT Protocol repo.
     pub sync_status: crate::blue::_2048::defs::SyncStatus,
 }
 pub type Record = atrium_api::types::Object<RecordData>;
-impl From<atrium_api::types::Unknown> for RecordData {
-    fn from(value: atrium_api::types::Unknown) -> Self {
-        Self::try_from_unknown(value).unwrap()
-    }
-}
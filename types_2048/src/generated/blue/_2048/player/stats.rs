@@ -0,0 +1,94 @@
+// @generated - This file is generated by esquema-codegen (forked from atrium-codegen). DO NOT EDIT.
+//!Definitions for the `blue.2048.player.stats` namespace.
+use atrium_api::types::TryFromUnknown;
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordData {
+    ///Average score across all games
+    pub average_score: i64,
+    pub created_at: atrium_api::types::string::Datetime,
+    ///Days in a row, in the player's time zone, with at least one game played as of their last game
+    #[cfg_attr(
+        feature = "skip_serializing",
+        serde(skip_serializing_if = "core::option::Option::is_none")
+    )]
+    pub current_streak: core::option::Option<i64>,
+    ///How many times each direction has been swiped
+    #[cfg_attr(
+        feature = "skip_serializing",
+        serde(skip_serializing_if = "core::option::Option::is_none")
+    )]
+    pub direction_counts: core::option::Option<DirectionCounts>,
+    ///The shortest active time in milliseconds it took to find a 2048 block
+    #[cfg_attr(
+        feature = "skip_serializing",
+        serde(skip_serializing_if = "core::option::Option::is_none")
+    )]
+    pub fastest_twenty_forty_eight_ms: core::option::Option<i64>,
+    ///Total numbers of games the user has played
+    pub games_played: i64,
+    ///The highest number block the player has fround. example 128, 256, etc
+    pub highest_number_block: i64,
+    ///The highest score the user has gotten in a game
+    pub highest_score: i64,
+    ///The smallest number of moves to get the 2048 block
+    pub least_moves_to_find_twenty_forty_eight: i64,
+    ///The most days in a row the player has played at least one game
+    #[cfg_attr(
+        feature = "skip_serializing",
+        serde(skip_serializing_if = "core::option::Option::is_none")
+    )]
+    pub longest_streak: core::option::Option<i64>,
+    ///Stats for games played in the timed and move limited modes. Those games are not counted in the classic stats above
+    #[cfg_attr(
+        feature = "skip_serializing",
+        serde(skip_serializing_if = "core::option::Option::is_none")
+    )]
+    pub mode_stats: core::option::Option<Vec<ModeStats>>,
+    ///The sync status of this record with the users AT Protocol repo.
+    pub sync_status: crate::blue::_2048::defs::SyncStatus,
+    ///Times the 2048 block has been found also count as wins
+    pub times_twenty_forty_eight_been_found: i64,
+    ///Total moves (swipes) across all games
+    #[cfg_attr(
+        feature = "skip_serializing",
+        serde(skip_serializing_if = "core::option::Option::is_none")
+    )]
+    pub total_moves: core::option::Option<i64>,
+    ///Total active time spent playing in milliseconds
+    #[cfg_attr(
+        feature = "skip_serializing",
+        serde(skip_serializing_if = "core::option::Option::is_none")
+    )]
+    pub total_play_time_ms: core::option::Option<i64>,
+    ///Total score across all games
+    pub total_score: i64,
+}
+pub type Record = atrium_api::types::Object<RecordData>;
+///How many moves have been made in each direction
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectionCountsData {
+    pub down: i64,
+    pub left: i64,
+    pub right: i64,
+    pub up: i64,
+}
+pub type DirectionCounts = atrium_api::types::Object<DirectionCountsData>;
+///A player's stats for one game mode and limit
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModeStatsData {
+    ///Average score across all games in this mode
+    pub average_score: i64,
+    ///Total numbers of games the user has played in this mode
+    pub games_played: i64,
+    ///The highest score the user has gotten in this mode
+    pub highest_score: i64,
+    ///Seconds for timeAttack games, moves for moveLimit games
+    pub limit: i64,
+    pub mode: String,
+    ///Total score across all games in this mode
+    pub total_score: i64,
+}
+pub type ModeStats = atrium_api::types::Object<ModeStatsData>;
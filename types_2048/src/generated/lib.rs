@@ -0,0 +1,4 @@
+// @generated - This file is generated by esquema-codegen (forked from atrium-codegen). DO NOT EDIT.
+pub mod record;
+pub mod blue;
+pub mod com;
@@ -9,6 +9,8 @@ pub enum KnownRecord {
     Blue2048KeyGame(Box<crate::blue::_2048::key::game::Record>),
     #[serde(rename = "blue.2048.key.player.stats")]
     Blue2048KeyPlayerStats(Box<crate::blue::_2048::key::player::stats::Record>),
+    #[serde(rename = "blue.2048.player.achievement")]
+    Blue2048PlayerAchievement(Box<crate::blue::_2048::player::achievement::Record>),
     #[serde(rename = "blue.2048.player.profile")]
     Blue2048PlayerProfile(Box<crate::blue::_2048::player::profile::Record>),
     #[serde(rename = "blue.2048.player.stats")]
@@ -48,6 +50,16 @@ impl From<crate::blue::_2048::key::player::stats::RecordData> for KnownRecord {
         KnownRecord::Blue2048KeyPlayerStats(Box::new(record_data.into()))
     }
 }
+impl From<crate::blue::_2048::player::achievement::Record> for KnownRecord {
+    fn from(record: crate::blue::_2048::player::achievement::Record) -> Self {
+        KnownRecord::Blue2048PlayerAchievement(Box::new(record))
+    }
+}
+impl From<crate::blue::_2048::player::achievement::RecordData> for KnownRecord {
+    fn from(record_data: crate::blue::_2048::player::achievement::RecordData) -> Self {
+        KnownRecord::Blue2048PlayerAchievement(Box::new(record_data.into()))
+    }
+}
 impl From<crate::blue::_2048::player::profile::Record> for KnownRecord {
     fn from(record: crate::blue::_2048::player::profile::Record) -> Self {
         KnownRecord::Blue2048PlayerProfile(Box::new(record))
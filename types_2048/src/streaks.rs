@@ -0,0 +1,29 @@
+/// Runs of days in a row with at least one game played
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PlayStreaks {
+    /// The run ending today, or yesterday since there is still time to keep it going
+    pub current: usize,
+    pub longest: usize,
+}
+
+impl PlayStreaks {
+    /// Days are day numbers with a game played on them, oldest first without repeats. What a day
+    /// is is up to the caller, the appview uses UTC days and the client the player's local ones
+    pub fn from_days(days: impl IntoIterator<Item = i64>, today: i64) -> Self {
+        let mut streaks = PlayStreaks::default();
+        let mut run = 0;
+        let mut previous_day = None;
+        for day in days {
+            run = match previous_day {
+                Some(previous_day) if previous_day + 1 == day => run + 1,
+                _ => 1,
+            };
+            streaks.longest = streaks.longest.max(run);
+            previous_day = Some(day);
+        }
+        if previous_day.is_some_and(|last_day| last_day >= today - 1) {
+            streaks.current = run;
+        }
+        streaks
+    }
+}